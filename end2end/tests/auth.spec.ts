@@ -0,0 +1,27 @@
+import { test, expect } from "@playwright/test";
+
+const BASE = "http://localhost:3000";
+
+test("unauthenticated visitors are sent to the login page", async ({ page }) => {
+  await page.goto(`${BASE}/portfolio`);
+
+  await expect(page).toHaveURL(`${BASE}/login`);
+  await expect(page.locator("h1")).toHaveText("Sign in to eZoro");
+});
+
+test("sign up, land on the dashboard, then sign out", async ({ page }) => {
+  const username = `e2e_${Date.now()}`;
+  await page.goto(`${BASE}/login`);
+
+  const signUp = page.locator("form", { hasText: "Create an account" });
+  await signUp.locator('input[name="username"]').fill(username);
+  await signUp.locator('input[name="display_name"]').fill("End To End");
+  await signUp.locator('input[name="password"]').fill("correct horse battery");
+  await signUp.getByRole("button", { name: "Sign up" }).click();
+
+  await expect(page).toHaveURL(`${BASE}/home`);
+  await expect(page.getByText("Welcome End To End !")).toBeVisible();
+
+  await page.getByRole("button", { name: "Sign out" }).click();
+  await expect(page).toHaveURL(`${BASE}/login`);
+});
@@ -0,0 +1,46 @@
+import { test, expect, type Page } from "@playwright/test";
+
+const BASE = "http://localhost:3000";
+
+async function signUp(page: Page, username: string) {
+  await page.goto(`${BASE}/login`);
+  const form = page.locator("form", { hasText: "Create an account" });
+  await form.locator('input[name="username"]').fill(username);
+  await form.locator('input[name="password"]').fill("correct horse battery");
+  await form.getByRole("button", { name: "Sign up" }).click();
+  await expect(page).toHaveURL(`${BASE}/home`);
+}
+
+function tradeRows(page: Page) {
+  return page.locator("table", { hasText: "Trade Date" }).locator("tbody tr");
+}
+
+// Needs at least one asset in the database, e.g. after `resume seed`.
+test("one account cannot see another account's trades", async ({ browser }) => {
+  const stamp = Date.now();
+
+  const alice = await (await browser.newContext()).newPage();
+  await signUp(alice, `alice_${stamp}`);
+  await alice.goto(`${BASE}/portfolio`);
+  await alice.locator('select[name="asset"] option').nth(0).waitFor({ state: "attached" });
+  await alice.locator('input[name="price"]').fill("123.45");
+  await alice.locator('input[name="qty"]').fill("3");
+  await alice.locator('input[name="trade_date"]').fill("2025-01-15T10:00");
+  await alice.getByRole("button", { name: "Add trade" }).click();
+  await expect(alice.getByText("Trade saved.")).toBeVisible();
+  await expect(tradeRows(alice)).toHaveCount(1);
+  await expect(tradeRows(alice).first()).toContainText("123.45");
+
+  const bob = await (await browser.newContext()).newPage();
+  await signUp(bob, `bob_${stamp}`);
+  await bob.goto(`${BASE}/portfolio`);
+  await expect(bob.getByText("No holdings yet.")).toBeVisible();
+  await expect(tradeRows(bob)).toHaveCount(0);
+
+  await bob.goto(`${BASE}/home`);
+  await expect(bob.getByText("No trades yet.")).toBeVisible();
+
+  // Alice still sees her own trade after Bob signed up.
+  await alice.reload();
+  await expect(tradeRows(alice)).toHaveCount(1);
+});
@@ -3,11 +3,19 @@ use crate::frontend::home::Home;
 use crate::frontend::portfolio::Portfolio;
 use crate::frontend::assets::Assets;
 use crate::frontend::wallet::Wallet;
+use crate::frontend::performance::Performance;
+use crate::frontend::asset_detail::AssetDetail;
+use crate::frontend::trade_import::TradeImport;
+use crate::frontend::statement_import::StatementImport;
+use crate::frontend::tax_lots::{SellLotsEditor, TaxLots};
+use crate::frontend::auth::{provide_session, use_session, Login};
+use crate::frontend::portfolios::provide_portfolio_selection;
+use crate::frontend::reporting::provide_reporting_currency;
 use leptos::prelude::*;
 use leptos_meta::{provide_meta_context, MetaTags, Stylesheet, Title};
 use leptos_router::{
-    components::{Route, Router, Routes},
-    StaticSegment,
+    components::{ProtectedRoute, Route, Router, Routes},
+    path, StaticSegment,
 };
 
 pub fn shell(options: LeptosOptions) -> impl IntoView {
@@ -32,6 +40,15 @@ pub fn shell(options: LeptosOptions) -> impl IntoView {
 pub fn App() -> impl IntoView {
     // Provides context that manages stylesheets, titles, meta tags, etc.
     provide_meta_context();
+    // Reporting currency shared by the navbar selector and every page showing totals
+    provide_reporting_currency();
+    // Portfolio shown by the positions, trades and dashboard; switched from the navbar
+    provide_portfolio_selection();
+    // Signed-in user, checked by every protected route below
+    provide_session();
+    let session = use_session();
+    let signed_in = move || session.is_signed_in();
+    let to_login = || "/login";
 
     view! {
         // injects a stylesheet into the document <head>
@@ -47,11 +64,18 @@ pub fn App() -> impl IntoView {
                 <Navbar/>
                 <main class="ml-64 p-6">
                     <Routes fallback=|| "Page not found.".into_view()>
-                        <Route path=StaticSegment("") view=Home/>
-                        <Route path=StaticSegment("home") view=Home/>
-                        <Route path=StaticSegment("portfolio") view=Portfolio/>
-                        <Route path=StaticSegment("search") view=Assets/>
-                        <Route path=StaticSegment("wallet") view=Wallet/>                        
+                        <Route path=StaticSegment("login") view=Login/>
+                        <ProtectedRoute path=StaticSegment("") view=Home condition=signed_in redirect_path=to_login/>
+                        <ProtectedRoute path=StaticSegment("home") view=Home condition=signed_in redirect_path=to_login/>
+                        <ProtectedRoute path=StaticSegment("portfolio") view=Portfolio condition=signed_in redirect_path=to_login/>
+                        <ProtectedRoute path=path!("/portfolio/import") view=TradeImport condition=signed_in redirect_path=to_login/>
+                        <ProtectedRoute path=path!("/portfolio/lots") view=TaxLots condition=signed_in redirect_path=to_login/>
+                        <ProtectedRoute path=path!("/portfolio/lots/:sell") view=SellLotsEditor condition=signed_in redirect_path=to_login/>
+                        <ProtectedRoute path=StaticSegment("performance") view=Performance condition=signed_in redirect_path=to_login/>
+                        <ProtectedRoute path=StaticSegment("search") view=Assets condition=signed_in redirect_path=to_login/>
+                        <ProtectedRoute path=StaticSegment("wallet") view=Wallet condition=signed_in redirect_path=to_login/>
+                        <ProtectedRoute path=path!("/wallet/import") view=StatementImport condition=signed_in redirect_path=to_login/>
+                        <ProtectedRoute path=path!("/asset/:symbol") view=AssetDetail condition=signed_in redirect_path=to_login/>
                     </Routes>
                 </main>
             </div>
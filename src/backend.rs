@@ -1,2 +1,27 @@
+pub mod allocation;
+pub mod auth;
+pub mod benchmark;
+#[cfg(feature = "ssr")]
+pub mod charts;
+#[cfg(feature = "ssr")]
+pub mod config;
+pub mod correlation;
+pub mod currency;
+pub mod dashboard;
 pub mod db;
+pub mod export;
+pub mod fx;
+#[cfg(feature = "ssr")]
+pub mod migrations;
 pub mod model;
+pub mod performance;
+pub mod portfolios;
+pub mod positions;
+pub mod prices;
+pub mod risk;
+#[cfg(feature = "ssr")]
+pub mod seed;
+pub mod settings;
+pub mod statement_import;
+pub mod tax_lots;
+pub mod trade_import;
@@ -0,0 +1,323 @@
+use leptos::prelude::ServerFnError;
+use leptos::server;
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+
+use crate::backend::model::{AssetWithPrice, Category, Decimal, Money};
+use crate::backend::positions::PositionsReport;
+#[cfg(feature = "ssr")]
+use crate::backend::auth::require_user;
+#[cfg(feature = "ssr")]
+use crate::backend::charts::pie_svg;
+#[cfg(feature = "ssr")]
+use crate::backend::db::{load_assets, load_categories};
+#[cfg(feature = "ssr")]
+use crate::backend::positions::positions_report;
+
+/// Risk score bands. The allocation and the badges on the asset pages both use
+/// them, so a score is coloured the way it is grouped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RiskBand {
+    Low,
+    Moderate,
+    Elevated,
+    High,
+}
+
+impl RiskBand {
+    /// Band of a stored score; `None` outside the 1-10 the schema allows.
+    pub fn from_score(score: u8) -> Option<RiskBand> {
+        match score {
+            1 | 2 => Some(RiskBand::Low),
+            3 | 4 => Some(RiskBand::Moderate),
+            5 | 6 => Some(RiskBand::Elevated),
+            7..=10 => Some(RiskBand::High),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RiskBand::Low => "Low (1-2)",
+            RiskBand::Moderate => "Moderate (3-4)",
+            RiskBand::Elevated => "Elevated (5-6)",
+            RiskBand::High => "High (7+)",
+        }
+    }
+
+    pub fn badge_class(self) -> &'static str {
+        match self {
+            RiskBand::Low => "text-green-700 bg-green-100",
+            RiskBand::Moderate => "text-lime-700 bg-lime-100",
+            RiskBand::Elevated => "text-amber-700 bg-amber-100",
+            RiskBand::High => "text-red-700 bg-red-100",
+        }
+    }
+}
+
+/// Badge colours of a risk score; grey when it has no band.
+pub fn risk_badge_class(score: u8) -> &'static str {
+    RiskBand::from_score(score).map_or("text-gray-700 bg-gray-100", RiskBand::badge_class)
+}
+
+/// Market value of one group, in the reporting currency.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllocationSlice {
+    pub label: String,
+    pub value: Money,
+    /// Share of the total market value, 0-100.
+    pub weight_pct: f64,
+}
+
+/// Position market value split three ways. Positions without a price are left out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Allocation {
+    pub total: Money,
+    pub by_category: Vec<AllocationSlice>,
+    pub by_risk: Vec<AllocationSlice>,
+    pub by_currency: Vec<AllocationSlice>,
+}
+
+/// Allocation plus its pie charts, rendered to SVG on the server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllocationCharts {
+    pub allocation: Allocation,
+    pub category_svg: String,
+    pub risk_svg: String,
+    pub currency_svg: String,
+}
+
+fn add_to(slices: &mut Vec<AllocationSlice>, label: String, value: Decimal, ccy: &str) {
+    match slices.iter_mut().find(|s| s.label == label) {
+        Some(slice) => slice.value.amount += value,
+        None => slices.push(AllocationSlice {
+            label,
+            value: Money::new(value, ccy),
+            weight_pct: 0.0,
+        }),
+    }
+}
+
+// Weights against the exact total, then values rounded for display; largest first.
+fn finish(mut slices: Vec<AllocationSlice>, total: Decimal) -> Vec<AllocationSlice> {
+    for slice in slices.iter_mut() {
+        if !total.is_zero() {
+            slice.weight_pct = (slice.value.amount / total * Decimal::ONE_HUNDRED)
+                .to_f64()
+                .unwrap_or_default();
+        }
+        slice.value = slice.value.rounded();
+    }
+    slices.sort_by_key(|s| std::cmp::Reverse(s.value.amount));
+    slices
+}
+
+/// Groups the report's positions by asset category, risk band and currency.
+/// `assets` and `categories` supply each position's category name and risk score.
+pub fn allocate(report: &PositionsReport, assets: &[AssetWithPrice], categories: &[Category]) -> Allocation {
+    let ccy = report.reporting_ccy.as_str();
+    let mut total = Decimal::ZERO;
+    let mut by_category = Vec::new();
+    let mut by_risk = Vec::new();
+    let mut by_currency = Vec::new();
+
+    for position in &report.positions {
+        let Some(value) = position.reporting_market_value() else {
+            continue;
+        };
+        total += value;
+
+        let asset = assets.iter().find(|a| a.id.as_ref() == Some(&position.asset));
+        let category = asset
+            .and_then(|a| categories.iter().find(|c| c.id.as_ref() == Some(&a.category)))
+            .map(|c| c.name.clone())
+            .unwrap_or_else(|| "Other".to_string());
+        let risk = asset
+            .and_then(|a| RiskBand::from_score(a.risk.risk_score))
+            .map(|band| band.label().to_string())
+            .unwrap_or_else(|| "Unrated".to_string());
+
+        add_to(&mut by_category, category, value, ccy);
+        add_to(&mut by_risk, risk, value, ccy);
+        add_to(&mut by_currency, position.ccy.clone(), value, ccy);
+    }
+
+    Allocation {
+        total: Money::new(total, ccy).rounded(),
+        by_category: finish(by_category, total),
+        by_risk: finish(by_risk, total),
+        by_currency: finish(by_currency, total),
+    }
+}
+
+#[cfg(feature = "ssr")]
+fn slices_svg(title: &str, slices: &[AllocationSlice]) -> Result<String, ServerFnError> {
+    if slices.is_empty() {
+        return Ok(String::new());
+    }
+    let values = slices
+        .iter()
+        .map(|s| (s.label.clone(), s.value.amount.to_f32().unwrap_or_default()))
+        .collect();
+    pie_svg(title, values, true)
+}
+
+#[server(GetAllocation, "/api")]
+pub async fn get_allocation() -> Result<AllocationCharts, ServerFnError> {
+    let user = require_user().await?;
+    let report = positions_report(&user).await?;
+    let assets = load_assets().await?;
+    let categories = load_categories().await?;
+    let allocation = allocate(&report, &assets, &categories);
+
+    Ok(AllocationCharts {
+        category_svg: slices_svg("By category", &allocation.by_category)?,
+        risk_svg: slices_svg("By risk", &allocation.by_risk)?,
+        currency_svg: slices_svg("By currency", &allocation.by_currency)?,
+        allocation,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::model::Risk;
+    use crate::backend::positions::Position;
+    use surrealdb::RecordId;
+
+    fn position(symbol: &str, ccy: &str, market_value: Option<i64>, fx_rate: i64) -> Position {
+        Position {
+            asset: RecordId::from(("asset", symbol)),
+            symbol: symbol.to_uppercase(),
+            ccy: ccy.to_string(),
+            precision: 2,
+            fx_rate: Some(Decimal::from(fx_rate)),
+            qty: Decimal::ONE,
+            avg_cost: Decimal::ZERO,
+            cost_basis: Decimal::ZERO,
+            last_price: market_value.map(Decimal::from),
+            market_value: market_value.map(Decimal::from),
+            unrealized_pnl: None,
+            realized_pnl: Decimal::ZERO,
+        }
+    }
+
+    fn asset(symbol: &str, category: &str, risk_score: u8) -> AssetWithPrice {
+        AssetWithPrice {
+            id: Some(RecordId::from(("asset", symbol))),
+            symbol: symbol.to_uppercase(),
+            category: RecordId::from(("category", category)),
+            risk: Risk {
+                id: None,
+                name: String::new(),
+                risk_score,
+            },
+            ccy: String::new(),
+            precision: 2,
+            recent_prices: Vec::new(),
+            last_price: None,
+            price_change_pct: None,
+        }
+    }
+
+    fn category(key: &str, name: &str) -> Category {
+        Category {
+            id: Some(RecordId::from(("category", key))),
+            name: name.to_string(),
+            description: String::new(),
+        }
+    }
+
+    fn slices(slices: &[AllocationSlice]) -> Vec<(&str, Decimal, f64)> {
+        slices
+            .iter()
+            .map(|s| (s.label.as_str(), s.value.amount, s.weight_pct))
+            .collect()
+    }
+
+    #[test]
+    fn scores_band_the_same_for_allocation_and_badges() {
+        let bands: Vec<Option<RiskBand>> = (0..=11).map(RiskBand::from_score).collect();
+        use RiskBand::*;
+        assert_eq!(
+            bands,
+            [
+                None,
+                Some(Low),
+                Some(Low),
+                Some(Moderate),
+                Some(Moderate),
+                Some(Elevated),
+                Some(Elevated),
+                Some(High),
+                Some(High),
+                Some(High),
+                Some(High),
+                None
+            ]
+        );
+        assert_eq!(risk_badge_class(1), Low.badge_class());
+        assert_eq!(risk_badge_class(10), High.badge_class());
+        assert_eq!(risk_badge_class(0), "text-gray-700 bg-gray-100");
+    }
+
+    #[test]
+    fn positions_are_grouped_by_category_band_and_currency() {
+        // In EUR: AAPL 600, MSFT 200, SONY 200. SONY's score is out of range and the
+        // CHF holding has no asset, so both are unrated; the unpriced one is left out.
+        let report = PositionsReport {
+            positions: vec![
+                position("aapl", "USD", Some(300), 2),
+                position("msft", "USD", Some(200), 1),
+                position("sony", "JPY", Some(100), 2),
+                position("unpriced", "USD", None, 1),
+                position("unknown", "CHF", Some(0), 1),
+            ],
+            reporting_ccy: "EUR".to_string(),
+            market_value: Money::new(Decimal::ZERO, "EUR"),
+            unrealized_pnl: Money::new(Decimal::ZERO, "EUR"),
+            realized_pnl: Money::new(Decimal::ZERO, "EUR"),
+            rates: Vec::new(),
+            unconverted: Vec::new(),
+        };
+        let assets = [
+            asset("aapl", "stocks", 4),
+            asset("msft", "stocks", 3),
+            asset("sony", "funds", 0),
+            asset("unpriced", "stocks", 9),
+        ];
+        let categories = [category("stocks", "Stocks"), category("funds", "Funds")];
+
+        let allocation = allocate(&report, &assets, &categories);
+        assert_eq!(allocation.total, Money::new(Decimal::from(1000), "EUR"));
+        assert_eq!(
+            slices(&allocation.by_category),
+            [("Stocks", Decimal::from(800), 80.0), ("Funds", Decimal::from(200), 20.0), ("Other", Decimal::ZERO, 0.0)]
+        );
+        assert_eq!(
+            slices(&allocation.by_risk),
+            [("Moderate (3-4)", Decimal::from(800), 80.0), ("Unrated", Decimal::from(200), 20.0)]
+        );
+        assert_eq!(
+            slices(&allocation.by_currency),
+            [("USD", Decimal::from(800), 80.0), ("JPY", Decimal::from(200), 20.0), ("CHF", Decimal::ZERO, 0.0)]
+        );
+    }
+
+    #[test]
+    fn nothing_priced_gives_empty_groups_and_no_weights() {
+        let report = PositionsReport {
+            positions: vec![position("aapl", "USD", None, 1)],
+            reporting_ccy: "EUR".to_string(),
+            market_value: Money::new(Decimal::ZERO, "EUR"),
+            unrealized_pnl: Money::new(Decimal::ZERO, "EUR"),
+            realized_pnl: Money::new(Decimal::ZERO, "EUR"),
+            rates: Vec::new(),
+            unconverted: Vec::new(),
+        };
+        let allocation = allocate(&report, &[], &[]);
+        assert!(allocation.total.amount.is_zero());
+        assert!(allocation.by_category.is_empty() && allocation.by_risk.is_empty() && allocation.by_currency.is_empty());
+    }
+}
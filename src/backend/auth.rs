@@ -0,0 +1,309 @@
+use leptos::prelude::ServerFnError;
+use leptos::server;
+use serde::{Deserialize, Serialize};
+use surrealdb::RecordId;
+
+#[cfg(feature = "ssr")]
+use crate::backend::config::SessionConfig;
+#[cfg(feature = "ssr")]
+use crate::backend::db::get_db;
+#[cfg(feature = "ssr")]
+use crate::backend::portfolios::{ensure_portfolio, DEFAULT_PORTFOLIO};
+#[cfg(feature = "ssr")]
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+#[cfg(feature = "ssr")]
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+#[cfg(feature = "ssr")]
+use argon2::Argon2;
+#[cfg(feature = "ssr")]
+use axum::http::{header, HeaderMap, HeaderValue};
+#[cfg(feature = "ssr")]
+use std::sync::OnceLock;
+
+/// Cookie carrying the session token.
+pub const SESSION_COOKIE: &str = "resume_session";
+
+/// Message of the error every data server function returns without a live session.
+pub const NOT_SIGNED_IN: &str = "Not signed in";
+
+#[cfg(feature = "ssr")]
+const SESSION_DAYS: i64 = 30;
+
+#[cfg(feature = "ssr")]
+const MIN_PASSWORD_LEN: usize = 8;
+
+// Hash of a password no account has, checked when the username is unknown so that
+// answer takes as long as a wrong password.
+#[cfg(feature = "ssr")]
+const DUMMY_HASH: &str =
+    "$argon2id$v=19$m=19456,t=2,p=1$OFRTU3PtUDmQqcvYL8Mtcg$oKc2ufR+6XWxsmawHwXKoDra0/CDXoLNkwfYpDtKzo0";
+
+#[cfg(feature = "ssr")]
+static SESSION_CONFIG: OnceLock<SessionConfig> = OnceLock::new();
+
+/// Sets how session cookies are issued; call once at startup.
+#[cfg(feature = "ssr")]
+pub fn init_sessions(config: &SessionConfig) {
+    SESSION_CONFIG.set(config.clone()).expect("Failed to set session config");
+}
+
+/// Signed-in account, without its password hash.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct User {
+    pub id: RecordId,
+    pub username: String,
+    pub display_name: String,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Serialize)]
+struct NewUser {
+    username: String,
+    display_name: String,
+    password_hash: String,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Deserialize)]
+struct Credentials {
+    id: RecordId,
+    username: String,
+    display_name: String,
+    password_hash: String,
+}
+
+#[cfg(feature = "ssr")]
+pub(crate) fn hash_password(password: &str) -> Result<String, ServerFnError> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| ServerFnError::new(format!("Cannot hash password: {}", e)))
+}
+
+#[cfg(feature = "ssr")]
+fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .map(|parsed| Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok())
+        .unwrap_or(false)
+}
+
+// 256 random bits, hex encoded.
+#[cfg(feature = "ssr")]
+fn new_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(feature = "ssr")]
+fn cookie_token(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE)
+        .map(|(_, token)| token.to_string())
+        .filter(|token| !token.is_empty())
+}
+
+#[cfg(feature = "ssr")]
+fn session_cookie(token: &str, max_age_secs: i64, secure: bool) -> String {
+    format!(
+        "{}={}; Path=/; HttpOnly; SameSite=Lax; Max-Age={}{}",
+        SESSION_COOKIE,
+        token,
+        max_age_secs,
+        if secure { "; Secure" } else { "" }
+    )
+}
+
+#[cfg(feature = "ssr")]
+fn set_session_cookie(token: &str, max_age_secs: i64) -> Result<(), ServerFnError> {
+    let secure = SESSION_CONFIG.get().is_some_and(|config| config.secure_cookie);
+    let cookie = session_cookie(token, max_age_secs, secure);
+    let response = leptos::prelude::expect_context::<leptos_axum::ResponseOptions>();
+    response.append_header(
+        header::SET_COOKIE,
+        HeaderValue::from_str(&cookie).map_err(|e| ServerFnError::new(e.to_string()))?,
+    );
+    Ok(())
+}
+
+#[cfg(feature = "ssr")]
+async fn start_session(user: &RecordId) -> Result<(), ServerFnError> {
+    let token = new_token();
+    get_db()
+        .query(
+            "CREATE session CONTENT {
+                token: $session_token,
+                user: $user,
+                expires_at: time::now() + <duration> $ttl
+            }",
+        )
+        .bind(("session_token", token.clone()))
+        .bind(("user", user.clone()))
+        .bind(("ttl", format!("{}d", SESSION_DAYS)))
+        .await?
+        .check()?;
+
+    set_session_cookie(&token, SESSION_DAYS * 24 * 60 * 60)
+}
+
+/// The user behind the request's session cookie, `None` without a live session.
+#[cfg(feature = "ssr")]
+pub async fn session_user() -> Result<Option<User>, ServerFnError> {
+    let headers: HeaderMap = leptos_axum::extract().await?;
+    user_from_headers(&headers).await
+}
+
+/// Same as [`session_user`] for plain axum handlers, which have the headers at hand.
+#[cfg(feature = "ssr")]
+pub async fn user_from_headers(headers: &HeaderMap) -> Result<Option<User>, ServerFnError> {
+    let Some(token) = cookie_token(headers) else {
+        return Ok(None);
+    };
+
+    let mut response = get_db()
+        .query(
+            "SELECT user.id AS id, user.username AS username, user.display_name AS display_name
+             FROM session WHERE token = $session_token AND expires_at > time::now() LIMIT 1",
+        )
+        .bind(("session_token", token))
+        .await?;
+    let users: Vec<User> = response.take(0)?;
+
+    Ok(users.into_iter().next())
+}
+
+/// Guard called first in every server function that reads or writes user data.
+#[cfg(feature = "ssr")]
+pub async fn require_user() -> Result<User, ServerFnError> {
+    session_user()
+        .await?
+        .ok_or_else(|| ServerFnError::new(NOT_SIGNED_IN))
+}
+
+#[server(CurrentUser, "/api")]
+pub async fn current_user() -> Result<Option<User>, ServerFnError> {
+    session_user().await
+}
+
+/// Creates an account with an empty default portfolio and signs it in. Usernames
+/// are case-insensitive.
+#[server(SignUp, "/api")]
+pub async fn sign_up(
+    username: String,
+    display_name: String,
+    password: String,
+) -> Result<User, ServerFnError> {
+    let username = username.trim().to_ascii_lowercase();
+    if username.len() < 3
+        || username.len() > 32
+        || !username
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    {
+        return Err(ServerFnError::new(
+            "Username must be 3 to 32 letters, digits, '.', '-' or '_'",
+        ));
+    }
+    if password.chars().count() < MIN_PASSWORD_LEN {
+        return Err(ServerFnError::new(format!(
+            "Password must be at least {} characters",
+            MIN_PASSWORD_LEN
+        )));
+    }
+    let display_name = match display_name.trim() {
+        "" => username.clone(),
+        name => name.to_string(),
+    };
+
+    let db = get_db();
+    let mut response = db
+        .query("SELECT VALUE id FROM user WHERE username = $username")
+        .bind(("username", username.clone()))
+        .await?;
+    let existing: Vec<RecordId> = response.take(0)?;
+    if !existing.is_empty() {
+        return Err(ServerFnError::new(format!("Username '{}' is already taken", username)));
+    }
+
+    let created: Option<User> = db
+        .create("user")
+        .content(NewUser {
+            username,
+            display_name,
+            password_hash: hash_password(&password)?,
+        })
+        .await?;
+    let user = created.ok_or_else(|| ServerFnError::new("Account was not created"))?;
+    ensure_portfolio(db, &user.id, DEFAULT_PORTFOLIO).await?;
+
+    start_session(&user.id).await?;
+    leptos_axum::redirect("/home");
+    Ok(user)
+}
+
+#[server(SignIn, "/api")]
+pub async fn sign_in(username: String, password: String) -> Result<User, ServerFnError> {
+    let mut response = get_db()
+        .query("SELECT * FROM user WHERE username = $username LIMIT 1")
+        .bind(("username", username.trim().to_ascii_lowercase()))
+        .await?;
+    let found: Vec<Credentials> = response.take(0)?;
+
+    // Same answer, after the same amount of hashing, for an unknown user and a wrong password.
+    let found = found.into_iter().next();
+    let hash = found.as_ref().map_or(DUMMY_HASH, |c| c.password_hash.as_str());
+    let valid = verify_password(&password, hash);
+    let credentials = found
+        .filter(|_| valid)
+        .ok_or_else(|| ServerFnError::new("Invalid username or password"))?;
+
+    start_session(&credentials.id).await?;
+    leptos_axum::redirect("/home");
+    Ok(User {
+        id: credentials.id,
+        username: credentials.username,
+        display_name: credentials.display_name,
+    })
+}
+
+#[server(SignOut, "/api")]
+pub async fn sign_out() -> Result<(), ServerFnError> {
+    let headers: HeaderMap = leptos_axum::extract().await?;
+    if let Some(token) = cookie_token(&headers) {
+        get_db()
+            .query("DELETE session WHERE token = $session_token")
+            .bind(("session_token", token))
+            .await?
+            .check()?;
+    }
+
+    set_session_cookie("", 0)?;
+    leptos_axum::redirect("/login");
+    Ok(())
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dummy_hash_is_a_real_argon2_hash() {
+        assert!(PasswordHash::new(DUMMY_HASH).is_ok());
+        assert!(!verify_password("correct horse battery", DUMMY_HASH));
+    }
+
+    #[test]
+    fn session_cookie_is_secure_only_when_configured() {
+        assert_eq!(
+            session_cookie("abc", 60, false),
+            "resume_session=abc; Path=/; HttpOnly; SameSite=Lax; Max-Age=60"
+        );
+        assert!(session_cookie("abc", 60, true).ends_with("; Secure"));
+    }
+}
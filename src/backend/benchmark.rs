@@ -0,0 +1,325 @@
+use leptos::prelude::ServerFnError;
+use leptos::server;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+use crate::backend::auth::require_user;
+#[cfg(feature = "ssr")]
+use crate::backend::charts::{line_svg, sparse_labels};
+#[cfg(feature = "ssr")]
+use crate::backend::db::get_db;
+#[cfg(feature = "ssr")]
+use crate::backend::fx::{load_fx_table, FxError, FxTable};
+#[cfg(feature = "ssr")]
+use crate::backend::model::Decimal;
+#[cfg(feature = "ssr")]
+use crate::backend::performance::{
+    compound, covariance, end_of_day, mean, parse_range, pct, std_dev, value_series, DAYS_PER_YEAR,
+};
+#[cfg(feature = "ssr")]
+use chrono::{Duration, NaiveDate};
+#[cfg(feature = "ssr")]
+use rust_decimal::prelude::ToPrimitive;
+#[cfg(feature = "ssr")]
+use surrealdb::{Datetime, RecordId};
+
+/// Category of the assets offered first as benchmarks.
+pub const INDEX_CATEGORY: &str = "indice";
+
+/// The selected portfolio measured against one asset over a date range. Returns
+/// are daily over calendar days, both in the reporting currency.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkReport {
+    pub benchmark: String,
+    pub from: String,
+    pub to: String,
+    pub days: usize,
+    /// Returns over the range, in percent.
+    pub portfolio_pct: Option<f64>,
+    pub benchmark_pct: Option<f64>,
+    /// Portfolio return less benchmark return, in percentage points.
+    pub excess_pct: Option<f64>,
+    /// Yearly standard deviation of the daily return differences, in percent.
+    pub tracking_error_pct: Option<f64>,
+    /// How much the portfolio moves for each move of the benchmark.
+    pub beta: Option<f64>,
+    /// Yearly mean return difference per unit of tracking error.
+    pub information_ratio: Option<f64>,
+    /// Both cumulative returns on one chart; empty when there is nothing to show.
+    pub svg: String,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Deserialize)]
+struct BenchmarkAsset {
+    id: RecordId,
+    symbol: String,
+    ccy: String,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Deserialize)]
+struct PricePoint {
+    price: Decimal,
+    price_date: Datetime,
+}
+
+// The asset called `symbol`, or the first index when blank.
+#[cfg(feature = "ssr")]
+async fn benchmark_asset(symbol: &str) -> Result<BenchmarkAsset, ServerFnError> {
+    let mut response = get_db()
+        .query(
+            "IF $symbol = '' {
+                SELECT id, symbol, ccy FROM asset WHERE category = $index ORDER BY symbol LIMIT 1
+            } ELSE {
+                SELECT id, symbol, ccy FROM asset WHERE symbol = $symbol LIMIT 1
+            }",
+        )
+        .bind(("symbol", symbol.trim().to_string()))
+        .bind(("index", RecordId::from_table_key("category", INDEX_CATEGORY)))
+        .await?;
+    let found: Vec<BenchmarkAsset> = response.take(0)?;
+    found.into_iter().next().ok_or_else(|| match symbol.trim() {
+        "" => ServerFnError::new("Choose an asset to compare with"),
+        symbol => ServerFnError::new(format!("Unknown asset '{}'", symbol)),
+    })
+}
+
+/// Daily returns of `asset` over `days`, in `ccy`. Days without a new price return
+/// zero, as do days before its first price.
+#[cfg(feature = "ssr")]
+async fn benchmark_returns(asset: &BenchmarkAsset, days: &[NaiveDate], ccy: &str) -> Result<Vec<f64>, ServerFnError> {
+    let Some(last) = days.last() else {
+        return Ok(Vec::new());
+    };
+    let mut response = get_db()
+        .query("SELECT price, price_date FROM price WHERE asset = $asset AND price_date <= $until ORDER BY price_date ASC")
+        .bind(("asset", asset.id.clone()))
+        .bind(("until", end_of_day(*last)))
+        .await?;
+    let prices: Vec<PricePoint> = response.take(0)?;
+    let fx = load_fx_table().await?;
+    price_returns(&prices, &asset.ccy, days, ccy, &fx).map_err(|e| ServerFnError::new(e.to_string()))
+}
+
+/// Daily returns over `days` of `prices` (oldest first, in `asset_ccy`) converted into
+/// `ccy`. Each day is valued at the last price up to its end.
+#[cfg(feature = "ssr")]
+fn price_returns(
+    prices: &[PricePoint],
+    asset_ccy: &str,
+    days: &[NaiveDate],
+    ccy: &str,
+    fx: &FxTable,
+) -> Result<Vec<f64>, FxError> {
+    let mut next = 0;
+    let mut price: Option<Decimal> = None;
+    let mut value_at = |day: NaiveDate| -> Result<Option<Decimal>, FxError> {
+        let close = end_of_day(day);
+        while let Some(point) = prices.get(next).filter(|p| p.price_date <= close) {
+            price = Some(point.price);
+            next += 1;
+        }
+        let Some(price) = price else {
+            return Ok(None);
+        };
+        Ok(Some(price * fx.quote(asset_ccy, ccy, &close)?.rate))
+    };
+
+    let mut previous = match days.first() {
+        Some(first) => value_at(*first - Duration::days(1))?,
+        None => None,
+    };
+    let mut returns = Vec::with_capacity(days.len());
+    for day in days {
+        let value = value_at(*day)?;
+        let daily = match (previous, value) {
+            (Some(before), Some(after)) if before > Decimal::ZERO => {
+                (after / before - Decimal::ONE).to_f64().unwrap_or_default()
+            }
+            _ => 0.0,
+        };
+        returns.push(daily);
+        previous = value;
+    }
+    Ok(returns)
+}
+
+/// Tracking error, beta and information ratio of daily `portfolio` returns against
+/// `benchmark` returns of the same days.
+#[cfg(feature = "ssr")]
+#[derive(Debug)]
+struct Relative {
+    tracking_error: Option<f64>,
+    beta: Option<f64>,
+    information_ratio: Option<f64>,
+}
+
+#[cfg(feature = "ssr")]
+fn relative(portfolio: &[f64], benchmark: &[f64]) -> Relative {
+    let active: Vec<f64> = portfolio.iter().zip(benchmark).map(|(p, b)| p - b).collect();
+    let tracking_error = std_dev(&active).map(|sd| sd * DAYS_PER_YEAR.sqrt());
+    let beta = match (covariance(portfolio, benchmark), covariance(benchmark, benchmark)) {
+        (Some(cov), Some(var)) if var > 0.0 => Some(cov / var),
+        _ => None,
+    };
+    let information_ratio = match (mean(&active), tracking_error) {
+        (Some(mean), Some(te)) if te > 0.0 => Some(mean * DAYS_PER_YEAR / te),
+        _ => None,
+    };
+    Relative {
+        tracking_error,
+        beta,
+        information_ratio,
+    }
+}
+
+/// Compares the selected portfolio with `benchmark` (a symbol; blank for the first
+/// index) from `from` to `to`, as on the Performance page.
+#[server(GetBenchmarkComparison, "/api")]
+pub async fn get_benchmark_comparison(
+    from: String,
+    to: String,
+    benchmark: String,
+) -> Result<BenchmarkReport, ServerFnError> {
+    let user = require_user().await?;
+    let (from, to) = parse_range(&from, &to)?;
+    let asset = benchmark_asset(&benchmark).await?;
+    let series = value_series(&user, from, to).await?;
+    let portfolio = series.daily_returns();
+    let benchmark = benchmark_returns(&asset, &series.days, &series.ccy).await?;
+
+    let Relative {
+        tracking_error,
+        beta,
+        information_ratio,
+    } = relative(&portfolio, &benchmark);
+    let (portfolio_total, benchmark_total) = if series.days.is_empty() {
+        (None, None)
+    } else {
+        (Some(compound(&portfolio)), Some(compound(&benchmark)))
+    };
+
+    let svg = if series.days.is_empty() {
+        String::new()
+    } else {
+        let cumulative = |returns: &[f64]| {
+            let mut growth = 1.0;
+            returns
+                .iter()
+                .map(|r| {
+                    growth *= 1.0 + r;
+                    ((growth - 1.0) * 100.0) as f32
+                })
+                .collect::<Vec<f32>>()
+        };
+        let labels = series.days.iter().map(|d| d.format("%Y-%m-%d").to_string()).collect();
+        line_svg(
+            &format!("Cumulative return (%) vs {}", asset.symbol),
+            sparse_labels(labels, 8),
+            vec![
+                ("Portfolio".to_string(), cumulative(&portfolio)),
+                (asset.symbol.clone(), cumulative(&benchmark)),
+            ],
+        )?
+    };
+
+    let day = |day: Option<&NaiveDate>| day.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default();
+    Ok(BenchmarkReport {
+        benchmark: asset.symbol,
+        from: day(series.days.first()),
+        to: day(series.days.last()),
+        days: series.days.len(),
+        portfolio_pct: portfolio_total.and_then(pct),
+        benchmark_pct: benchmark_total.and_then(pct),
+        excess_pct: portfolio_total.zip(benchmark_total).and_then(|(p, b)| pct(p - b)),
+        tracking_error_pct: tracking_error.and_then(pct),
+        beta,
+        information_ratio,
+        svg,
+    })
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+    use crate::backend::model::FxRate;
+    use std::str::FromStr;
+
+    fn day(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn noon(value: &str) -> Datetime {
+        Datetime::from(day(value).and_hms_opt(12, 0, 0).unwrap().and_utc())
+    }
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("a figure");
+        assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn an_identical_benchmark_has_beta_one_and_no_tracking_error() {
+        let returns = [0.01, -0.02, 0.015, 0.0, 0.03];
+        let relative = relative(&returns, &returns);
+        assert_close(relative.beta, 1.0);
+        assert_close(relative.tracking_error, 0.0);
+        assert_eq!(relative.information_ratio, None);
+    }
+
+    #[test]
+    fn beta_tracking_error_and_information_ratio_of_known_returns() {
+        // Covariance 0.07 / 6000 over benchmark variance 0.1 / 6000: beta 0.7. The
+        // differences 1, 0, -1, 1% have mean 0.25% and deviation √(2.75 / 3)%, so the
+        // tracking error is that times √365 and the ratio 0.25% × 365 over it.
+        let portfolio = [0.02, -0.01, 0.01, 0.01];
+        let benchmark = [0.01, -0.01, 0.02, 0.0];
+        let relative = relative(&portfolio, &benchmark);
+        assert_close(relative.beta, 0.7);
+        assert_close(relative.tracking_error, (2.75e-4_f64 / 3.0).sqrt() * 365f64.sqrt());
+        assert_close(relative.information_ratio, 4.988_623_420_981_346);
+
+        // Twice the benchmark's moves: beta 2.
+        let doubled: Vec<f64> = benchmark.iter().map(|r| r * 2.0).collect();
+        assert_close(super::relative(&doubled, &benchmark).beta, 2.0);
+    }
+
+    #[test]
+    fn benchmark_returns_carry_the_last_price_and_convert_each_day() {
+        let prices = [
+            PricePoint {
+                price: Decimal::from(100),
+                price_date: noon("2024-03-02"),
+            },
+            PricePoint {
+                price: Decimal::from(110),
+                price_date: noon("2024-03-04"),
+            },
+        ];
+        let rate = |value: &str, date: &str| FxRate {
+            id: None,
+            base: "USD".to_string(),
+            quote: "EUR".to_string(),
+            rate: Decimal::from_str(value).unwrap(),
+            rate_date: noon(date),
+        };
+        let fx = FxTable::new(vec![rate("1", "2024-01-01"), rate("1.1", "2024-03-06")]);
+        let days: Vec<NaiveDate> = ["2024-03-01", "2024-03-02", "2024-03-03", "2024-03-04", "2024-03-05", "2024-03-06"]
+            .into_iter()
+            .map(day)
+            .collect();
+
+        // Nothing before the first price, zero on the day it appears and while it is
+        // unchanged, then +10% on the new price and +10% again on the new rate.
+        let returns = price_returns(&prices, "USD", &days, "EUR", &fx).unwrap();
+        assert_eq!(returns.len(), days.len());
+        for (actual, expected) in returns.iter().zip([0.0, 0.0, 0.0, 0.1, 0.0, 0.1]) {
+            assert!((actual - expected).abs() < 1e-12, "{:?}", returns);
+        }
+
+        // Without a rate into the reporting currency there is nothing to compare.
+        assert!(price_returns(&prices, "USD", &days, "CHF", &fx).is_err());
+        assert!(price_returns(&prices, "USD", &[], "CHF", &fx).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,108 @@
+use charts_rs::{CandlestickChart, HeatmapChart, LineChart, PieChart, Series};
+use leptos::prelude::ServerFnError;
+
+pub const CHART_WIDTH: f32 = 800.0;
+pub const CHART_HEIGHT: f32 = 360.0;
+pub const PIE_SIZE: f32 = 320.0;
+
+/// One bar of a candlestick chart.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Candle {
+    pub open: f32,
+    pub close: f32,
+    pub low: f32,
+    pub high: f32,
+}
+
+/// Keeps about `max` evenly spaced labels and blanks the others, so long
+/// ranges do not print a date under every point.
+pub fn sparse_labels(labels: Vec<String>, max: usize) -> Vec<String> {
+    let step = labels.len().div_ceil(max.max(1)).max(1);
+    labels
+        .into_iter()
+        .enumerate()
+        .map(|(index, label)| if index % step == 0 { label } else { String::new() })
+        .collect()
+}
+
+fn render_error(err: impl std::fmt::Display) -> ServerFnError {
+    ServerFnError::new(format!("Cannot render chart: {}", err))
+}
+
+/// Line chart as an SVG document, one line per named series.
+pub fn line_svg(
+    title: &str,
+    labels: Vec<String>,
+    series: Vec<(String, Vec<f32>)>,
+) -> Result<String, ServerFnError> {
+    let show_legend = series.len() > 1;
+    let series = series
+        .into_iter()
+        .map(|(name, data)| Series::new(name, data))
+        .collect();
+    let mut chart = LineChart::new(series, labels);
+    chart.title_text = title.to_string();
+    chart.width = CHART_WIDTH;
+    chart.height = CHART_HEIGHT;
+    chart.legend_show = Some(show_legend);
+
+    chart.svg().map_err(render_error)
+}
+
+/// Candlestick chart as an SVG document.
+pub fn candlestick_svg(title: &str, labels: Vec<String>, candles: &[Candle]) -> Result<String, ServerFnError> {
+    // charts-rs takes the candles flattened as open, close, low, high.
+    let data = candles
+        .iter()
+        .flat_map(|c| [c.open, c.close, c.low, c.high])
+        .collect();
+    let mut chart = CandlestickChart::new(vec![Series::new(title.to_string(), data)], labels);
+    chart.title_text = title.to_string();
+    chart.width = CHART_WIDTH;
+    chart.height = CHART_HEIGHT;
+    chart.legend_show = Some(false);
+
+    chart.svg().map_err(render_error)
+}
+
+/// Pie chart as an SVG document, or a donut when `donut` is set. Slices that
+/// are not positive cannot be drawn and are left out.
+pub fn pie_svg(title: &str, slices: Vec<(String, f32)>, donut: bool) -> Result<String, ServerFnError> {
+    let series = slices
+        .into_iter()
+        .filter(|(_, value)| *value > 0.0)
+        .map(|(name, value)| Series::new(name, vec![value]))
+        .collect();
+    let mut chart = PieChart::new(series);
+    chart.title_text = title.to_string();
+    chart.width = PIE_SIZE;
+    chart.height = PIE_SIZE;
+    chart.rose_type = Some(false);
+    chart.radius = PIE_SIZE * 0.3;
+    chart.inner_radius = if donut { PIE_SIZE * 0.15 } else { 0.0 };
+
+    chart.svg().map_err(render_error)
+}
+
+/// Heatmap of a square matrix of values between -1 and 1, such as correlations,
+/// shading from blue at -1 to red at 1. `matrix[row][column]` belongs to
+/// `labels[row]` and `labels[column]`.
+pub fn correlation_heatmap_svg(title: &str, labels: Vec<String>, matrix: &[Vec<f32>]) -> Result<String, ServerFnError> {
+    let size = labels.len();
+    // charts-rs numbers the cells row by row, the first row at the bottom.
+    let cells = matrix
+        .iter()
+        .enumerate()
+        .flat_map(|(row, values)| values.iter().enumerate().map(move |(column, value)| (row * size + column, *value)))
+        .collect();
+    let mut chart = HeatmapChart::new(cells, labels.clone(), labels);
+    chart.title_text = title.to_string();
+    chart.width = CHART_WIDTH;
+    chart.height = CHART_WIDTH.min(CHART_HEIGHT + 40.0 * size as f32);
+    chart.series.min = -1.0;
+    chart.series.max = 1.0;
+    chart.series.min_color = (59, 130, 246).into();
+    chart.series.max_color = (220, 38, 38).into();
+
+    chart.svg().map_err(render_error)
+}
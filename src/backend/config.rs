@@ -0,0 +1,431 @@
+use serde::Deserialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Config file read when `RESUME_CONFIG` is not set. It is optional: if it does
+/// not exist every setting has to come from the environment.
+pub const DEFAULT_CONFIG_FILE: &str = "resume.toml";
+
+#[derive(Debug, Clone)]
+pub struct AppConfig {
+    pub database: DbConfig,
+    pub session: SessionConfig,
+}
+
+/// How session cookies are issued.
+#[derive(Debug, Clone, Default)]
+pub struct SessionConfig {
+    /// Mark the cookie `Secure`, so browsers only send it over HTTPS. Turn it on
+    /// whenever the site is served over HTTPS.
+    pub secure_cookie: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct DbConfig {
+    pub engine: DbEngine,
+    pub namespace: String,
+    pub database: String,
+}
+
+/// Where the data lives. `Memory` and `File` run SurrealDB inside the server
+/// process and need the `embedded` cargo feature.
+#[derive(Debug, Clone)]
+pub enum DbEngine {
+    Remote { endpoint: String, auth: DbAuth },
+    Memory,
+    File { path: PathBuf },
+}
+
+impl DbEngine {
+    /// Address understood by `surrealdb::engine::any::connect`.
+    pub fn address(&self) -> String {
+        match self {
+            DbEngine::Remote { endpoint, .. } if endpoint.contains("://") => endpoint.clone(),
+            DbEngine::Remote { endpoint, .. } => format!("ws://{}", endpoint),
+            DbEngine::Memory => "mem://".to_string(),
+            DbEngine::File { path } => format!("surrealkv://{}", path.display()),
+        }
+    }
+}
+
+/// How the server authenticates against SurrealDB.
+#[derive(Debug, Clone)]
+pub enum DbAuth {
+    Root {
+        username: String,
+        password: String,
+    },
+    Namespace {
+        username: String,
+        password: String,
+    },
+    Database {
+        username: String,
+        password: String,
+    },
+    Record {
+        access: String,
+        username: String,
+        password: String,
+    },
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(PathBuf, std::io::Error),
+    Parse(PathBuf, String),
+    InvalidEngine(String),
+    EmbeddedNotBuilt(String),
+    InvalidAuthMode(String),
+    InvalidFlag(&'static str, String),
+    Missing(Vec<&'static str>),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read(path, err) => {
+                write!(f, "cannot read config file {}: {}", path.display(), err)
+            }
+            ConfigError::Parse(path, err) => {
+                write!(f, "cannot parse config file {}: {}", path.display(), err)
+            }
+            ConfigError::InvalidEngine(engine) => write!(
+                f,
+                "unknown database engine '{}' (expected remote, memory or file)",
+                engine
+            ),
+            ConfigError::EmbeddedNotBuilt(engine) => write!(
+                f,
+                "database engine '{}' needs a server built with the embedded feature",
+                engine
+            ),
+            ConfigError::InvalidAuthMode(mode) => write!(
+                f,
+                "unknown database auth mode '{}' (expected root, namespace, database or record)",
+                mode
+            ),
+            ConfigError::InvalidFlag(key, value) => {
+                write!(f, "{} must be true or false, not '{}'", key, value)
+            }
+            ConfigError::Missing(keys) => {
+                write!(f, "missing configuration values: {}", keys.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+// One layer of settings. The file and the environment each produce one and
+// the environment wins wherever it sets a value.
+#[derive(Debug, Default, Deserialize)]
+struct RawConfig {
+    #[serde(default)]
+    database: RawDbConfig,
+    #[serde(default)]
+    session: RawSessionConfig,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawDbConfig {
+    engine: Option<String>,
+    path: Option<String>,
+    endpoint: Option<String>,
+    namespace: Option<String>,
+    database: Option<String>,
+    #[serde(default)]
+    auth: RawDbAuth,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawSessionConfig {
+    secure_cookie: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawDbAuth {
+    mode: Option<String>,
+    username: Option<String>,
+    password: Option<String>,
+    access: Option<String>,
+}
+
+impl RawConfig {
+    fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| ConfigError::Read(path.to_path_buf(), err))?;
+        Self::from_toml(path, &text)
+    }
+
+    fn from_toml(path: &Path, text: &str) -> Result<Self, ConfigError> {
+        toml::from_str(text).map_err(|err| ConfigError::Parse(path.to_path_buf(), err.to_string()))
+    }
+
+    fn from_env() -> Result<Self, ConfigError> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    // The environment layer, with `lookup` reading one variable. Empty values count as unset.
+    fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, ConfigError> {
+        let var = |name: &str| lookup(name).filter(|value| !value.is_empty());
+        let secure_cookie = match var("RESUME_SECURE_COOKIE") {
+            Some(value) => Some(parse_flag(&value).ok_or(ConfigError::InvalidFlag(
+                "session.secure_cookie (RESUME_SECURE_COOKIE)",
+                value,
+            ))?),
+            None => None,
+        };
+        Ok(RawConfig {
+            database: RawDbConfig {
+                engine: var("RESUME_DB_ENGINE"),
+                path: var("RESUME_DB_PATH"),
+                endpoint: var("RESUME_DB_ENDPOINT"),
+                namespace: var("RESUME_DB_NAMESPACE"),
+                database: var("RESUME_DB_DATABASE"),
+                auth: RawDbAuth {
+                    mode: var("RESUME_DB_AUTH"),
+                    username: var("RESUME_DB_USERNAME"),
+                    password: var("RESUME_DB_PASSWORD"),
+                    access: var("RESUME_DB_ACCESS"),
+                },
+            },
+            session: RawSessionConfig { secure_cookie },
+        })
+    }
+
+    fn merge(self, over: RawConfig) -> Self {
+        let db = self.database;
+        let top = over.database;
+        RawConfig {
+            database: RawDbConfig {
+                engine: top.engine.or(db.engine),
+                path: top.path.or(db.path),
+                endpoint: top.endpoint.or(db.endpoint),
+                namespace: top.namespace.or(db.namespace),
+                database: top.database.or(db.database),
+                auth: RawDbAuth {
+                    mode: top.auth.mode.or(db.auth.mode),
+                    username: top.auth.username.or(db.auth.username),
+                    password: top.auth.password.or(db.auth.password),
+                    access: top.auth.access.or(db.auth.access),
+                },
+            },
+            session: RawSessionConfig {
+                secure_cookie: over.session.secure_cookie.or(self.session.secure_cookie),
+            },
+        }
+    }
+
+    fn resolve(self) -> Result<AppConfig, ConfigError> {
+        let session = SessionConfig {
+            secure_cookie: self.session.secure_cookie.unwrap_or(false),
+        };
+        let db = self.database;
+        let mut missing = Vec::new();
+        let mut require = |value: Option<String>, key: &'static str| {
+            if value.is_none() {
+                missing.push(key);
+            }
+            value.unwrap_or_default()
+        };
+
+        let namespace = require(db.namespace, "database.namespace (RESUME_DB_NAMESPACE)");
+        let database = require(db.database, "database.database (RESUME_DB_DATABASE)");
+
+        let engine_name = db.engine.unwrap_or_else(|| "remote".to_string());
+        let engine = match engine_name.to_ascii_lowercase().as_str() {
+            "memory" | "file" if !cfg!(feature = "embedded") => {
+                return Err(ConfigError::EmbeddedNotBuilt(engine_name))
+            }
+            "memory" => DbEngine::Memory,
+            "file" => DbEngine::File {
+                path: require(db.path, "database.path (RESUME_DB_PATH)").into(),
+            },
+            "remote" => {
+                let endpoint = require(db.endpoint, "database.endpoint (RESUME_DB_ENDPOINT)");
+                let mode = db.auth.mode.map(|mode| mode.to_ascii_lowercase());
+                if mode.is_none() {
+                    require(None, "database.auth.mode (RESUME_DB_AUTH)");
+                }
+                let username =
+                    require(db.auth.username, "database.auth.username (RESUME_DB_USERNAME)");
+                let password =
+                    require(db.auth.password, "database.auth.password (RESUME_DB_PASSWORD)");
+
+                let auth = match mode.as_deref() {
+                    Some("root") => DbAuth::Root { username, password },
+                    Some("namespace") => DbAuth::Namespace { username, password },
+                    Some("database") => DbAuth::Database { username, password },
+                    Some("record") => DbAuth::Record {
+                        access: require(db.auth.access, "database.auth.access (RESUME_DB_ACCESS)"),
+                        username,
+                        password,
+                    },
+                    // An empty mode in the file is as unknown as a misspelt one.
+                    Some(other) => return Err(ConfigError::InvalidAuthMode(other.to_string())),
+                    // Already reported as missing above.
+                    None => DbAuth::Root { username, password },
+                };
+                DbEngine::Remote { endpoint, auth }
+            }
+            other => return Err(ConfigError::InvalidEngine(other.to_string())),
+        };
+
+        if !missing.is_empty() {
+            return Err(ConfigError::Missing(missing));
+        }
+
+        Ok(AppConfig {
+            database: DbConfig {
+                engine,
+                namespace,
+                database,
+            },
+            session,
+        })
+    }
+}
+
+/// Loads the configuration from the TOML file named by `RESUME_CONFIG`
+/// (default `resume.toml`) and overlays the `RESUME_DB_*` environment variables.
+/// Only the remote engine needs an endpoint and credentials.
+pub fn load() -> Result<AppConfig, ConfigError> {
+    let file_layer = match std::env::var("RESUME_CONFIG") {
+        Ok(path) => RawConfig::from_file(Path::new(&path))?,
+        Err(_) if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+            RawConfig::from_file(Path::new(DEFAULT_CONFIG_FILE))?
+        }
+        Err(_) => RawConfig::default(),
+    };
+
+    file_layer.merge(RawConfig::from_env()?).resolve()
+}
+
+fn parse_flag(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" | "on" => Some(true),
+        "false" | "0" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn file(text: &str) -> RawConfig {
+        RawConfig::from_toml(Path::new("resume.toml"), text).unwrap()
+    }
+
+    fn env(vars: &[(&str, &str)]) -> Result<RawConfig, ConfigError> {
+        let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        RawConfig::from_vars(|name| vars.get(name).cloned())
+    }
+
+    const REMOTE: &str = r#"
+        [database]
+        endpoint = "db.example.com:8000"
+        namespace = "resume"
+        database = "prod"
+
+        [database.auth]
+        mode = "namespace"
+        username = "app"
+        password = "from-file"
+    "#;
+
+    #[test]
+    fn the_environment_overrides_the_file_value_by_value() {
+        let config = file(REMOTE)
+            .merge(
+                env(&[
+                    ("RESUME_DB_DATABASE", "staging"),
+                    ("RESUME_DB_PASSWORD", "from-env"),
+                    ("RESUME_DB_USERNAME", ""),
+                ])
+                .unwrap(),
+            )
+            .resolve()
+            .unwrap();
+        assert_eq!((config.database.namespace.as_str(), config.database.database.as_str()), ("resume", "staging"));
+        assert_eq!(config.database.engine.address(), "ws://db.example.com:8000");
+        match config.database.engine {
+            // An empty variable leaves the file's username in place.
+            DbEngine::Remote { auth: DbAuth::Namespace { username, password }, .. } => {
+                assert_eq!((username.as_str(), password.as_str()), ("app", "from-env"));
+            }
+            other => panic!("unexpected engine {:?}", other),
+        }
+        assert!(!config.session.secure_cookie);
+    }
+
+    #[test]
+    fn the_environment_alone_is_enough() {
+        let config = RawConfig::default()
+            .merge(
+                env(&[
+                    ("RESUME_DB_ENDPOINT", "wss://db.example.com"),
+                    ("RESUME_DB_NAMESPACE", "ns"),
+                    ("RESUME_DB_DATABASE", "db"),
+                    ("RESUME_DB_AUTH", "Record"),
+                    ("RESUME_DB_ACCESS", "account"),
+                    ("RESUME_DB_USERNAME", "user"),
+                    ("RESUME_DB_PASSWORD", "secret"),
+                    ("RESUME_SECURE_COOKIE", "yes"),
+                ])
+                .unwrap(),
+            )
+            .resolve()
+            .unwrap();
+        assert_eq!(config.database.engine.address(), "wss://db.example.com");
+        assert!(matches!(config.database.engine, DbEngine::Remote { auth: DbAuth::Record { .. }, .. }));
+        assert!(config.session.secure_cookie);
+    }
+
+    #[test]
+    fn every_missing_value_is_listed() {
+        match RawConfig::default().resolve() {
+            Err(ConfigError::Missing(keys)) => assert_eq!(
+                keys,
+                [
+                    "database.namespace (RESUME_DB_NAMESPACE)",
+                    "database.database (RESUME_DB_DATABASE)",
+                    "database.endpoint (RESUME_DB_ENDPOINT)",
+                    "database.auth.mode (RESUME_DB_AUTH)",
+                    "database.auth.username (RESUME_DB_USERNAME)",
+                    "database.auth.password (RESUME_DB_PASSWORD)",
+                ]
+            ),
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn unknown_or_empty_modes_engines_and_flags_are_refused() {
+        let empty_mode = file(&REMOTE.replace(r#"mode = "namespace""#, r#"mode = """#)).resolve();
+        assert!(matches!(empty_mode, Err(ConfigError::InvalidAuthMode(mode)) if mode.is_empty()));
+        let misspelt = file(REMOTE).merge(env(&[("RESUME_DB_AUTH", "rot")]).unwrap()).resolve();
+        assert!(matches!(misspelt, Err(ConfigError::InvalidAuthMode(mode)) if mode == "rot"));
+        let engine = file(REMOTE).merge(env(&[("RESUME_DB_ENGINE", "sqlite")]).unwrap()).resolve();
+        assert!(matches!(engine, Err(ConfigError::InvalidEngine(_))));
+        assert!(matches!(env(&[("RESUME_SECURE_COOKIE", "maybe")]), Err(ConfigError::InvalidFlag(..))));
+        assert!(matches!(
+            RawConfig::from_toml(Path::new("resume.toml"), "[database"),
+            Err(ConfigError::Parse(..))
+        ));
+    }
+
+    #[test]
+    fn embedded_engines_need_the_embedded_feature() {
+        let memory = file(REMOTE).merge(env(&[("RESUME_DB_ENGINE", "memory")]).unwrap()).resolve();
+        if cfg!(feature = "embedded") {
+            assert_eq!(memory.unwrap().database.engine.address(), "mem://");
+            let missing_path = file(REMOTE).merge(env(&[("RESUME_DB_ENGINE", "file")]).unwrap()).resolve();
+            assert!(matches!(missing_path, Err(ConfigError::Missing(keys)) if keys == ["database.path (RESUME_DB_PATH)"]));
+        } else {
+            assert!(matches!(memory, Err(ConfigError::EmbeddedNotBuilt(engine)) if engine == "memory"));
+        }
+    }
+}
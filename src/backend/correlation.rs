@@ -0,0 +1,346 @@
+use leptos::prelude::ServerFnError;
+use leptos::server;
+use serde::{Deserialize, Serialize};
+
+use crate::backend::prices::PriceRange;
+#[cfg(feature = "ssr")]
+use crate::backend::auth::require_user;
+#[cfg(feature = "ssr")]
+use crate::backend::charts::correlation_heatmap_svg;
+#[cfg(feature = "ssr")]
+use crate::backend::fx::load_fx_table;
+#[cfg(feature = "ssr")]
+use crate::backend::model::Decimal;
+#[cfg(feature = "ssr")]
+use crate::backend::performance::{covariance, end_of_day};
+#[cfg(feature = "ssr")]
+use crate::backend::positions::positions_report;
+#[cfg(feature = "ssr")]
+use crate::backend::risk::load_prices;
+#[cfg(feature = "ssr")]
+use chrono::{Duration, NaiveDate};
+#[cfg(feature = "ssr")]
+use rust_decimal::prelude::ToPrimitive;
+#[cfg(feature = "ssr")]
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Correlation from which a pair of holdings is listed as moving together.
+pub const HIGH_CORRELATION: f64 = 0.8;
+
+/// Two holdings whose returns move together.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorrelatedPair {
+    pub first: String,
+    pub second: String,
+    pub correlation: f64,
+}
+
+/// How the returns of the assets held in the selected portfolio move together.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorrelationReport {
+    pub range: PriceRange,
+    pub reporting_ccy: String,
+    /// Held assets with prices, in matrix order.
+    pub symbols: Vec<String>,
+    /// Fewest returns any figure of the matrix is computed from.
+    pub observations: usize,
+    /// `matrix[i][j]` correlates `symbols[i]` with `symbols[j]` over the days both
+    /// have a price; `None` when one of them never moved or they share too few days.
+    pub matrix: Vec<Vec<Option<f64>>>,
+    /// Weighted volatility of the holdings over the volatility of the whole; 1 means
+    /// no diversification, higher is better.
+    pub diversification_ratio: Option<f64>,
+    /// Pairs at or above `HIGH_CORRELATION`, strongest first.
+    pub pairs: Vec<CorrelatedPair>,
+    /// Empty with fewer than two assets.
+    pub svg: String,
+    /// Set when holdings without a rate into the reporting currency were left out.
+    pub unconverted_note: Option<String>,
+}
+
+/// Daily values of one holding in the reporting currency, with its weight in the
+/// portfolio.
+#[cfg(feature = "ssr")]
+struct Holding {
+    symbol: String,
+    weight: f64,
+    values: BTreeMap<NaiveDate, Decimal>,
+}
+
+#[cfg(feature = "ssr")]
+impl Holding {
+    /// Returns from one of `days` to the next; `days` must all have a value.
+    fn returns<'a>(&self, days: impl Iterator<Item = &'a NaiveDate>) -> Vec<f64> {
+        let values: Vec<Decimal> = days.map(|day| self.values[day]).collect();
+        values
+            .windows(2)
+            .map(|pair| {
+                if pair[0] > Decimal::ZERO {
+                    (pair[1] / pair[0] - Decimal::ONE).to_f64().unwrap_or_default()
+                } else {
+                    0.0
+                }
+            })
+            .collect()
+    }
+}
+
+/// Correlation matrix and diversification ratio of `holdings`.
+#[cfg(feature = "ssr")]
+struct Correlations {
+    observations: usize,
+    matrix: Vec<Vec<Option<f64>>>,
+    diversification_ratio: Option<f64>,
+}
+
+/// Correlates each pair of holdings over the days both have a value, so that one
+/// asset with a short history does not shorten every other pair. Each holding's own
+/// volatility is taken over all its days.
+#[cfg(feature = "ssr")]
+fn correlate(holdings: &[Holding]) -> Correlations {
+    let size = holdings.len();
+    let own: Vec<Vec<f64>> = holdings.iter().map(|h| h.returns(h.values.keys())).collect();
+    let volatility: Vec<Option<f64>> = own
+        .iter()
+        .map(|returns| covariance(returns, returns).filter(|v| *v > 0.0).map(f64::sqrt))
+        .collect();
+    let mut observations = own.iter().map(Vec::len).min().unwrap_or_default();
+    let mut matrix = vec![vec![None; size]; size];
+    for i in 0..size {
+        matrix[i][i] = volatility[i].map(|_| 1.0);
+        for j in i + 1..size {
+            let days: Vec<&NaiveDate> = holdings[i]
+                .values
+                .keys()
+                .filter(|day| holdings[j].values.contains_key(*day))
+                .collect();
+            let (a, b) = (holdings[i].returns(days.iter().copied()), holdings[j].returns(days.iter().copied()));
+            observations = observations.min(a.len());
+            let correlation = match (covariance(&a, &b), covariance(&a, &a), covariance(&b, &b)) {
+                (Some(cov), Some(var_a), Some(var_b)) if var_a > 0.0 && var_b > 0.0 => {
+                    Some((cov / (var_a * var_b).sqrt()).clamp(-1.0, 1.0))
+                }
+                _ => None,
+            };
+            matrix[i][j] = correlation;
+            matrix[j][i] = correlation;
+        }
+    }
+
+    // Weighted average volatility over the volatility of the weighted portfolio, whose
+    // covariances are the pairs' correlations scaled by each holding's own volatility.
+    let weighted_volatility: f64 = (0..size)
+        .map(|i| holdings[i].weight * volatility[i].unwrap_or_default())
+        .sum();
+    let portfolio_variance: f64 = (0..size)
+        .flat_map(|i| (0..size).map(move |j| (i, j)))
+        .map(|(i, j)| {
+            let covariance = match (matrix[i][j], volatility[i], volatility[j]) {
+                (Some(c), Some(vol_i), Some(vol_j)) => c * vol_i * vol_j,
+                _ => 0.0,
+            };
+            holdings[i].weight * holdings[j].weight * covariance
+        })
+        .sum();
+    let diversification_ratio = (size > 1 && portfolio_variance > 0.0)
+        .then(|| weighted_volatility / portfolio_variance.sqrt())
+        .filter(|r| r.is_finite());
+
+    Correlations {
+        observations,
+        matrix,
+        diversification_ratio,
+    }
+}
+
+/// Correlations of the daily returns of the assets held in the selected portfolio
+/// over `range` ending today. Prices are converted into the reporting currency and
+/// each pair is compared on the days both have one; weights are market values.
+/// Holdings that cannot be converted on one of their days are left out.
+#[server(GetCorrelation, "/api")]
+pub async fn get_correlation(range: PriceRange) -> Result<CorrelationReport, ServerFnError> {
+    let user = require_user().await?;
+    let today = chrono::Utc::now().date_naive();
+    let since = range.days().map(|days| today - Duration::days(days));
+
+    let report = positions_report(&user).await?;
+    let open: Vec<_> = report.positions.iter().filter(|p| !p.qty.is_zero()).collect();
+    let prices = load_prices(open.iter().map(|p| p.asset.clone()).collect()).await?;
+    let fx = load_fx_table().await?;
+
+    let mut unconverted = BTreeSet::new();
+    let mut held = Vec::with_capacity(open.len());
+    for position in open {
+        // Last price of each day, in the reporting currency; assets without prices drop out.
+        let closes: BTreeMap<NaiveDate, Decimal> = prices
+            .iter()
+            .filter(|p| p.asset == position.asset)
+            .map(|p| (p.price_date.into_inner_ref().date_naive(), p.price))
+            .filter(|(day, _)| since.is_none_or(|since| *day >= since) && *day <= today)
+            .collect();
+        let values: Option<BTreeMap<NaiveDate, Decimal>> = position.fx_rate.and_then(|_| {
+            closes
+                .into_iter()
+                .map(|(day, close)| {
+                    let quote = fx.quote(&position.ccy, &report.reporting_ccy, &end_of_day(day)).ok()?;
+                    Some((day, close * quote.rate))
+                })
+                .collect()
+        });
+        match values {
+            Some(values) if !values.is_empty() => held.push((position, values)),
+            Some(_) => {}
+            None => {
+                unconverted.insert(position.ccy.clone());
+            }
+        }
+    }
+
+    let total: Decimal = held.iter().filter_map(|(p, _)| p.reporting_market_value()).sum();
+    let holdings: Vec<Holding> = held
+        .into_iter()
+        .map(|(position, values)| Holding {
+            symbol: position.symbol.clone(),
+            weight: match position.reporting_market_value() {
+                Some(value) if total > Decimal::ZERO => (value / total).to_f64().unwrap_or_default(),
+                _ => 0.0,
+            },
+            values,
+        })
+        .collect();
+    let size = holdings.len();
+    let Correlations {
+        observations,
+        matrix,
+        diversification_ratio,
+    } = correlate(&holdings);
+
+    let mut pairs: Vec<CorrelatedPair> = (0..size)
+        .flat_map(|i| (i + 1..size).map(move |j| (i, j)))
+        .filter_map(|(i, j)| {
+            matrix[i][j]
+                .filter(|c| *c >= HIGH_CORRELATION)
+                .map(|correlation| CorrelatedPair {
+                    first: holdings[i].symbol.clone(),
+                    second: holdings[j].symbol.clone(),
+                    correlation,
+                })
+        })
+        .collect();
+    pairs.sort_by(|a, b| b.correlation.total_cmp(&a.correlation));
+
+    let symbols: Vec<String> = holdings.iter().map(|h| h.symbol.clone()).collect();
+    let svg = if size < 2 {
+        String::new()
+    } else {
+        let cells: Vec<Vec<f32>> = matrix
+            .iter()
+            .map(|row| row.iter().map(|c| c.unwrap_or_default() as f32).collect())
+            .collect();
+        correlation_heatmap_svg(
+            &format!("Correlation of daily returns - {}", range.label()),
+            symbols.clone(),
+            &cells,
+        )?
+    };
+    let unconverted_note = (!unconverted.is_empty()).then(|| {
+        format!(
+            "No {} rate for {}: holdings in those currencies are left out.",
+            report.reporting_ccy,
+            unconverted.into_iter().collect::<Vec<_>>().join(", ")
+        )
+    });
+
+    Ok(CorrelationReport {
+        range,
+        reporting_ccy: report.reporting_ccy,
+        symbols,
+        observations,
+        matrix,
+        diversification_ratio,
+        pairs,
+        svg,
+        unconverted_note,
+    })
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn holding(symbol: &str, weight: f64, first_day: u32, values: &[&str]) -> Holding {
+        Holding {
+            symbol: symbol.to_string(),
+            weight,
+            values: values
+                .iter()
+                .enumerate()
+                .map(|(i, value)| {
+                    (
+                        NaiveDate::from_ymd_opt(2024, 3, first_day + i as u32).unwrap(),
+                        Decimal::from_str(value).unwrap(),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("a figure");
+        assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn each_pair_is_correlated_over_its_own_days() {
+        // A returns +10, +10, -10, +10%; B -10, +10, -10, +10%: covariance 0.02 / 3
+        // over variances 0.03 / 3 and 0.04 / 3, a correlation of 1 / √3. C starts on
+        // the third day and moves with both of them from there.
+        let holdings = [
+            holding("A", 0.5, 1, &["100", "110", "121", "108.9", "119.79"]),
+            holding("B", 0.3, 1, &["100", "90", "99", "89.1", "98.01"]),
+            holding("C", 0.2, 3, &["50", "45", "49.5"]),
+        ];
+        let correlations = correlate(&holdings);
+        let matrix = &correlations.matrix;
+        assert_close(matrix[0][1], 1.0 / 3f64.sqrt());
+        assert_close(matrix[1][0], 1.0 / 3f64.sqrt());
+        assert_close(matrix[0][2], 1.0);
+        assert_close(matrix[1][2], 1.0);
+        assert!((0..3).all(|i| matrix[i][i] == Some(1.0)));
+        assert_eq!(correlations.observations, 2);
+    }
+
+    #[test]
+    fn holdings_that_never_move_have_no_correlation() {
+        let holdings = [
+            holding("A", 0.5, 1, &["100", "110", "99"]),
+            holding("FLAT", 0.5, 1, &["10", "10", "10"]),
+        ];
+        let correlations = correlate(&holdings);
+        assert_eq!(correlations.matrix[0][1], None);
+        assert_eq!(correlations.matrix[1][1], None);
+    }
+
+    #[test]
+    fn diversification_ratio_is_weighted_over_whole_volatility() {
+        // Two holdings moving as one do not diversify.
+        let same = [
+            holding("A", 0.6, 1, &["100", "110", "99", "108.9"]),
+            holding("B", 0.4, 1, &["200", "220", "198", "217.8"]),
+        ];
+        assert_close(correlate(&same).diversification_ratio, 1.0);
+
+        // Halves of equal volatility and no correlation: σ / √(σ² / 2) = √2.
+        let independent = [
+            holding("A", 0.5, 1, &["100", "110", "99", "108.9", "98.01"]),
+            holding("B", 0.5, 1, &["100", "110", "121", "108.9", "98.01"]),
+        ];
+        let correlations = correlate(&independent);
+        assert_close(correlations.matrix[0][1], 0.0);
+        assert_close(correlations.diversification_ratio, 2f64.sqrt());
+
+        // A single holding has nothing to diversify with.
+        assert_eq!(correlate(&same[..1]).diversification_ratio, None);
+    }
+}
@@ -0,0 +1,73 @@
+use rust_decimal::Decimal;
+
+/// Active ISO 4217 currency codes (funds and precious-metal codes excluded).
+pub const ISO_4217: &[&str] = &[
+    "AED", "AFN", "ALL", "AMD", "ANG", "AOA", "ARS", "AUD", "AWG", "AZN", "BAM", "BBD", "BDT",
+    "BGN", "BHD", "BIF", "BMD", "BND", "BOB", "BRL", "BSD", "BTN", "BWP", "BYN", "BZD", "CAD",
+    "CDF", "CHF", "CLP", "CNY", "COP", "CRC", "CUP", "CVE", "CZK", "DJF", "DKK", "DOP", "DZD",
+    "EGP", "ERN", "ETB", "EUR", "FJD", "FKP", "GBP", "GEL", "GHS", "GIP", "GMD", "GNF", "GTQ",
+    "GYD", "HKD", "HNL", "HTG", "HUF", "IDR", "ILS", "INR", "IQD", "IRR", "ISK", "JMD", "JOD",
+    "JPY", "KES", "KGS", "KHR", "KMF", "KPW", "KRW", "KWD", "KYD", "KZT", "LAK", "LBP", "LKR",
+    "LRD", "LSL", "LYD", "MAD", "MDL", "MGA", "MKD", "MMK", "MNT", "MOP", "MRU", "MUR", "MVR",
+    "MWK", "MXN", "MYR", "MZN", "NAD", "NGN", "NIO", "NOK", "NPR", "NZD", "OMR", "PAB", "PEN",
+    "PGK", "PHP", "PKR", "PLN", "PYG", "QAR", "RON", "RSD", "RUB", "RWF", "SAR", "SBD", "SCR",
+    "SDG", "SEK", "SGD", "SHP", "SLE", "SOS", "SRD", "SSP", "STN", "SVC", "SYP", "SZL", "THB",
+    "TJS", "TMT", "TND", "TOP", "TRY", "TTD", "TWD", "TZS", "UAH", "UGX", "USD", "UYU", "UZS",
+    "VES", "VND", "VUV", "WST", "XAF", "XCD", "XOF", "XPF", "YER", "ZAR", "ZMW", "ZWG",
+];
+
+/// Upper-cased code if `code` is an active ISO 4217 currency.
+pub fn normalize_ccy(code: &str) -> Option<String> {
+    let code = code.trim().to_ascii_uppercase();
+    ISO_4217.contains(&code.as_str()).then_some(code)
+}
+
+/// Display symbol for the common currencies, the ISO code otherwise.
+pub fn currency_symbol(code: &str) -> &str {
+    match code {
+        "EUR" => "€",
+        "USD" => "$",
+        "GBP" => "£",
+        "JPY" => "¥",
+        "CNY" => "¥",
+        "INR" => "₹",
+        "KRW" => "₩",
+        _ => code,
+    }
+}
+
+/// Decimal places of the currency's minor unit: 0 for yen-like currencies,
+/// 3 for the dinar family, 2 otherwise.
+pub fn minor_units(code: &str) -> u32 {
+    match code {
+        "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF" | "UGX"
+        | "VND" | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+        "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+        _ => 2,
+    }
+}
+
+/// `€1234.56`, or `1234.56 SEK` when the currency has no symbol of its own,
+/// rounded to the currency's minor unit.
+pub fn format_amount(amount: Decimal, code: &str) -> String {
+    let symbol = currency_symbol(code);
+    let rounded = amount.round_dp(minor_units(code));
+    let sign = if rounded.is_sign_negative() && !rounded.is_zero() { "-" } else { "" };
+    let digits = format!("{:.*}", minor_units(code) as usize, rounded.abs());
+    if symbol == code {
+        format!("{}{} {}", sign, digits, code)
+    } else {
+        format!("{}{}{}", sign, symbol, digits)
+    }
+}
+
+/// Price with the asset's own number of decimals, e.g. `$1.0912` for a forex pair.
+pub fn format_price(price: Decimal, code: &str, precision: u32) -> String {
+    let symbol = currency_symbol(code);
+    let digits = format!("{:.*}", precision as usize, price.round_dp(precision));
+    if symbol == code {
+        format!("{} {}", digits, code)
+    } else {
+        format!("{}{}", symbol, digits)
+    }
+}
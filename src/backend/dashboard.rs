@@ -0,0 +1,110 @@
+use leptos::prelude::ServerFnError;
+use leptos::server;
+use serde::{Deserialize, Serialize};
+use surrealdb::Datetime;
+
+use crate::backend::allocation::AllocationSlice;
+use crate::backend::model::{AssetWithPrice, Money, Trade, Wallet};
+#[cfg(feature = "ssr")]
+use crate::backend::auth::require_user;
+#[cfg(feature = "ssr")]
+use crate::backend::allocation::allocate;
+#[cfg(feature = "ssr")]
+use crate::backend::db::{load_assets, load_categories, wallet_total};
+#[cfg(feature = "ssr")]
+use crate::backend::portfolios::portfolio_query;
+#[cfg(feature = "ssr")]
+use crate::backend::model::Decimal;
+#[cfg(feature = "ssr")]
+use crate::backend::positions::positions_report;
+#[cfg(feature = "ssr")]
+use rust_decimal::prelude::ToPrimitive;
+
+/// Number of trades and wallet entries listed on the dashboard.
+pub const RECENT_LIMIT: usize = 5;
+
+/// Everything the Home page shows, in the reporting currency.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Dashboard {
+    /// Wallet cash plus the market value of open positions.
+    pub net_worth: Money,
+    pub cash: Money,
+    pub invested: Money,
+    /// Move of the positions since the previous price of each asset.
+    pub day_change: Money,
+    pub day_change_pct: Option<f64>,
+    /// Invested amount by asset category.
+    pub allocation: Vec<AllocationSlice>,
+    pub assets: Vec<AssetWithPrice>,
+    pub recent_trades: Vec<Trade>,
+    pub recent_wallet: Vec<Wallet>,
+    pub as_of: Datetime,
+    /// Warning when positions or cash without a rate were left out of the totals.
+    pub unconverted_note: Option<String>,
+}
+
+#[cfg(feature = "ssr")]
+fn pct(part: Decimal, whole: Decimal) -> Option<f64> {
+    if whole.is_zero() {
+        return None;
+    }
+    (part / whole * Decimal::ONE_HUNDRED).to_f64()
+}
+
+/// Aggregates the dashboard so the Home page loads in a single round-trip.
+#[server(GetDashboard, "/api")]
+pub async fn get_dashboard() -> Result<Dashboard, ServerFnError> {
+    let user = require_user().await?;
+    let wallet_total = wallet_total(&user).await?;
+    let report = positions_report(&user).await?;
+    let assets = load_assets().await?;
+    let categories = load_categories().await?;
+    let ccy = report.reporting_ccy.clone();
+
+    let mut day_change = Decimal::ZERO;
+    for position in &report.positions {
+        let asset = assets.iter().find(|a| a.id.as_ref() == Some(&position.asset));
+        let prices = asset.map(|a| a.recent_prices.as_slice());
+        if let (Some([latest, previous]), Some(rate)) = (prices, position.fx_rate) {
+            day_change += (latest.price - previous.price) * position.qty * rate;
+        }
+    }
+    let invested = report.market_value.amount;
+    let notes: Vec<String> = [report.unconverted_note(), wallet_total.unconverted_note()]
+        .into_iter()
+        .flatten()
+        .collect();
+    let unconverted_note = (!notes.is_empty()).then(|| notes.join(" "));
+    let allocation = allocate(&report, &assets, &categories).by_category;
+
+    let mut response = portfolio_query(
+        &user,
+        "SELECT * FROM trade WHERE owner = $owner AND (!$portfolio OR portfolio = $portfolio)
+            ORDER BY trade_date DESC LIMIT $limit;
+         SELECT * FROM wallet WHERE owner = $owner ORDER BY tx_date DESC LIMIT $limit;",
+    )
+    .await?
+    .bind(("limit", RECENT_LIMIT))
+    .await?;
+    let recent_trades: Vec<Trade> = response.take(0)?;
+    let recent_wallet: Vec<Wallet> = response.take(1)?;
+
+    let cash = wallet_total.total;
+    let net_worth = cash
+        .checked_add(&report.market_value)
+        .ok_or_else(|| ServerFnError::new("Wallet and positions use different currencies"))?;
+
+    Ok(Dashboard {
+        net_worth,
+        cash,
+        invested: report.market_value,
+        day_change: Money::new(day_change, ccy).rounded(),
+        day_change_pct: pct(day_change, invested - day_change),
+        allocation,
+        assets,
+        recent_trades,
+        recent_wallet,
+        as_of: wallet_total.as_of,
+        unconverted_note,
+    })
+}
@@ -1,61 +1,458 @@
 use leptos::prelude::ServerFnError;
 use leptos::server;
 use std::sync::OnceLock;
-use surrealdb::engine::remote::ws::{Client, Ws};
+use surrealdb::engine::any::Any;
+#[cfg(feature = "ssr")]
+use surrealdb::engine::any;
 use surrealdb::Surreal;
-use crate::backend::model::{AssetWithPrice, Category, Trade, Wallet};
+use crate::backend::model::{
+    AssetWithPrice, Category, Decimal, Trade, TradeSide, TradeStatus, Wallet, WalletCategory,
+    WalletTotal,
+};
+#[cfg(feature = "ssr")]
+use crate::backend::auth::{require_user, User};
+#[cfg(feature = "ssr")]
+use crate::backend::model::Money;
+#[cfg(feature = "ssr")]
+use crate::backend::portfolios::{ensure_portfolio, owned_portfolio, portfolio_query, DEFAULT_PORTFOLIO};
+#[cfg(feature = "ssr")]
+use crate::backend::tax_lots::{forget_lot_assignments, record_lot_matches};
+#[cfg(feature = "ssr")]
+use rust_decimal::prelude::ToPrimitive;
+#[cfg(feature = "ssr")]
+use crate::backend::fx::load_fx_table;
+#[cfg(feature = "ssr")]
+use crate::backend::settings::reporting_ccy;
+#[cfg(feature = "ssr")]
+use crate::backend::currency::{minor_units, normalize_ccy};
+#[cfg(feature = "ssr")]
+use crate::backend::config::{DbAuth, DbConfig, DbEngine};
+#[cfg(feature = "ssr")]
+use crate::backend::migrations;
+#[cfg(feature = "ssr")]
+use surrealdb::opt::auth::{Database, Namespace, Record, Root};
+#[cfg(feature = "ssr")]
+use surrealdb::{Datetime, RecordId};
 
 
 
-pub static DB: OnceLock<Surreal<Client>> = OnceLock::new();
+pub static DB: OnceLock<Surreal<Any>> = OnceLock::new();
 
-// Initialize once at startup
-pub async fn init_db() -> Result<(), ServerFnError> {
-    let db = Surreal::new::<Ws>("127.0.0.1:8000").await?;
+// Credentials sent to a record access method's SIGNIN clause.
+#[cfg(feature = "ssr")]
+#[derive(serde::Serialize)]
+struct RecordCredentials<'a> {
+    username: &'a str,
+    password: &'a str,
+}
 
-    // Sign in with credentials
-    db.signin(surrealdb::opt::auth::Root {
-        username: "root",
-        password: "root",
-    })
-    .await?;
+// Opens a connection for the configured engine without registering it globally
+#[cfg(feature = "ssr")]
+pub async fn connect(config: &DbConfig) -> Result<Surreal<Any>, ServerFnError> {
+    let db = any::connect(config.engine.address()).await?;
+
+    // Embedded engines run in-process and need no credentials
+    if let DbEngine::Remote { auth, .. } = &config.engine {
+        match auth {
+            DbAuth::Root { username, password } => {
+                db.signin(Root { username, password }).await?;
+            }
+            DbAuth::Namespace { username, password } => {
+                db.signin(Namespace {
+                    namespace: &config.namespace,
+                    username,
+                    password,
+                })
+                .await?;
+            }
+            DbAuth::Database { username, password } => {
+                db.signin(Database {
+                    namespace: &config.namespace,
+                    database: &config.database,
+                    username,
+                    password,
+                })
+                .await?;
+            }
+            DbAuth::Record {
+                access,
+                username,
+                password,
+            } => {
+                db.signin(Record {
+                    namespace: &config.namespace,
+                    database: &config.database,
+                    access,
+                    params: RecordCredentials { username, password },
+                })
+                .await?;
+            }
+        }
+    }
+
+    db.use_ns(&config.namespace).use_db(&config.database).await?;
+
+    Ok(db)
+}
 
-    db.use_ns("eric").use_db("Trading").await?;
+// Initialize once at startup
+#[cfg(feature = "ssr")]
+pub async fn init_db(config: &DbConfig) -> Result<(), ServerFnError> {
+    let db = connect(config).await?;
+    migrations::migrate(&db).await?;
     DB.set(db).expect("Failed to set database");
 
     Ok(())
 }
 
 // Use anywhere in your app
-pub fn get_db() -> &'static Surreal<Client> {
+pub fn get_db() -> &'static Surreal<Any> {
     DB.get().expect("Database not initialized")
 }
 
-#[server(GetCategories, "/api")]
-pub async fn get_categories() -> Result<Vec<Category>, ServerFnError> {
-    let db = get_db();
+/// Tables whose records belong to one account.
+#[cfg(feature = "ssr")]
+const OWNED_TABLES: &[&str] = &["trade", "wallet", "setting", "portfolio", "lot_assignment"];
 
-    // Use FETCH to get the related ratings data
- let categories = db.select("category").await?;
+/// Query with `$owner` bound to the signed-in user. Every read, update or delete of
+/// trades, wallet entries, portfolios, lot assignments or user settings goes through
+/// here, and `check_owner_scope` refuses the query unless each such statement filters
+/// on `owner = $owner`; creates set `owner` on the new record instead.
+#[cfg(feature = "ssr")]
+pub fn owned_query(user: &User, sql: &str) -> Result<surrealdb::method::Query<'static, Any>, ServerFnError> {
+    check_owner_scope(sql)?;
+    Ok(get_db().query(sql.to_string()).bind(("owner", user.id.clone())))
+}
+
+/// Checks that every top-level SELECT, UPDATE, UPSERT or DELETE in `sql` that can reach
+/// an owned table has `owner = $owner` in its WHERE clause, alone or joined by AND.
+/// Targets given as params or expressions count as owned, as they may point anywhere.
+/// LET may only hold such a SELECT; statements that could read or write owned records
+/// some other way (IF, FOR, RETURN, RELATE, INSERT … ON DUPLICATE KEY UPDATE, DEFINE …)
+/// are refused.
+#[cfg(feature = "ssr")]
+pub(crate) fn check_owner_scope(sql: &str) -> Result<(), ServerFnError> {
+    use surrealdb::sql::{Statement, Subquery, Value};
+
+    let query = surrealdb::syn::parse(sql).map_err(|e| ServerFnError::new(e.to_string()))?;
+    for statement in query.0 .0.iter() {
+        let scoped = match statement {
+            Statement::Select(s) => is_scoped(&s.what, &s.cond),
+            Statement::Update(s) => is_scoped(&s.what, &s.cond),
+            Statement::Upsert(s) => is_scoped(&s.what, &s.cond),
+            Statement::Delete(s) => is_scoped(&s.what, &s.cond),
+            Statement::Set(s) => match &s.what {
+                Value::Subquery(subquery) => match subquery.as_ref() {
+                    Subquery::Select(s) => is_scoped(&s.what, &s.cond),
+                    _ => false,
+                },
+                _ => false,
+            },
+            Statement::Insert(s) => s.update.is_none(),
+            Statement::Create(_) | Statement::Begin(_) | Statement::Commit(_) | Statement::Cancel(_) => true,
+            _ => false,
+        };
+        if !scoped {
+            return Err(ServerFnError::new(format!(
+                "Query is not limited to the signed-in account: {}",
+                statement
+            )));
+        }
+    }
+    Ok(())
+}
+
+// Whether a statement over `what` only reaches the signed-in account's records.
+#[cfg(feature = "ssr")]
+fn is_scoped(what: &surrealdb::sql::Values, cond: &Option<surrealdb::sql::Cond>) -> bool {
+    use surrealdb::sql::Value;
+
+    let owned = what.iter().any(|target| match target {
+        Value::Table(table) => OWNED_TABLES.contains(&table.0.as_str()),
+        Value::Thing(thing) => OWNED_TABLES.contains(&thing.tb.as_str()),
+        _ => true,
+    });
+    !owned || cond.as_ref().is_some_and(|cond| requires_owner(&cond.0))
+}
+
+// Whether `cond` holds only when `owner = $owner`.
+#[cfg(feature = "ssr")]
+fn requires_owner(cond: &surrealdb::sql::Value) -> bool {
+    use surrealdb::sql::{Expression, Operator, Value};
+
+    let Value::Expression(expression) = cond else {
+        return false;
+    };
+    match expression.as_ref() {
+        Expression::Binary { l, o: Operator::And, r } => requires_owner(l) || requires_owner(r),
+        Expression::Binary { l, o: Operator::Equal, r } => {
+            l.to_string() == "owner" && r.to_string() == "$owner"
+        }
+        _ => false,
+    }
+}
+
+/// Rows `assign_owner` handed over.
+#[cfg(feature = "ssr")]
+#[derive(Debug, Default)]
+pub struct AssignedRecords {
+    pub trades: usize,
+    pub wallet: usize,
+}
+
+/// Hands the trades and wallet entries recorded before accounts existed to the
+/// account `username`, booking the trades in its default portfolio. Run by the
+/// `assign-owner` subcommand; signing up never takes over such rows.
+#[cfg(feature = "ssr")]
+pub async fn assign_owner(db: &Surreal<Any>, username: &str) -> Result<AssignedRecords, ServerFnError> {
+    let username = username.trim().to_ascii_lowercase();
+    let mut response = db
+        .query("SELECT VALUE id FROM user WHERE username = $username")
+        .bind(("username", username.clone()))
+        .await?;
+    let found: Vec<RecordId> = response.take(0)?;
+    let owner = found
+        .into_iter()
+        .next()
+        .ok_or_else(|| ServerFnError::new(format!("No account named '{}'", username)))?;
+    let portfolio = ensure_portfolio(db, &owner, DEFAULT_PORTFOLIO).await?;
+
+    let mut response = db
+        .query(
+            "BEGIN TRANSACTION;
+             UPDATE trade SET owner = $owner, portfolio = $portfolio WHERE owner = NONE RETURN VALUE id;
+             UPDATE wallet SET owner = $owner WHERE owner = NONE RETURN VALUE id;
+             COMMIT TRANSACTION;",
+        )
+        .bind(("owner", owner))
+        .bind(("portfolio", portfolio))
+        .await?;
+    let trades: Vec<RecordId> = response.take(0)?;
+    let wallet: Vec<RecordId> = response.take(1)?;
+    Ok(AssignedRecords {
+        trades: trades.len(),
+        wallet: wallet.len(),
+    })
+}
+
+/// Every asset category.
+#[cfg(feature = "ssr")]
+pub async fn load_categories() -> Result<Vec<Category>, ServerFnError> {
+    let categories = get_db().select("category").await?;
 
     Ok(categories)
 }
 
+#[server(GetCategories, "/api")]
+pub async fn get_categories() -> Result<Vec<Category>, ServerFnError> {
+    require_user().await?;
+    load_categories().await
+}
+
 #[server(GetWallet, "/api")]
 pub async fn get_wallet() -> Result<Vec<Wallet>, ServerFnError> {
-    let db = get_db();
-   
- let categories = db.select("wallet").await?;
+    let user = require_user().await?;
 
-    Ok(categories)
+    let mut response = owned_query(&user, "SELECT * FROM wallet WHERE owner = $owner ORDER BY tx_date DESC")?
+        .await?;
+    let entries: Vec<Wallet> = response.take(0)?;
+
+    Ok(entries)
 }
 
 
-#[server(GetAssets, "/api")]
-pub async fn get_assets() -> Result<Vec<AssetWithPrice>, ServerFnError> {
+// Net amount held in one currency.
+#[cfg(feature = "ssr")]
+#[derive(serde::Deserialize)]
+struct CcyBalance {
+    ccy: String,
+    amount: Decimal,
+}
+
+/// Nets `user`'s non-cancelled wallet entries per currency and converts each balance
+/// into the reporting currency at the latest rates. A currency without a rate is left
+/// out of the total and listed in `unconverted` rather than guessed.
+#[cfg(feature = "ssr")]
+pub async fn wallet_total(user: &User) -> Result<WalletTotal, ServerFnError> {
+    let target = reporting_ccy(user).await?;
+
+    let mut response = owned_query(
+        user,
+        "SELECT ccy, math::sum(amount) AS amount FROM wallet
+         WHERE owner = $owner AND status != 'cancelled' GROUP BY ccy",
+    )?
+    .await?;
+    let balances: Vec<CcyBalance> = response.take(0)?;
+    let fx = load_fx_table().await?;
+    let as_of = Datetime::from(chrono::Utc::now());
+
+    let mut total = Money::zero(target.clone());
+    let mut rates = Vec::new();
+    let mut unconverted = Vec::new();
+    for balance in balances {
+        let Ok(quote) = fx.quote(&balance.ccy, &target, &as_of) else {
+            if !balance.amount.is_zero() {
+                unconverted.push(balance.ccy);
+            }
+            continue;
+        };
+        let converted = Money::new(balance.amount, balance.ccy).convert(quote.rate, &target);
+        total = total
+            .checked_add(&converted)
+            .ok_or_else(|| ServerFnError::new("Wallet total overflows"))?;
+        if quote.from != quote.to {
+            rates.push(quote);
+        }
+    }
+
+    Ok(WalletTotal {
+        total: total.rounded(),
+        as_of,
+        rates,
+        unconverted,
+    })
+}
+
+#[server(GetWalletTotal, "/api")]
+pub async fn get_wallet_total() -> Result<WalletTotal, ServerFnError> {
+    let user = require_user().await?;
+    wallet_total(&user).await
+}
+
+#[server(GetWalletCategories, "/api")]
+pub async fn get_wallet_categories() -> Result<Vec<WalletCategory>, ServerFnError> {
+    require_user().await?;
     let db = get_db();
 
     let mut response = db
+        .query("SELECT * FROM wallet_category ORDER BY kind, name")
+        .await?;
+    let categories: Vec<WalletCategory> = response.take(0)?;
+
+    Ok(categories)
+}
+
+pub const WALLET_STATUSES: &[&str] = &["pending", "completed", "cancelled"];
+
+#[cfg(feature = "ssr")]
+fn wallet_id(id: &str) -> Result<RecordId, ServerFnError> {
+    let key = id.strip_prefix("wallet:").unwrap_or(id).trim();
+    if key.is_empty() {
+        return Err(ServerFnError::new("Missing wallet entry id"));
+    }
+    Ok(RecordId::from_table_key("wallet", key))
+}
+
+#[cfg(feature = "ssr")]
+pub(crate) fn validate_wallet_status(status: &str) -> Result<String, ServerFnError> {
+    let status = status.trim().to_ascii_lowercase();
+    if !WALLET_STATUSES.contains(&status.as_str()) {
+        return Err(ServerFnError::new(format!(
+            "Status must be one of: {}",
+            WALLET_STATUSES.join(", ")
+        )));
+    }
+    Ok(status)
+}
+
+// Empty selection clears the category; anything else must exist.
+#[cfg(feature = "ssr")]
+pub(crate) async fn wallet_category_id(category: Option<String>) -> Result<Option<RecordId>, ServerFnError> {
+    let Some(key) = category
+        .as_deref()
+        .map(|c| c.strip_prefix("wallet_category:").unwrap_or(c).trim())
+        .filter(|c| !c.is_empty())
+    else {
+        return Ok(None);
+    };
+    let id = RecordId::from_table_key("wallet_category", key);
+
+    let mut response = get_db()
+        .query("SELECT VALUE id FROM $category")
+        .bind(("category", id.clone()))
+        .await?;
+    let found: Vec<RecordId> = response.take(0)?;
+    if found.is_empty() {
+        return Err(ServerFnError::new(format!("Unknown wallet category '{}'", key)));
+    }
+
+    Ok(Some(id))
+}
+
+/// Records a deposit or withdrawal; `amount` is always entered as a positive number
+/// with no more decimals than the currency's minor unit.
+#[server(AddWalletEntry, "/api")]
+pub async fn add_wallet_entry(
+    kind: String,
+    amount: Decimal,
+    ccy: String,
+    status: String,
+    tx_date: String,
+    note: String,
+    category: Option<String>,
+) -> Result<Wallet, ServerFnError> {
+    let user = require_user().await?;
+    let withdrawal = match kind.trim().to_ascii_lowercase().as_str() {
+        "deposit" => false,
+        "withdrawal" => true,
+        _ => return Err(ServerFnError::new("Kind must be deposit or withdrawal")),
+    };
+    if amount <= Decimal::ZERO || amount > Decimal::from(1_000_000_000_000i64) {
+        return Err(ServerFnError::new("Amount must be a positive number"));
+    }
+    let ccy = normalize_ccy(&ccy)
+        .ok_or_else(|| ServerFnError::new(format!("'{}' is not an ISO 4217 currency code", ccy.trim())))?;
+    if amount.normalize().scale() > minor_units(&ccy) {
+        return Err(ServerFnError::new(format!(
+            "{} amounts take at most {} decimals",
+            ccy,
+            minor_units(&ccy)
+        )));
+    }
+
+    let entry = Wallet {
+        id: None,
+        amount: if withdrawal { -amount } else { amount },
+        ccy,
+        status: validate_wallet_status(&status)?,
+        tx_date: parse_datetime(&tx_date)?,
+        note: note.trim().to_string(),
+        category: wallet_category_id(category).await?,
+        reference: None,
+        owner: Some(user.id),
+    };
+
+    let created: Option<Wallet> = get_db().create("wallet").content(entry).await?;
+    created.ok_or_else(|| ServerFnError::new("Wallet entry was not created"))
+}
+
+/// Edits the bookkeeping fields of an entry; amount, currency and date stay as recorded.
+#[server(UpdateWalletEntry, "/api")]
+pub async fn update_wallet_entry(
+    id: String,
+    note: String,
+    status: String,
+    category: Option<String>,
+) -> Result<Wallet, ServerFnError> {
+    let user = require_user().await?;
+    let mut response = owned_query(
+        &user,
+        "UPDATE $id SET note = $note, status = $status, category = $category
+         WHERE owner = $owner RETURN AFTER",
+    )?
+    .bind(("id", wallet_id(&id)?))
+    .bind(("note", note.trim().to_string()))
+    .bind(("status", validate_wallet_status(&status)?))
+    .bind(("category", wallet_category_id(category).await?))
+    .await?;
+    let updated: Option<Wallet> = response.take(0)?;
+    updated.ok_or_else(|| ServerFnError::new("Wallet entry not found"))
+}
+
+/// Every asset with its two latest prices, the last price and the change between them.
+#[cfg(feature = "ssr")]
+pub async fn load_assets() -> Result<Vec<AssetWithPrice>, ServerFnError> {
+    let mut response = get_db()
         .query(
             "SELECT *,
                 (SELECT * FROM price WHERE asset = $parent.id ORDER BY price_date DESC LIMIT 2)
@@ -65,10 +462,10 @@ pub async fn get_assets() -> Result<Vec<AssetWithPrice>, ServerFnError> {
         .await?;
     let mut assets: Vec<AssetWithPrice> = response.take(0)?;
     for item in assets.iter_mut() {
-        let last_price = item.recent_prices.get(0).map(|p| p.price);
-        let price_change_pct = match (item.recent_prices.get(0), item.recent_prices.get(1)) {
-            (Some(latest), Some(prev)) if prev.price != 0.0 => {
-                Some((latest.price - prev.price) / prev.price * 100.0)
+        let last_price = item.recent_prices.first().map(|p| p.price);
+        let price_change_pct = match (item.recent_prices.first(), item.recent_prices.get(1)) {
+            (Some(latest), Some(prev)) if !prev.price.is_zero() => {
+                ((latest.price - prev.price) / prev.price * Decimal::ONE_HUNDRED).to_f64()
             }
             _ => None,
         };
@@ -79,14 +476,630 @@ pub async fn get_assets() -> Result<Vec<AssetWithPrice>, ServerFnError> {
     Ok(assets)
 }
 
+#[server(GetAssets, "/api")]
+pub async fn get_assets() -> Result<Vec<AssetWithPrice>, ServerFnError> {
+    require_user().await?;
+    load_assets().await
+}
+
 #[server(GetTrades, "/api")]
 pub async fn get_trades() -> Result<Vec<Trade>, ServerFnError> {
-    let db = get_db();
+    let user = require_user().await?;
 
-    let mut response = db
-        .query("SELECT * FROM trade ORDER BY trade_date DESC")
-        .await?;
+    let mut response = portfolio_query(
+        &user,
+        "SELECT * FROM trade WHERE owner = $owner AND (!$portfolio OR portfolio = $portfolio)
+         ORDER BY trade_date DESC",
+    )
+    .await?
+    .await?;
     let trades: Vec<Trade> = response.take(0)?;
 
     Ok(trades)
 }
+
+#[cfg(feature = "ssr")]
+fn trade_id(id: &str) -> Result<RecordId, ServerFnError> {
+    let key = id.strip_prefix("trade:").unwrap_or(id).trim();
+    if key.is_empty() {
+        return Err(ServerFnError::new("Missing trade id"));
+    }
+    Ok(RecordId::from_table_key("trade", key))
+}
+
+// Accepts `datetime-local` input values (`2024-05-01T14:30`), full RFC 3339, the same with a
+// space instead of the `T`, or a plain date (ISO or day first, as `31/12/2024` or `31.12.2024`), as UTC.
+#[cfg(feature = "ssr")]
+pub(crate) fn parse_datetime(value: &str) -> Result<Datetime, ServerFnError> {
+    use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+    let value = value.trim();
+    let parsed = DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M").map(|dt| dt.and_utc()))
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S").map(|dt| dt.and_utc()))
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").map(|dt| dt.and_utc()))
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").map(|dt| dt.and_utc()))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .or_else(|_| NaiveDate::parse_from_str(value, "%d/%m/%Y"))
+                .or_else(|_| NaiveDate::parse_from_str(value, "%d.%m.%Y"))
+                .map(|d| d.and_hms_opt(0, 0, 0).expect("midnight").and_utc())
+        })
+        .map_err(|_| ServerFnError::new(format!("Invalid date '{}'", value)))?;
+
+    Ok(Datetime::from(parsed))
+}
+
+#[cfg(feature = "ssr")]
+pub(crate) fn validate_trade(
+    price: Decimal,
+    qty: Decimal,
+    fees: Decimal,
+    precision: u32,
+) -> Result<(), ServerFnError> {
+    if price <= Decimal::ZERO {
+        return Err(ServerFnError::new("Price must be a positive number"));
+    }
+    if price.normalize().scale() > precision {
+        return Err(ServerFnError::new(format!(
+            "This asset is priced with at most {} decimals",
+            precision
+        )));
+    }
+    if qty <= Decimal::ZERO {
+        return Err(ServerFnError::new("Quantity must be a positive number"));
+    }
+    if fees < Decimal::ZERO {
+        return Err(ServerFnError::new("Fees cannot be negative"));
+    }
+    Ok(())
+}
+
+// Price precision of the asset a trade was booked on.
+#[cfg(feature = "ssr")]
+async fn trade_precision(id: &RecordId) -> Result<u32, ServerFnError> {
+    let mut response = get_db()
+        .query("SELECT VALUE asset.precision FROM $id")
+        .bind(("id", id.clone()))
+        .await?;
+    let precisions: Vec<u32> = response.take(0)?;
+    precisions
+        .first()
+        .copied()
+        .ok_or_else(|| ServerFnError::new("Trade not found"))
+}
+
+#[cfg(feature = "ssr")]
+async fn current_trade_status(user: &User, id: &RecordId) -> Result<TradeStatus, ServerFnError> {
+    let mut response = owned_query(user, "SELECT VALUE status FROM $id WHERE owner = $owner")?
+        .bind(("id", id.clone()))
+        .await?;
+    let statuses: Vec<TradeStatus> = response.take(0)?;
+    statuses
+        .first()
+        .copied()
+        .ok_or_else(|| ServerFnError::new("Trade not found"))
+}
+
+#[cfg(feature = "ssr")]
+fn check_transition(from: TradeStatus, to: TradeStatus) -> Result<(), ServerFnError> {
+    if from.can_transition_to(to) {
+        Ok(())
+    } else if from == to {
+        Err(ServerFnError::new(format!("A {} trade can no longer be edited", from)))
+    } else {
+        Err(ServerFnError::new(format!(
+            "Cannot move a trade from {} to {}",
+            from, to
+        )))
+    }
+}
+
+// Id and price precision of an asset that must already exist.
+#[cfg(feature = "ssr")]
+async fn existing_asset(asset: &str) -> Result<(RecordId, u32), ServerFnError> {
+    let key = asset.strip_prefix("asset:").unwrap_or(asset).trim();
+    if key.is_empty() {
+        return Err(ServerFnError::new("Missing asset"));
+    }
+    let id = RecordId::from_table_key("asset", key);
+
+    let mut response = get_db()
+        .query("SELECT VALUE precision FROM $asset")
+        .bind(("asset", id.clone()))
+        .await?;
+    let found: Vec<u32> = response.take(0)?;
+    let precision = found
+        .first()
+        .copied()
+        .ok_or_else(|| ServerFnError::new(format!("Unknown asset '{}'", key)))?;
+
+    Ok((id, precision))
+}
+
+/// Fields of the new-trade form. Sent flattened, so the form keeps one input per field.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TradeInput {
+    pub portfolio: String,
+    pub asset: String,
+    pub side: TradeSide,
+    pub price: Decimal,
+    pub qty: Decimal,
+    pub fees: Decimal,
+    pub status: TradeStatus,
+    pub trade_date: String,
+}
+
+#[server(CreateTrade, "/api")]
+pub async fn create_trade(#[server(flatten)] input: TradeInput) -> Result<Trade, ServerFnError> {
+    let user = require_user().await?;
+    let portfolio = owned_portfolio(&user, &input.portfolio).await?;
+    let (asset, precision) = existing_asset(&input.asset).await?;
+    validate_trade(input.price, input.qty, input.fees, precision)?;
+    if !TradeStatus::INITIAL.contains(&input.status) {
+        return Err(ServerFnError::new(format!("A trade cannot be created as {}", input.status)));
+    }
+    let trade = Trade {
+        id: None,
+        asset,
+        side: input.side,
+        price: input.price,
+        qty: input.qty,
+        fees: input.fees,
+        status: input.status,
+        trade_date: parse_datetime(&input.trade_date)?,
+        portfolio: Some(portfolio),
+        owner: Some(user.id.clone()),
+    };
+
+    let created: Option<Trade> = get_db().create("trade").content(trade).await?;
+    let created = created.ok_or_else(|| ServerFnError::new("Trade was not created"))?;
+    record_lot_matches(&user).await?;
+    Ok(created)
+}
+
+#[server(UpdateTrade, "/api")]
+pub async fn update_trade(
+    id: String,
+    side: TradeSide,
+    price: Decimal,
+    qty: Decimal,
+    fees: Decimal,
+    status: TradeStatus,
+    trade_date: String,
+) -> Result<Trade, ServerFnError> {
+    let user = require_user().await?;
+    let id = trade_id(&id)?;
+    check_transition(current_trade_status(&user, &id).await?, status)?;
+    validate_trade(price, qty, fees, trade_precision(&id).await?)?;
+
+    let mut response = owned_query(
+        &user,
+        "UPDATE $id SET side = $side, price = $price, qty = $qty, fees = $fees, status = $status,
+            trade_date = $trade_date
+         WHERE owner = $owner RETURN AFTER",
+    )?
+    .bind(("id", id.clone()))
+    .bind(("side", side))
+    .bind(("price", price))
+    .bind(("qty", qty))
+    .bind(("fees", fees))
+    .bind(("status", status))
+    .bind(("trade_date", parse_datetime(&trade_date)?))
+    .await?;
+    let updated: Option<Trade> = response.take(0)?;
+    let updated = updated.ok_or_else(|| ServerFnError::new("Trade not found"))?;
+    forget_lot_assignments(&user, &id).await?;
+    record_lot_matches(&user).await?;
+    Ok(updated)
+}
+
+#[server(CancelTrade, "/api")]
+pub async fn cancel_trade(id: String) -> Result<Trade, ServerFnError> {
+    let user = require_user().await?;
+    let id = trade_id(&id)?;
+    check_transition(current_trade_status(&user, &id).await?, TradeStatus::Cancelled)?;
+
+    let mut response = owned_query(&user, "UPDATE $id SET status = $status WHERE owner = $owner RETURN AFTER")?
+        .bind(("id", id.clone()))
+        .bind(("status", TradeStatus::Cancelled))
+        .await?;
+    let cancelled: Option<Trade> = response.take(0)?;
+    let cancelled = cancelled.ok_or_else(|| ServerFnError::new("Trade not found"))?;
+    forget_lot_assignments(&user, &id).await?;
+    record_lot_matches(&user).await?;
+    Ok(cancelled)
+}
+
+#[server(DeleteTrade, "/api")]
+pub async fn delete_trade(id: String) -> Result<(), ServerFnError> {
+    let user = require_user().await?;
+    let id = trade_id(&id)?;
+    let status = current_trade_status(&user, &id).await?;
+    if !status.can_delete() {
+        return Err(ServerFnError::new(format!("A {} trade cannot be deleted", status)));
+    }
+    let mut response = owned_query(&user, "DELETE $id WHERE owner = $owner RETURN BEFORE")?
+        .bind(("id", id.clone()))
+        .await?;
+    let deleted: Option<Trade> = response.take(0)?;
+    deleted.ok_or_else(|| ServerFnError::new("Trade not found"))?;
+    forget_lot_assignments(&user, &id).await?;
+    record_lot_matches(&user).await
+}
+
+#[cfg(all(test, feature = "embedded"))]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Fresh in-memory database with every migration applied.
+    pub(crate) async fn memory_db() -> Surreal<Any> {
+        let config = DbConfig {
+            engine: DbEngine::Memory,
+            namespace: "test".to_string(),
+            database: "test".to_string(),
+        };
+        let db = connect(&config).await.expect("embedded engine starts");
+        migrations::migrate(&db).await.expect("migrations apply");
+        db
+    }
+
+    #[tokio::test]
+    async fn migrates_and_round_trips_a_wallet_entry_on_the_embedded_engine() {
+        let db = memory_db().await;
+        assert_eq!(
+            migrations::current_version(&db).await.unwrap(),
+            migrations::latest_version()
+        );
+        assert!(migrations::migrate(&db).await.unwrap().is_empty());
+
+        let entry = Wallet {
+            id: None,
+            amount: Decimal::new(-1250, 2),
+            ccy: "EUR".to_string(),
+            status: "completed".to_string(),
+            tx_date: Datetime::from(chrono::Utc::now()),
+            note: "Weekly shop".to_string(),
+            category: Some(RecordId::from_table_key("wallet_category", "groceries")),
+            reference: None,
+            owner: None,
+        };
+        let created: Option<Wallet> = db.create("wallet").content(entry).await.unwrap();
+        let id = created.and_then(|w| w.id).expect("entry created");
+
+        let read: Option<Wallet> = db.select(id.clone()).await.unwrap();
+        let read = read.expect("entry read back");
+        assert_eq!(read.amount, Decimal::new(-1250, 2));
+        assert_eq!(read.note, "Weekly shop");
+
+        let mut response = db
+            .query("UPDATE $id SET status = 'cancelled' RETURN AFTER")
+            .bind(("id", id.clone()))
+            .await
+            .unwrap();
+        let updated: Option<Wallet> = response.take(0).unwrap();
+        assert_eq!(updated.expect("entry updated").status, "cancelled");
+
+        let deleted: Option<Wallet> = db.delete(id.clone()).await.unwrap();
+        assert!(deleted.is_some());
+        let gone: Option<Wallet> = db.select(id).await.unwrap();
+        assert!(gone.is_none());
+    }
+
+    /// Runs `test` against the global connection the server functions use. Every such test
+    /// shares one runtime and one database, since the embedded engine's tasks live on the
+    /// runtime that opened it; tests keep apart by signing in as accounts of their own.
+    pub(crate) fn on_global_db<F: std::future::Future>(test: impl FnOnce(&'static Surreal<Any>) -> F) -> F::Output {
+        static RUNTIME: std::sync::LazyLock<tokio::runtime::Runtime> = std::sync::LazyLock::new(|| {
+            let runtime = tokio::runtime::Builder::new_multi_thread()
+                .enable_all()
+                .build()
+                .expect("test runtime starts");
+            runtime.block_on(async {
+                let db = memory_db().await;
+                db.query(
+                    "CREATE category:stocks CONTENT { name: 'Stocks' };
+                     CREATE risk:mid CONTENT { name: 'Medium', risk_score: 4 };
+                     CREATE asset:aapl CONTENT {
+                        symbol: 'AAPL', ccy: 'EUR', category: category:stocks, risk: risk:mid
+                     };",
+                )
+                .await
+                .unwrap()
+                .check()
+                .unwrap();
+                DB.set(db).expect("global database set once");
+            });
+            runtime
+        });
+        RUNTIME.block_on(test(get_db()))
+    }
+
+    pub(crate) async fn create_user(db: &Surreal<Any>, username: &str) -> User {
+        let mut response = db
+            .query(
+                "CREATE ONLY user CONTENT {
+                    username: $username, display_name: $username, password_hash: 'unused'
+                 } RETURN id, username, display_name",
+            )
+            .bind(("username", username.to_string()))
+            .await
+            .unwrap();
+        let user: Option<User> = response.take(0).unwrap();
+        user.expect("user created")
+    }
+
+    async fn create_rows(db: &Surreal<Any>, owner: Option<&User>, note: &str) {
+        db.query(
+            "CREATE trade CONTENT {
+                asset: asset:aapl, side: 'buy', price: 100, qty: 1, status: 'open',
+                trade_date: time::now(), owner: $owner
+             };
+             CREATE wallet CONTENT {
+                amount: 100, ccy: 'EUR', status: 'completed', tx_date: time::now(),
+                note: $note, owner: $owner
+             };",
+        )
+        .bind(("owner", owner.map(|user| user.id.clone())))
+        .bind(("note", note.to_string()))
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+    }
+
+    async fn wallet_notes(user: &User) -> Vec<String> {
+        let mut response = owned_query(user, "SELECT VALUE note FROM wallet WHERE owner = $owner ORDER BY note")
+            .unwrap()
+            .await
+            .unwrap();
+        response.take(0).unwrap()
+    }
+
+    /// Cookie header of a fresh session for `user`.
+    pub(crate) async fn sign_in_as(db: &Surreal<Any>, user: &User) -> String {
+        let token = format!("token-{}", user.username);
+        db.query("CREATE session CONTENT { token: $session_token, user: $user, expires_at: time::now() + 1d }")
+            .bind(("session_token", token.clone()))
+            .bind(("user", user.id.clone()))
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+        format!("{}={}", crate::backend::auth::SESSION_COOKIE, token)
+    }
+
+    /// Runs a server function as a request carrying `cookie`, as the axum integration does.
+    pub(crate) async fn call_as<T>(cookie: &str, call: impl std::future::Future<Output = T>) -> T {
+        use leptos::prelude::{provide_context, Owner};
+        use leptos::reactive::computed::ScopedFuture;
+
+        let (parts, ()) = axum::http::Request::builder()
+            .header(axum::http::header::COOKIE, cookie)
+            .body(())
+            .unwrap()
+            .into_parts();
+        let owner = Owner::new();
+        owner
+            .with(|| {
+                provide_context(parts);
+                provide_context(leptos_axum::ResponseOptions::default());
+                ScopedFuture::new(call)
+            })
+            .await
+    }
+
+    pub(crate) async fn export_as(cookie: &str, file: &str) -> String {
+        use axum::extract::Path;
+        use axum::http::{header, HeaderMap, HeaderValue};
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::COOKIE, HeaderValue::from_str(cookie).unwrap());
+        let response = crate::backend::export::export_file(Path(file.to_string()), headers).await;
+        assert!(response.status().is_success(), "{} failed", file);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn owned_queries_must_filter_on_the_owner() {
+        for sql in [
+            "SELECT * FROM trade WHERE owner = $owner AND (!$portfolio OR portfolio = $portfolio)",
+            "SELECT * FROM wallet WHERE status != 'cancelled' AND owner = $owner ORDER BY tx_date",
+            "UPDATE $id SET status = $status WHERE owner = $owner RETURN AFTER",
+            "UPSERT $setting SET lot_method = $method, owner = $owner WHERE owner = $owner",
+            "BEGIN TRANSACTION; DELETE $id WHERE owner = $owner; COMMIT TRANSACTION;",
+            "LET $found = (SELECT VALUE id FROM portfolio WHERE owner = $owner AND name = $name)",
+            "CREATE ONLY portfolio CONTENT { name: $name, owner: $owner }",
+            "INSERT INTO lot_assignment $added",
+            "SELECT * FROM asset",
+        ] {
+            assert!(check_owner_scope(sql).is_ok(), "{}", sql);
+        }
+        for sql in [
+            "SELECT * FROM trade",
+            "SELECT * FROM trade WHERE owner = $user",
+            "SELECT * FROM wallet WHERE owner = $owner OR status = 'completed'",
+            "SELECT * FROM wallet WHERE owner != $owner",
+            "DELETE $id",
+            "UPDATE trade:abc SET qty = 1",
+            "UPSERT $setting SET owner = $owner",
+            "SELECT * FROM asset, trade WHERE symbol = 'AAPL'",
+            "SELECT * FROM type::table($table) WHERE symbol = 'AAPL'",
+            "LET $all = (SELECT * FROM trade)",
+            "RETURN (SELECT * FROM trade)",
+            "IF true { DELETE trade }",
+            "INSERT INTO trade $rows ON DUPLICATE KEY UPDATE qty = 0",
+            "SELECT * FROM trade WHERE owner = $owner; DELETE wallet;",
+        ] {
+            assert!(check_owner_scope(sql).is_err(), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn transitions_are_checked_against_the_lifecycle() {
+        use TradeStatus::*;
+        for from in TradeStatus::ALL {
+            for to in TradeStatus::ALL {
+                let allowed = matches!(
+                    (from, to),
+                    (Pending, Pending) | (Pending, Open) | (Pending, Cancelled) | (Open, Open) | (Open, Closed)
+                );
+                assert_eq!(check_transition(from, to).is_ok(), allowed, "{} -> {}", from, to);
+            }
+        }
+        assert_eq!(
+            check_transition(Closed, Closed).unwrap_err().to_string(),
+            ServerFnError::new("A closed trade can no longer be edited").to_string()
+        );
+        assert_eq!(
+            check_transition(Open, Pending).unwrap_err().to_string(),
+            ServerFnError::new("Cannot move a trade from open to pending").to_string()
+        );
+        assert_eq!(TradeStatus::ALL.map(TradeStatus::can_delete), [true, true, false, true]);
+    }
+
+    #[test]
+    fn trades_follow_their_lifecycle_through_the_server_functions() {
+        use crate::backend::portfolios::{ensure_portfolio, DEFAULT_PORTFOLIO};
+
+        on_global_db(|db| async move {
+            let user = create_user(db, "lifecycle").await;
+            let portfolio = ensure_portfolio(db, &user.id, DEFAULT_PORTFOLIO).await.unwrap();
+            let session = sign_in_as(db, &user).await;
+            let create = |status| {
+                create_trade(TradeInput {
+                    portfolio: portfolio.to_string(),
+                    asset: "asset:aapl".to_string(),
+                    side: TradeSide::Buy,
+                    price: Decimal::from(100),
+                    qty: Decimal::ONE,
+                    fees: Decimal::ZERO,
+                    status,
+                    trade_date: "2025-01-02".to_string(),
+                })
+            };
+            let key = |trade: &Trade| trade.id.clone().unwrap().to_string();
+            let update = |trade: &Trade, status| {
+                update_trade(
+                    key(trade),
+                    TradeSide::Buy,
+                    Decimal::from(100),
+                    Decimal::ONE,
+                    Decimal::ZERO,
+                    status,
+                    "2025-01-02".to_string(),
+                )
+            };
+
+            assert!(call_as(&session, create(TradeStatus::Cancelled)).await.is_err());
+            let trade = call_as(&session, create(TradeStatus::Pending)).await.unwrap();
+            let trade = call_as(&session, update(&trade, TradeStatus::Open)).await.unwrap();
+            assert!(call_as(&session, cancel_trade(key(&trade))).await.is_err());
+            let trade = call_as(&session, update(&trade, TradeStatus::Closed)).await.unwrap();
+            assert!(call_as(&session, update(&trade, TradeStatus::Closed)).await.is_err());
+
+            // A closed trade stays; cancelled and open ones may be deleted.
+            assert!(call_as(&session, delete_trade(key(&trade))).await.is_err());
+            let pending = call_as(&session, create(TradeStatus::Pending)).await.unwrap();
+            let cancelled = call_as(&session, cancel_trade(key(&pending))).await.unwrap();
+            call_as(&session, delete_trade(key(&cancelled))).await.unwrap();
+            let open = call_as(&session, create(TradeStatus::Open)).await.unwrap();
+            call_as(&session, delete_trade(key(&open))).await.unwrap();
+            let left: Vec<RecordId> = call_as(&session, get_trades())
+                .await
+                .unwrap()
+                .into_iter()
+                .filter_map(|t| t.id)
+                .collect();
+            assert_eq!(left, vec![trade.id.clone().unwrap()]);
+        });
+    }
+
+    #[test]
+    fn server_functions_only_reach_the_signed_in_account() {
+        use crate::backend::positions::get_positions;
+
+        on_global_db(|db| async move {
+            // Rows written before accounts existed stay unowned until handed over.
+            create_rows(db, None, "legacy").await;
+            let alice = create_user(db, "alice").await;
+            let bob = create_user(db, "bob").await;
+            assert!(wallet_notes(&alice).await.is_empty());
+            let assigned = assign_owner(db, "Alice").await.unwrap();
+            assert_eq!((assigned.trades, assigned.wallet), (1, 1));
+            assert!(assign_owner(db, "carol").await.is_err());
+            let main = ensure_portfolio(db, &alice.id, DEFAULT_PORTFOLIO).await.unwrap();
+
+            create_rows(db, Some(&alice), "alice").await;
+            create_rows(db, Some(&bob), "bob").await;
+            let as_alice = sign_in_as(db, &alice).await;
+            let as_bob = sign_in_as(db, &bob).await;
+
+            assert!(call_as("", get_trades()).await.is_err());
+            assert!(call_as("resume_session=forged", get_wallet()).await.is_err());
+
+            let trades = call_as(&as_alice, get_trades()).await.unwrap();
+            assert_eq!(trades.len(), 2);
+            assert!(trades.iter().all(|t| t.owner.as_ref() == Some(&alice.id)));
+            assert!(trades.iter().any(|t| t.portfolio.as_ref() == Some(&main)));
+            let wallet = call_as(&as_bob, get_wallet()).await.unwrap();
+            let notes: Vec<&str> = wallet.iter().map(|w| w.note.as_str()).collect();
+            assert_eq!(notes, vec!["bob"]);
+
+            let positions = call_as(&as_alice, get_positions()).await.unwrap();
+            let held: Decimal = positions.positions.iter().map(|p| p.qty).sum();
+            assert_eq!(held, Decimal::from(2));
+            let positions = call_as(&as_bob, get_positions()).await.unwrap();
+            let held: Decimal = positions.positions.iter().map(|p| p.qty).sum();
+            assert_eq!(held, Decimal::ONE);
+
+            // Another account's trade is "not found", and stays where it was.
+            let bobs = call_as(&as_bob, get_trades()).await.unwrap();
+            let bobs_trade = bobs[0].id.clone().expect("stored trade has an id").to_string();
+            assert!(call_as(&as_alice, delete_trade(bobs_trade.clone())).await.is_err());
+            assert_eq!(call_as(&as_bob, get_trades()).await.unwrap().len(), 1);
+            call_as(&as_bob, delete_trade(bobs_trade)).await.unwrap();
+            assert!(call_as(&as_bob, get_trades()).await.unwrap().is_empty());
+
+            let export = export_as(&as_alice, "wallet.csv").await;
+            assert!(export.contains("alice") && export.contains("legacy"));
+            assert!(!export.contains("bob"));
+            let export = export_as(&as_bob, "wallet.json").await;
+            assert!(export.contains("bob") && !export.contains("alice"));
+            let export = export_as(&as_bob, "trades.csv").await;
+            assert_eq!(export.lines().count(), 1, "only the header row is left");
+        });
+    }
+
+    #[test]
+    fn cash_without_a_rate_is_left_out_of_the_totals() {
+        use crate::backend::dashboard::get_dashboard;
+
+        on_global_db(|db| async move {
+            let user = create_user(db, "yen-saver").await;
+            let session = sign_in_as(db, &user).await;
+            db.query(
+                "CREATE wallet CONTENT {
+                    amount: 120.5, ccy: 'EUR', status: 'completed', tx_date: time::now(), note: '', owner: $owner
+                 };
+                 CREATE wallet CONTENT {
+                    amount: 5000, ccy: 'JPY', status: 'completed', tx_date: time::now(), note: '', owner: $owner
+                 };",
+            )
+            .bind(("owner", user.id.clone()))
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+
+            let total = call_as(&session, get_wallet_total()).await.unwrap();
+            assert_eq!(total.total, Money::new(Decimal::new(1205, 1), "EUR"));
+            assert_eq!(total.unconverted, vec!["JPY".to_string()]);
+            let dashboard = call_as(&session, get_dashboard()).await.unwrap();
+            assert_eq!(dashboard.cash, total.total);
+            assert!(dashboard.unconverted_note.unwrap().contains("JPY"));
+        });
+    }
+}
@@ -0,0 +1,488 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+use crate::backend::auth::{user_from_headers, User, NOT_SIGNED_IN};
+#[cfg(feature = "ssr")]
+use crate::backend::db::owned_query;
+#[cfg(feature = "ssr")]
+use crate::backend::model::{Decimal, TradeSide, TradeStatus};
+#[cfg(feature = "ssr")]
+use crate::backend::portfolios::portfolio_query;
+#[cfg(feature = "ssr")]
+use crate::backend::positions::positions_report;
+#[cfg(feature = "ssr")]
+use axum::extract::Path;
+#[cfg(feature = "ssr")]
+use axum::http::{header, HeaderMap, StatusCode};
+#[cfg(feature = "ssr")]
+use axum::response::{IntoResponse, Response};
+#[cfg(feature = "ssr")]
+use leptos::prelude::ServerFnError;
+#[cfg(feature = "ssr")]
+use surrealdb::Datetime;
+
+/// Columns of a trades export, in order. The names are the ones the trade import
+/// recognises, so an export maps itself when imported again.
+pub const TRADE_COLUMNS: &[&str] = &["date", "symbol", "side", "qty", "price", "fees", "status", "portfolio"];
+
+/// Columns of a positions export, in order. Amounts are in the asset's currency;
+/// `fx_rate` converts them into `reporting_currency`.
+pub const POSITION_COLUMNS: &[&str] = &[
+    "symbol",
+    "currency",
+    "qty",
+    "avg_cost",
+    "cost_basis",
+    "last_price",
+    "market_value",
+    "unrealized_pnl",
+    "realized_pnl",
+    "fx_rate",
+    "reporting_currency",
+];
+
+/// Columns of a wallet export, in order. The names are the ones the bank CSV
+/// import recognises.
+pub const WALLET_COLUMNS: &[&str] = &["date", "amount", "currency", "description", "reference", "status", "category"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportDataset {
+    Trades,
+    Positions,
+    Wallet,
+}
+
+impl ExportDataset {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ExportDataset::Trades => "trades",
+            ExportDataset::Positions => "positions",
+            ExportDataset::Wallet => "wallet",
+        }
+    }
+
+    pub fn columns(self) -> &'static [&'static str] {
+        match self {
+            ExportDataset::Trades => TRADE_COLUMNS,
+            ExportDataset::Positions => POSITION_COLUMNS,
+            ExportDataset::Wallet => WALLET_COLUMNS,
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<ExportDataset> {
+        [ExportDataset::Trades, ExportDataset::Positions, ExportDataset::Wallet]
+            .into_iter()
+            .find(|d| d.as_str() == value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Xlsx,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [ExportFormat::Csv, ExportFormat::Json, ExportFormat::Xlsx];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Xlsx => "xlsx",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Json => "JSON",
+            ExportFormat::Xlsx => "Excel",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Json => "application/json",
+            ExportFormat::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<ExportFormat> {
+        ExportFormat::ALL.into_iter().find(|f| f.extension() == value)
+    }
+}
+
+/// Download link of a dataset, served by [`export_file`].
+pub fn export_url(dataset: ExportDataset, format: ExportFormat) -> String {
+    format!("/export/{}.{}", dataset.as_str(), format.extension())
+}
+
+// A cell keeps numbers apart from text so spreadsheets get real numbers.
+#[cfg(feature = "ssr")]
+enum Cell {
+    Text(String),
+    Number(Decimal),
+    Empty,
+}
+
+#[cfg(feature = "ssr")]
+impl Cell {
+    fn text(value: impl Into<String>) -> Cell {
+        Cell::Text(value.into())
+    }
+
+    fn number(value: Option<Decimal>) -> Cell {
+        value.map(|v| Cell::Number(v.normalize())).unwrap_or(Cell::Empty)
+    }
+
+    fn date(value: &Datetime) -> Cell {
+        Cell::Text(value.into_inner_ref().to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+    }
+
+    fn to_text(&self) -> String {
+        match self {
+            Cell::Text(text) => text.clone(),
+            Cell::Number(value) => value.to_string(),
+            Cell::Empty => String::new(),
+        }
+    }
+
+    // Text a spreadsheet would read as a formula gets a leading apostrophe, which it
+    // shows as plain text. Numbers stay as they are, so negative amounts keep their sign.
+    fn to_csv_text(&self) -> String {
+        match self {
+            Cell::Text(text) if text.starts_with(['=', '+', '-', '@', '\t', '\r']) => format!("'{}", text),
+            cell => cell.to_text(),
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+type Rows = Vec<Vec<Cell>>;
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Deserialize)]
+struct TradeExportRow {
+    trade_date: Datetime,
+    symbol: Option<String>,
+    side: TradeSide,
+    qty: Decimal,
+    price: Decimal,
+    fees: Decimal,
+    status: TradeStatus,
+    portfolio: Option<String>,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Deserialize)]
+struct WalletExportRow {
+    tx_date: Datetime,
+    amount: Decimal,
+    ccy: String,
+    note: String,
+    reference: Option<String>,
+    status: String,
+    category: Option<String>,
+}
+
+/// Trades as listed on the Portfolio page: the selected portfolio, newest first.
+#[cfg(feature = "ssr")]
+async fn trade_rows(user: &User) -> Result<Rows, ServerFnError> {
+    let mut response = portfolio_query(
+        user,
+        "SELECT trade_date, asset.symbol AS symbol, side, qty, price, fees, status,
+            portfolio.name AS portfolio
+         FROM trade WHERE owner = $owner AND (!$portfolio OR portfolio = $portfolio)
+         ORDER BY trade_date DESC",
+    )
+    .await?
+    .await?;
+    let trades: Vec<TradeExportRow> = response.take(0)?;
+
+    Ok(trades
+        .into_iter()
+        .map(|t| {
+            vec![
+                Cell::date(&t.trade_date),
+                Cell::text(t.symbol.unwrap_or_default()),
+                Cell::text(t.side.as_str()),
+                Cell::number(Some(t.qty)),
+                Cell::number(Some(t.price)),
+                Cell::number(Some(t.fees)),
+                Cell::text(t.status.as_str()),
+                Cell::text(t.portfolio.unwrap_or_default()),
+            ]
+        })
+        .collect())
+}
+
+#[cfg(feature = "ssr")]
+async fn position_rows(user: &User) -> Result<Rows, ServerFnError> {
+    let report = positions_report(user).await?;
+
+    Ok(report
+        .positions
+        .into_iter()
+        .map(|p| {
+            vec![
+                Cell::text(p.symbol),
+                Cell::text(p.ccy),
+                Cell::number(Some(p.qty)),
+                Cell::number(Some(p.avg_cost)),
+                Cell::number(Some(p.cost_basis)),
+                Cell::number(p.last_price),
+                Cell::number(p.market_value),
+                Cell::number(p.unrealized_pnl),
+                Cell::number(Some(p.realized_pnl)),
+                Cell::number(p.fx_rate),
+                Cell::text(report.reporting_ccy.clone()),
+            ]
+        })
+        .collect())
+}
+
+#[cfg(feature = "ssr")]
+async fn wallet_rows(user: &User) -> Result<Rows, ServerFnError> {
+    let mut response = owned_query(
+        user,
+        "SELECT tx_date, amount, ccy, note, reference, status, category.name AS category
+         FROM wallet WHERE owner = $owner ORDER BY tx_date DESC",
+    )?
+    .await?;
+    let entries: Vec<WalletExportRow> = response.take(0)?;
+
+    Ok(entries
+        .into_iter()
+        .map(|w| {
+            vec![
+                Cell::date(&w.tx_date),
+                Cell::number(Some(w.amount)),
+                Cell::text(w.ccy),
+                Cell::text(w.note),
+                Cell::text(w.reference.unwrap_or_default()),
+                Cell::text(w.status),
+                Cell::text(w.category.unwrap_or_default()),
+            ]
+        })
+        .collect())
+}
+
+#[cfg(feature = "ssr")]
+fn write_csv(columns: &[&str], rows: &Rows) -> Result<Vec<u8>, ServerFnError> {
+    let failed = |e: csv::Error| ServerFnError::new(format!("Cannot write CSV: {}", e));
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(columns).map_err(failed)?;
+    for row in rows {
+        writer.write_record(row.iter().map(Cell::to_csv_text)).map_err(failed)?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| ServerFnError::new(format!("Cannot write CSV: {}", e)))
+}
+
+/// An array of objects keyed by column. Numbers are strings so they keep their exact
+/// decimal value; empty cells are `null`.
+#[cfg(feature = "ssr")]
+fn write_json(columns: &[&str], rows: &Rows) -> Result<Vec<u8>, ServerFnError> {
+    let objects: Vec<serde_json::Map<String, serde_json::Value>> = rows
+        .iter()
+        .map(|row| {
+            columns
+                .iter()
+                .zip(row)
+                .map(|(column, cell)| {
+                    let value = match cell {
+                        Cell::Empty => serde_json::Value::Null,
+                        cell => serde_json::Value::String(cell.to_text()),
+                    };
+                    (column.to_string(), value)
+                })
+                .collect()
+        })
+        .collect();
+    serde_json::to_vec_pretty(&objects).map_err(|e| ServerFnError::new(format!("Cannot write JSON: {}", e)))
+}
+
+#[cfg(feature = "ssr")]
+fn write_xlsx(sheet: &str, columns: &[&str], rows: &Rows) -> Result<Vec<u8>, ServerFnError> {
+    use rust_decimal::prelude::ToPrimitive;
+    use rust_xlsxwriter::{Format, Workbook, XlsxError};
+
+    let failed = |e: XlsxError| ServerFnError::new(format!("Cannot write XLSX: {}", e));
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name(sheet).map_err(failed)?;
+    let bold = Format::new().set_bold();
+    for (col, column) in columns.iter().enumerate() {
+        worksheet.write_string_with_format(0, col as u16, *column, &bold).map_err(failed)?;
+    }
+    for (index, row) in rows.iter().enumerate() {
+        let line = index as u32 + 1;
+        for (col, cell) in row.iter().enumerate() {
+            match cell {
+                Cell::Text(text) => {
+                    worksheet.write_string(line, col as u16, text).map_err(failed)?;
+                }
+                Cell::Number(value) => {
+                    worksheet
+                        .write_number(line, col as u16, value.to_f64().unwrap_or_default())
+                        .map_err(failed)?;
+                }
+                Cell::Empty => {}
+            }
+        }
+    }
+    worksheet.set_freeze_panes(1, 0).map_err(failed)?;
+    workbook.save_to_buffer().map_err(failed)
+}
+
+#[cfg(feature = "ssr")]
+async fn export_bytes(
+    user: &User,
+    dataset: ExportDataset,
+    format: ExportFormat,
+) -> Result<Vec<u8>, ServerFnError> {
+    let rows = match dataset {
+        ExportDataset::Trades => trade_rows(user).await?,
+        ExportDataset::Positions => position_rows(user).await?,
+        ExportDataset::Wallet => wallet_rows(user).await?,
+    };
+    let columns = dataset.columns();
+    match format {
+        ExportFormat::Csv => write_csv(columns, &rows),
+        ExportFormat::Json => write_json(columns, &rows),
+        ExportFormat::Xlsx => write_xlsx(dataset.as_str(), columns, &rows),
+    }
+}
+
+/// `GET /export/{dataset}.{format}`: the signed-in user's trades, positions or wallet
+/// entries as a CSV, JSON or XLSX download. Trades and positions follow the
+/// portfolio selection, like the Portfolio page. The file is built in full before it
+/// is sent, since XLSX cannot be written row by row.
+#[cfg(feature = "ssr")]
+pub async fn export_file(Path(file): Path<String>, headers: HeaderMap) -> Response {
+    let parsed = file
+        .split_once('.')
+        .and_then(|(dataset, format)| Some((ExportDataset::parse(dataset)?, ExportFormat::parse(format)?)));
+    let Some((dataset, format)) = parsed else {
+        return (StatusCode::NOT_FOUND, "Unknown export").into_response();
+    };
+
+    let user = match user_from_headers(&headers).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return (StatusCode::UNAUTHORIZED, NOT_SIGNED_IN).into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    match export_bytes(&user, dataset, format).await {
+        Ok(bytes) => {
+            let filename = format!(
+                "{}-{}.{}",
+                dataset.as_str(),
+                chrono::Utc::now().format("%Y-%m-%d"),
+                format.extension()
+            );
+            (
+                [
+                    (header::CONTENT_TYPE, format.content_type().to_string()),
+                    (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+                ],
+                bytes,
+            )
+                .into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_text_that_looks_like_a_formula_is_escaped() {
+        let rows = vec![vec![
+            Cell::text("=HYPERLINK(\"http://example.com\")"),
+            Cell::text("+1"),
+            Cell::text("-fee"),
+            Cell::text("@SUM(A1)"),
+            Cell::text("Salary"),
+            Cell::number(Some(Decimal::new(-1250, 2))),
+            Cell::Empty,
+        ]];
+        let csv = String::from_utf8(write_csv(&["a", "b", "c", "d", "e", "f", "g"], &rows).unwrap()).unwrap();
+        assert_eq!(
+            csv.lines().nth(1).unwrap(),
+            "\"'=HYPERLINK(\"\"http://example.com\"\")\",'+1,'-fee,'@SUM(A1),Salary,-12.5,"
+        );
+    }
+
+    #[cfg(feature = "embedded")]
+    #[test]
+    fn exported_trades_import_again_as_the_same_trades() {
+        use crate::backend::db::tests::{call_as, create_user, export_as, on_global_db, sign_in_as};
+        use crate::backend::db::{create_trade, get_trades, TradeInput};
+        use crate::backend::model::Trade;
+        use crate::backend::portfolios::{ensure_portfolio, DEFAULT_PORTFOLIO};
+        use crate::backend::trade_import::{import_trades, preview_trade_import, read_trade_csv};
+
+        on_global_db(|db| async move {
+            let user = create_user(db, "exporter").await;
+            let main = ensure_portfolio(db, &user.id, DEFAULT_PORTFOLIO).await.unwrap();
+            let session = sign_in_as(db, &user).await;
+            for (side, price, qty, fees, date) in [
+                (TradeSide::Buy, Decimal::new(10125, 2), Decimal::new(15, 1), Decimal::new(199, 2), "2025-01-02T09:30:00Z"),
+                (TradeSide::Sell, Decimal::new(110, 0), Decimal::ONE, Decimal::ZERO, "2025-02-03T15:00:00Z"),
+            ] {
+                call_as(
+                    &session,
+                    create_trade(TradeInput {
+                        portfolio: main.to_string(),
+                        asset: "asset:aapl".to_string(),
+                        side,
+                        price,
+                        qty,
+                        fees,
+                        status: TradeStatus::Open,
+                        trade_date: date.to_string(),
+                    }),
+                )
+                .await
+                .unwrap();
+            }
+            let csv = export_as(&session, "trades.csv").await;
+
+            // The header maps itself, and every line is already in the portfolio.
+            let columns = call_as(&session, read_trade_csv(csv.clone())).await.unwrap();
+            assert_eq!(columns.rows, 2);
+            let preview = call_as(&session, preview_trade_import(csv.clone(), main.to_string(), columns.mapping.clone()))
+                .await
+                .unwrap();
+            assert_eq!((preview.new, preview.duplicates, preview.invalid), (0, 2, 0));
+
+            let copy = ensure_portfolio(db, &user.id, "Copy").await.unwrap();
+            let imported = call_as(&session, import_trades(csv, copy.to_string(), columns.mapping))
+                .await
+                .unwrap();
+            assert_eq!((imported.created, imported.duplicates), (2, 0));
+
+            let trades = call_as(&session, get_trades()).await.unwrap();
+            let fields = |portfolio: &surrealdb::RecordId| {
+                let mut fields: Vec<_> = trades
+                    .iter()
+                    .filter(|t| t.portfolio.as_ref() == Some(portfolio))
+                    .map(|t: &Trade| (t.asset.clone(), t.side, t.price, t.qty, t.fees, t.trade_date.clone()))
+                    .collect();
+                fields.sort_by(|a, b| a.5.cmp(&b.5));
+                fields
+            };
+            assert_eq!(fields(&main).len(), 2);
+            assert_eq!(fields(&copy), fields(&main));
+        });
+    }
+}
@@ -0,0 +1,237 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use surrealdb::Datetime;
+
+use crate::backend::currency::normalize_ccy;
+use crate::backend::model::{Decimal, FxRate, Money};
+
+/// Currency used to triangulate pairs that have no direct or inverse rate.
+pub const PIVOT_CCY: &str = "EUR";
+
+/// Rate applied for one conversion. `rate_date` is the date of the oldest
+/// rate involved when the conversion went through the pivot currency.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FxQuote {
+    pub from: String,
+    pub to: String,
+    pub rate: Decimal,
+    pub rate_date: Datetime,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FxError {
+    UnknownCurrency(String),
+    NoRate { from: String, to: String },
+}
+
+impl fmt::Display for FxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FxError::UnknownCurrency(code) => {
+                write!(f, "'{}' is not an ISO 4217 currency code", code)
+            }
+            FxError::NoRate { from, to } => write!(
+                f,
+                "no {}/{} rate available for that date (directly or via {})",
+                from, to, PIVOT_CCY
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FxError {}
+
+/// Dated FX rates for arbitrary pairs, answering "what was X/Y on date D".
+#[derive(Debug, Clone, Default)]
+pub struct FxTable {
+    rates: Vec<FxRate>,
+}
+
+impl FxTable {
+    pub fn new(mut rates: Vec<FxRate>) -> Self {
+        for rate in rates.iter_mut() {
+            rate.base = rate.base.trim().to_ascii_uppercase();
+            rate.quote = rate.quote.trim().to_ascii_uppercase();
+        }
+        rates.retain(|r| r.rate > Decimal::ZERO);
+        rates.sort_by(|a, b| a.rate_date.cmp(&b.rate_date));
+        FxTable { rates }
+    }
+
+    // Latest rate on or before `at` quoted either way round, as (rate, date) for from -> to.
+    // On the same date the pair as quoted wins over its inverse.
+    fn direct(&self, from: &str, to: &str, at: &Datetime) -> Option<(Decimal, Datetime)> {
+        self.rates
+            .iter()
+            .filter(|r| r.rate_date <= *at)
+            .filter_map(|r| {
+                if r.base == from && r.quote == to {
+                    Some((&r.rate_date, true, r.rate))
+                } else if r.base == to && r.quote == from {
+                    Some((&r.rate_date, false, Decimal::ONE / r.rate))
+                } else {
+                    None
+                }
+            })
+            .max_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)))
+            .map(|(date, _, rate)| (rate, date.clone()))
+    }
+
+    /// Rate effective at `at` to turn one unit of `from` into `to`.
+    pub fn quote(&self, from: &str, to: &str, at: &Datetime) -> Result<FxQuote, FxError> {
+        let from = normalize_ccy(from).ok_or_else(|| FxError::UnknownCurrency(from.trim().to_string()))?;
+        let to = normalize_ccy(to).ok_or_else(|| FxError::UnknownCurrency(to.trim().to_string()))?;
+
+        let found = if from == to {
+            Some((Decimal::ONE, at.clone()))
+        } else if let Some(direct) = self.direct(&from, &to, at) {
+            Some(direct)
+        } else {
+            match (self.direct(&from, PIVOT_CCY, at), self.direct(PIVOT_CCY, &to, at)) {
+                (Some((first, first_date)), Some((second, second_date))) => {
+                    Some((first * second, first_date.min(second_date)))
+                }
+                _ => None,
+            }
+        };
+
+        let (rate, rate_date) = found.ok_or_else(|| FxError::NoRate {
+            from: from.clone(),
+            to: to.clone(),
+        })?;
+        Ok(FxQuote {
+            from,
+            to,
+            rate,
+            rate_date,
+        })
+    }
+
+    pub fn convert(&self, money: &Money, to: &str, at: &Datetime) -> Result<Money, FxError> {
+        let quote = self.quote(&money.ccy, to, at)?;
+        Ok(money.convert(quote.rate, &quote.to))
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub async fn load_fx_table() -> Result<FxTable, leptos::prelude::ServerFnError> {
+    let mut response = crate::backend::db::get_db()
+        .query("SELECT * FROM fx_rate ORDER BY rate_date ASC")
+        .await?;
+    let rates: Vec<FxRate> = response.take(0)?;
+
+    Ok(FxTable::new(rates))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use std::str::FromStr;
+
+    fn day(date: &str) -> Datetime {
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+        Datetime::from(date.and_hms_opt(12, 0, 0).unwrap().and_utc())
+    }
+
+    fn rate(base: &str, quote: &str, rate: &str, date: &str) -> FxRate {
+        FxRate {
+            id: None,
+            base: base.to_string(),
+            quote: quote.to_string(),
+            rate: Decimal::from_str(rate).unwrap(),
+            rate_date: day(date),
+        }
+    }
+
+    fn dec(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
+
+    #[test]
+    fn same_currency_is_one() {
+        let quote = FxTable::default().quote("usd", "USD", &day("2024-01-01")).unwrap();
+        assert_eq!(quote.rate, Decimal::ONE);
+        assert_eq!(quote.from, "USD");
+    }
+
+    #[test]
+    fn direct_rate_wins_over_inverse_and_pivot() {
+        let table = FxTable::new(vec![
+            rate("USD", "GBP", "0.80", "2024-01-01"),
+            rate("GBP", "USD", "1.20", "2024-01-01"),
+            rate("EUR", "USD", "1.10", "2024-01-01"),
+            rate("EUR", "GBP", "0.85", "2024-01-01"),
+        ]);
+        let quote = table.quote("USD", "GBP", &day("2024-01-02")).unwrap();
+        assert_eq!(quote.rate, dec("0.80"));
+    }
+
+    #[test]
+    fn newer_inverse_rate_wins_over_older_direct_rate() {
+        let table = FxTable::new(vec![
+            rate("USD", "GBP", "0.80", "2024-01-01"),
+            rate("GBP", "USD", "1.25", "2024-01-02"),
+        ]);
+        let quote = table.quote("USD", "GBP", &day("2024-01-03")).unwrap();
+        assert_eq!(quote.rate, Decimal::ONE / dec("1.25"));
+        assert_eq!(quote.rate_date, day("2024-01-02"));
+    }
+
+    #[test]
+    fn inverse_rate_is_used_before_the_pivot() {
+        let table = FxTable::new(vec![
+            rate("GBP", "USD", "1.25", "2024-01-01"),
+            rate("EUR", "USD", "1.10", "2024-01-01"),
+            rate("EUR", "GBP", "0.85", "2024-01-01"),
+        ]);
+        let quote = table.quote("USD", "GBP", &day("2024-01-02")).unwrap();
+        assert_eq!(quote.rate, Decimal::ONE / dec("1.25"));
+    }
+
+    #[test]
+    fn pivots_through_eur_and_reports_the_older_date() {
+        let table = FxTable::new(vec![
+            rate("EUR", "USD", "1.10", "2024-01-01"),
+            rate("EUR", "CHF", "0.95", "2024-01-03"),
+        ]);
+        let quote = table.quote("USD", "CHF", &day("2024-01-05")).unwrap();
+        assert_eq!(quote.rate, Decimal::ONE / dec("1.10") * dec("0.95"));
+        assert_eq!(quote.rate_date, day("2024-01-01"));
+    }
+
+    #[test]
+    fn picks_the_latest_rate_on_or_before_the_date() {
+        let table = FxTable::new(vec![
+            rate("EUR", "USD", "1.12", "2024-03-01"),
+            rate("EUR", "USD", "1.08", "2024-01-01"),
+            rate("EUR", "USD", "1.10", "2024-02-01"),
+        ]);
+        let on = |date: &str| table.quote("EUR", "USD", &day(date)).map(|q| q.rate);
+        assert_eq!(on("2024-01-15"), Ok(dec("1.08")));
+        assert_eq!(on("2024-02-01"), Ok(dec("1.10")));
+        assert_eq!(on("2024-12-31"), Ok(dec("1.12")));
+        assert_eq!(
+            on("2023-12-31"),
+            Err(FxError::NoRate {
+                from: "EUR".to_string(),
+                to: "USD".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn normalizes_codes_and_ignores_non_positive_rates() {
+        let table = FxTable::new(vec![
+            rate(" eur", "usd ", "1.10", "2024-01-01"),
+            rate("EUR", "USD", "0", "2024-02-01"),
+        ]);
+        let quote = table.quote("EUR", "USD", &day("2024-03-01")).unwrap();
+        assert_eq!(quote.rate, dec("1.10"));
+        assert!(matches!(
+            table.quote("EUR", "XYZ1", &day("2024-03-01")),
+            Err(FxError::UnknownCurrency(_))
+        ));
+    }
+}
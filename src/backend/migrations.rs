@@ -0,0 +1,243 @@
+use leptos::logging::log;
+use leptos::prelude::ServerFnError;
+use surrealdb::engine::any::Any;
+use surrealdb::Surreal;
+
+/// One schema change. Versions are applied in ascending order and never edited
+/// once released: change the schema by appending a new entry.
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    pub script: &'static str,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        script: include_str!("migrations/0001_initial_schema.surql"),
+    },
+    Migration {
+        version: 2,
+        name: "wallet_categories",
+        script: include_str!("migrations/0002_wallet_categories.surql"),
+    },
+    Migration {
+        version: 3,
+        name: "trade_side_status",
+        script: include_str!("migrations/0003_trade_side_status.surql"),
+    },
+    Migration {
+        version: 4,
+        name: "fx_rates",
+        script: include_str!("migrations/0004_fx_rates.surql"),
+    },
+    Migration {
+        version: 5,
+        name: "reporting_currency",
+        script: include_str!("migrations/0005_reporting_currency.surql"),
+    },
+    Migration {
+        version: 6,
+        name: "decimal_money",
+        script: include_str!("migrations/0006_decimal_money.surql"),
+    },
+    Migration {
+        version: 7,
+        name: "users_sessions",
+        script: include_str!("migrations/0007_users_sessions.surql"),
+    },
+    Migration {
+        version: 8,
+        name: "record_owners",
+        script: include_str!("migrations/0008_record_owners.surql"),
+    },
+    Migration {
+        version: 9,
+        name: "portfolios",
+        script: include_str!("migrations/0009_portfolios.surql"),
+    },
+    Migration {
+        version: 10,
+        name: "trade_fees",
+        script: include_str!("migrations/0010_trade_fees.surql"),
+    },
+    Migration {
+        version: 11,
+        name: "wallet_reference",
+        script: include_str!("migrations/0011_wallet_reference.surql"),
+    },
+    Migration {
+        version: 12,
+        name: "tax_lots",
+        script: include_str!("migrations/0012_tax_lots.surql"),
+    },
+    Migration {
+        version: 13,
+        name: "decimal_backfill",
+        script: include_str!("migrations/0013_decimal_backfill.surql"),
+    },
+];
+
+// Bookkeeping table, created before anything else so the current version can be read.
+const BOOTSTRAP: &str = "
+    DEFINE TABLE IF NOT EXISTS schema_migration SCHEMAFULL;
+    DEFINE FIELD IF NOT EXISTS version ON schema_migration TYPE int;
+    DEFINE FIELD IF NOT EXISTS name ON schema_migration TYPE string;
+    DEFINE FIELD IF NOT EXISTS applied_at ON schema_migration TYPE datetime;
+    DEFINE INDEX IF NOT EXISTS schema_migration_version ON schema_migration FIELDS version UNIQUE;
+";
+
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+/// Highest version recorded in `schema_migration`, 0 for an empty database.
+pub async fn current_version(db: &Surreal<Any>) -> Result<u32, ServerFnError> {
+    db.query(BOOTSTRAP).await?.check()?;
+
+    let mut response = db
+        .query("SELECT VALUE version FROM schema_migration ORDER BY version DESC LIMIT 1")
+        .await?;
+    let versions: Vec<u32> = response.take(0)?;
+
+    Ok(versions.first().copied().unwrap_or(0))
+}
+
+/// Applies every migration newer than the database, each in its own transaction
+/// together with its `schema_migration` record. Returns the versions applied.
+pub async fn migrate(db: &Surreal<Any>) -> Result<Vec<u32>, ServerFnError> {
+    let current = current_version(db).await?;
+    let latest = latest_version();
+
+    if current > latest {
+        return Err(ServerFnError::new(format!(
+            "database schema is at version {} but this build only knows up to version {}; refusing to start",
+            current, latest
+        )));
+    }
+
+    let mut applied = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        let script = format!(
+            "BEGIN TRANSACTION;
+             {}
+             CREATE schema_migration CONTENT {{ version: $version, name: $name, applied_at: time::now() }};
+             COMMIT TRANSACTION;",
+            migration.script
+        );
+
+        db.query(script)
+            .bind(("version", migration.version))
+            .bind(("name", migration.name))
+            .await?
+            .check()
+            .map_err(|e| {
+                ServerFnError::new(format!(
+                    "migration {} ({}) failed: {}",
+                    migration.version, migration.name, e
+                ))
+            })?;
+
+        log!("Applied migration {} ({})", migration.version, migration.name);
+        applied.push(migration.version);
+    }
+
+    Ok(applied)
+}
+
+#[cfg(all(test, feature = "embedded"))]
+mod tests {
+    use super::*;
+    use crate::backend::config::{DbConfig, DbEngine};
+    use crate::backend::db::connect;
+    use crate::backend::model::Decimal;
+
+    // Rows as the app wrote them before migrations existed: tables created on first
+    // insert, floats for prices, sells as negative quantities, free-form statuses.
+    const BASELINE: &str = "
+        CREATE category:stocks CONTENT { name: 'Stocks', description: 'Listed shares' };
+        CREATE risk:high CONTENT { name: 'High', risk_score: 7 };
+        CREATE asset:aapl CONTENT { symbol: 'AAPL', category: category:stocks, risk: risk:high };
+        CREATE price:one CONTENT { asset: asset:aapl, price: 187.25, price_date: d'2024-05-02T00:00:00Z' };
+        CREATE price:two CONTENT { asset: asset:aapl, price: 190.0, price_date: d'2024-05-03T00:00:00Z' };
+        CREATE trade:bought CONTENT {
+            asset: asset:aapl, price: 150.5, qty: 10, status: 'Open', trade_date: d'2024-01-02T10:00:00Z'
+        };
+        CREATE trade:sold CONTENT {
+            asset: asset:aapl, price: 180.0, qty: -4, status: ' Closed ', trade_date: d'2024-04-02T10:00:00Z'
+        };
+        CREATE trade:filled CONTENT {
+            asset: asset:aapl, price: 160.0, qty: 1, status: 'executed', trade_date: d'2024-03-02T10:00:00Z'
+        };
+        CREATE wallet:salary CONTENT {
+            amount: 2500, ccy: 'EUR', status: 'completed', tx_date: d'2024-01-31T00:00:00Z', note: 'Salary'
+        };
+        CREATE wallet:rent CONTENT {
+            amount: -900, ccy: 'EUR', status: 'completed', tx_date: d'2024-02-01T00:00:00Z', note: 'Rent'
+        };
+    ";
+
+    #[tokio::test]
+    async fn upgrades_a_database_written_before_migrations() {
+        let config = DbConfig {
+            engine: DbEngine::Memory,
+            namespace: "test".to_string(),
+            database: "test".to_string(),
+        };
+        let db = connect(&config).await.expect("embedded engine starts");
+        db.query(BASELINE).await.unwrap().check().unwrap();
+
+        let applied = migrate(&db).await.expect("migrations apply over existing tables");
+        assert_eq!(applied, MIGRATIONS.iter().map(|m| m.version).collect::<Vec<_>>());
+
+        // Read as floats, which keeps the values and leaves out the number types.
+        let mut response = db
+            .query(
+                "SELECT VALUE [side, status] FROM [trade:bought, trade:sold, trade:filled];
+                 SELECT VALUE [<float> qty, <float> price] FROM [trade:bought, trade:sold, trade:filled];
+                 SELECT VALUE <float> amount FROM [wallet:salary, wallet:rent];
+                 SELECT VALUE <float> price FROM [price:one, price:two];
+                 SELECT VALUE [symbol, ccy] FROM asset;
+                 SELECT VALUE precision FROM asset;
+                 SELECT VALUE name FROM category;
+                 SELECT VALUE risk_score FROM risk;",
+            )
+            .await
+            .unwrap();
+        let trades: Vec<(String, String)> = response.take(0).unwrap();
+        let numbers: Vec<(f64, f64)> = response.take(1).unwrap();
+        let amounts: Vec<f64> = response.take(2).unwrap();
+        let prices: Vec<f64> = response.take(3).unwrap();
+        let assets: Vec<(String, String)> = response.take(4).unwrap();
+        let precisions: Vec<i64> = response.take(5).unwrap();
+        let categories: Vec<String> = response.take(6).unwrap();
+        let risks: Vec<i64> = response.take(7).unwrap();
+
+        let text = |a: &str, b: &str| (a.to_string(), b.to_string());
+        assert_eq!(trades, vec![text("buy", "open"), text("sell", "closed"), text("buy", "open")]);
+        assert_eq!(numbers, vec![(10.0, 150.5), (4.0, 180.0), (1.0, 160.0)]);
+        assert_eq!(amounts, vec![2500.0, -900.0]);
+        assert_eq!(prices, vec![187.25, 190.0]);
+        assert_eq!(assets, vec![text("AAPL", "USD")]);
+        assert_eq!(precisions, vec![2]);
+        assert_eq!(categories, vec!["Stocks"]);
+        assert_eq!(risks, vec![7]);
+
+        // And load as the decimals the app reads them as.
+        let mut response = db
+            .query(
+                "SELECT VALUE amount FROM [wallet:salary, wallet:rent];
+                 SELECT VALUE price FROM [price:one, price:two];
+                 SELECT VALUE [qty, price] FROM trade:filled;",
+            )
+            .await
+            .unwrap();
+        let amounts: Vec<Decimal> = response.take(0).unwrap();
+        let prices: Vec<Decimal> = response.take(1).unwrap();
+        let filled: Vec<(Decimal, Decimal)> = response.take(2).unwrap();
+        assert_eq!(amounts, vec![Decimal::from(2500), Decimal::from(-900)]);
+        assert_eq!(prices, vec![Decimal::new(18725, 2), Decimal::from(190)]);
+        assert_eq!(filled, vec![(Decimal::ONE, Decimal::from(160))]);
+    }
+}
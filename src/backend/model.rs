@@ -1,25 +1,142 @@
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use surrealdb::{Datetime, RecordId};
 
+pub use rust_decimal::Decimal;
+
+use crate::backend::currency::{format_amount, minor_units};
+
+/// Exact amount in one currency. Amounts travel as decimal strings over the
+/// API and are stored as SurrealDB `decimal` fields, so totals never drift.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Money {
+    pub amount: Decimal,
+    pub ccy: String,
+}
+
+impl Money {
+    pub fn new(amount: Decimal, ccy: impl Into<String>) -> Self {
+        Money {
+            amount,
+            ccy: ccy.into(),
+        }
+    }
+
+    pub fn zero(ccy: impl Into<String>) -> Self {
+        Money::new(Decimal::ZERO, ccy)
+    }
+
+    /// Sum of two amounts, `None` when the currencies differ or the sum overflows.
+    pub fn checked_add(&self, other: &Money) -> Option<Money> {
+        if self.ccy != other.ccy {
+            return None;
+        }
+        Some(Money::new(self.amount.checked_add(other.amount)?, self.ccy.clone()))
+    }
+
+    /// This amount in `ccy`, given how many units of `ccy` one unit of ours is worth.
+    pub fn convert(&self, rate: Decimal, ccy: &str) -> Money {
+        Money::new(self.amount * rate, ccy)
+    }
+
+    /// Rounded half-even to the currency's minor unit (cents for most currencies).
+    pub fn rounded(&self) -> Money {
+        Money::new(self.amount.round_dp(minor_units(&self.ccy)), self.ccy.clone())
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.amount.is_sign_negative() && !self.amount.is_zero()
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&format_amount(self.amount, &self.ccy))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Category {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<RecordId>,
     pub name: String,    
     pub description: String  
 }
 
+/// Named group of trades owned by one account.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Portfolio {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<RecordId>,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<RecordId>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Wallet {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<RecordId>,
-    pub amount: i64,    
+    /// Signed: deposits are positive, withdrawals negative.
+    pub amount: Decimal,
     pub ccy: String ,
     pub status: String,
     pub tx_date: Datetime,
-    pub note: String
+    pub note: String,
+    #[serde(default)]
+    pub category: Option<RecordId>,
+    /// Bank reference of an entry imported from a statement.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    /// Account the entry belongs to; always set by the server.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<RecordId>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletCategory {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<RecordId>,
+    pub name: String,
+    pub kind: String,
+}
+/// Cash balance converted into one currency at the rates effective `as_of`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletTotal {
+    pub total: Money,
+    pub as_of: Datetime,
+    pub rates: Vec<crate::backend::fx::FxQuote>,
+    /// Currencies held without a rate into the total's currency; left out of it.
+    pub unconverted: Vec<String>,
+}
+
+impl WalletTotal {
+    /// Warning to show when some balances could not be converted.
+    pub fn unconverted_note(&self) -> Option<String> {
+        (!self.unconverted.is_empty()).then(|| {
+            format!(
+                "No {} rate for {}: that cash is left out of the total.",
+                self.total.ccy,
+                self.unconverted.join(", ")
+            )
+        })
+    }
+}
 
+/// One unit of `base` is worth `rate` units of `quote` from `rate_date` on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FxRate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<RecordId>,
+    pub base: String,
+    pub quote: String,
+    pub rate: Decimal,
+    pub rate_date: Datetime,
 }
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Risk {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<RecordId>,
     pub name: String,    
     pub risk_score: u8  
@@ -27,37 +144,158 @@ pub struct Risk {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Price {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<RecordId>,
     pub asset: RecordId,
-    pub price: f64,
+    pub price: Decimal,
     pub price_date: Datetime,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Asset {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<RecordId>,
     pub symbol: String,
     pub category: RecordId,
     pub risk: Risk,
+    pub ccy: String,
+    /// Decimal places prices of this asset are quoted with.
+    pub precision: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AssetWithPrice {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<RecordId>,
     pub symbol: String,
     pub category: RecordId,
     pub risk: Risk,
+    pub ccy: String,
+    pub precision: u32,
     pub recent_prices: Vec<Price>,
-    pub last_price: Option<f64>,
+    pub last_price: Option<Decimal>,
     pub price_change_pct: Option<f64>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+impl TradeSide {
+    pub const ALL: [TradeSide; 2] = [TradeSide::Buy, TradeSide::Sell];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TradeSide::Buy => "buy",
+            TradeSide::Sell => "sell",
+        }
+    }
+}
+
+impl fmt::Display for TradeSide {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Lifecycle of a trade: pending -> open -> closed, or pending -> cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TradeStatus {
+    Pending,
+    Open,
+    Closed,
+    Cancelled,
+}
+
+impl TradeStatus {
+    pub const ALL: [TradeStatus; 4] = [
+        TradeStatus::Pending,
+        TradeStatus::Open,
+        TradeStatus::Closed,
+        TradeStatus::Cancelled,
+    ];
+
+    /// Statuses a new trade may be entered with.
+    pub const INITIAL: [TradeStatus; 3] =
+        [TradeStatus::Pending, TradeStatus::Open, TradeStatus::Closed];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TradeStatus::Pending => "pending",
+            TradeStatus::Open => "open",
+            TradeStatus::Closed => "closed",
+            TradeStatus::Cancelled => "cancelled",
+        }
+    }
+
+    /// Executed trades count towards holdings.
+    pub fn is_executed(self) -> bool {
+        matches!(self, TradeStatus::Open | TradeStatus::Closed)
+    }
+
+    /// Closed and cancelled trades can no longer be edited.
+    pub fn is_final(self) -> bool {
+        matches!(self, TradeStatus::Closed | TradeStatus::Cancelled)
+    }
+
+    /// Staying in the same non-final status is allowed so other fields can be edited.
+    pub fn can_transition_to(self, next: TradeStatus) -> bool {
+        matches!(
+            (self, next),
+            (TradeStatus::Pending, TradeStatus::Pending)
+                | (TradeStatus::Pending, TradeStatus::Open)
+                | (TradeStatus::Pending, TradeStatus::Cancelled)
+                | (TradeStatus::Open, TradeStatus::Open)
+                | (TradeStatus::Open, TradeStatus::Closed)
+        )
+    }
+
+    /// Closed trades are realized history, so they stay. Anything else may be deleted
+    /// as a mistake: a pending or open trade can still be edited anyway, and a
+    /// cancelled one never counted towards holdings.
+    pub fn can_delete(self) -> bool {
+        self != TradeStatus::Closed
+    }
+}
+
+impl fmt::Display for TradeStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Trade {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<RecordId>,
     pub asset: RecordId,
-    pub price: f64,
-    pub qty: f64,
-    pub status: String,
+    pub side: TradeSide,
+    pub price: Decimal,
+    /// Always positive; the direction is carried by `side`.
+    pub qty: Decimal,
+    /// Commission and charges in the asset's currency, never negative.
+    #[serde(default)]
+    pub fees: Decimal,
+    pub status: TradeStatus,
     pub trade_date: Datetime,
+    /// Portfolio the trade was booked in.
+    #[serde(default)]
+    pub portfolio: Option<RecordId>,
+    /// Account the trade belongs to; always set by the server.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<RecordId>,
+}
+
+impl Trade {
+    /// Quantity with sells negative, as used when folding positions.
+    pub fn signed_qty(&self) -> Decimal {
+        match self.side {
+            TradeSide::Buy => self.qty,
+            TradeSide::Sell => -self.qty,
+        }
+    }
 }
@@ -0,0 +1,693 @@
+use leptos::prelude::ServerFnError;
+use leptos::server;
+use serde::{Deserialize, Serialize};
+
+use crate::backend::model::Money;
+#[cfg(feature = "ssr")]
+use crate::backend::auth::{require_user, User};
+#[cfg(feature = "ssr")]
+use crate::backend::charts::{line_svg, sparse_labels};
+#[cfg(feature = "ssr")]
+use crate::backend::db::{get_db, owned_query};
+#[cfg(feature = "ssr")]
+use crate::backend::fx::{load_fx_table, FxTable};
+#[cfg(feature = "ssr")]
+use crate::backend::model::{Decimal, Trade, TradeSide};
+#[cfg(feature = "ssr")]
+use crate::backend::portfolios::{portfolio_query, selected_portfolio};
+#[cfg(feature = "ssr")]
+use crate::backend::settings::reporting_ccy;
+#[cfg(feature = "ssr")]
+use chrono::{Duration, NaiveDate};
+#[cfg(feature = "ssr")]
+use rust_decimal::prelude::ToPrimitive;
+#[cfg(feature = "ssr")]
+use surrealdb::{Datetime, RecordId};
+
+/// Returns of the selected portfolio over a date range, in the reporting currency.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerformanceReport {
+    /// First and last day measured, `YYYY-MM-DD`. The range starts no earlier than
+    /// the first trade or wallet entry.
+    pub from: String,
+    pub to: String,
+    /// Whether wallet cash is part of the value; only with all portfolios selected.
+    pub includes_cash: bool,
+    /// Value at the end of the day before `from`.
+    pub start_value: Money,
+    pub end_value: Money,
+    /// Money put in (positive) or taken out (negative) during the range.
+    pub net_flows: Money,
+    /// End value less start value and net flows.
+    pub gain: Money,
+    /// Time-weighted return over the range, in percent.
+    pub twr_pct: Option<f64>,
+    /// Time-weighted return per year, for ranges of a year or more.
+    pub twr_annualized_pct: Option<f64>,
+    /// Money-weighted return (XIRR), per year, in percent.
+    pub mwr_pct: Option<f64>,
+    pub days: usize,
+    /// Cumulative time-weighted return chart; empty when there is nothing to show.
+    pub svg: String,
+    /// Set when holdings or cash without a rate into the reporting currency were left out.
+    pub unconverted_note: Option<String>,
+}
+
+/// Daily value of the selected portfolio with the money that entered or left it each
+/// day, in the reporting currency. Shared by the reports built on daily returns.
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone)]
+pub struct ValueSeries {
+    pub ccy: String,
+    pub includes_cash: bool,
+    /// Consecutive calendar days, oldest first.
+    pub days: Vec<NaiveDate>,
+    /// Value at the end of each day.
+    pub values: Vec<Decimal>,
+    /// External flows of each day: purchases and deposits positive, sales and
+    /// withdrawals negative.
+    pub flows: Vec<Decimal>,
+    /// Value at the end of the day before the first one.
+    pub opening: Decimal,
+    /// Currencies without a rate into `ccy`; holdings and cash in them are left out of
+    /// every day, flows included.
+    pub unconverted: Vec<String>,
+}
+
+#[cfg(feature = "ssr")]
+impl ValueSeries {
+    /// Warning to show when some holdings or cash could not be converted.
+    pub fn unconverted_note(&self) -> Option<String> {
+        (!self.unconverted.is_empty()).then(|| {
+            format!(
+                "No {} rate for {}: holdings and cash in those currencies are left out.",
+                self.ccy,
+                self.unconverted.join(", ")
+            )
+        })
+    }
+
+    /// Time-weighted return of each day. A day's flows are taken to arrive at its
+    /// start; days with nothing invested return zero.
+    pub fn daily_returns(&self) -> Vec<f64> {
+        let mut previous = self.opening;
+        self.values
+            .iter()
+            .zip(&self.flows)
+            .map(|(value, flow)| {
+                let base = previous + flow;
+                previous = *value;
+                if base <= Decimal::ZERO {
+                    return 0.0;
+                }
+                (value / base - Decimal::ONE).to_f64().unwrap_or_default()
+            })
+            .collect()
+    }
+
+    /// Compounded daily returns, one running total per day.
+    pub fn cumulative_returns(&self) -> Vec<f64> {
+        let mut growth = 1.0;
+        self.daily_returns()
+            .into_iter()
+            .map(|r| {
+                growth *= 1.0 + r;
+                growth - 1.0
+            })
+            .collect()
+    }
+}
+
+/// Requested range from `YYYY-MM-DD` form values. A blank start means since the first
+/// activity, a blank end today.
+#[cfg(feature = "ssr")]
+pub fn parse_range(from: &str, to: &str) -> Result<(Option<NaiveDate>, NaiveDate), ServerFnError> {
+    let parse = |value: &str| {
+        NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+            .map_err(|_| ServerFnError::new(format!("'{}' is not a date (YYYY-MM-DD)", value.trim())))
+    };
+    let to = match to.trim() {
+        "" => chrono::Utc::now().date_naive(),
+        value => parse(value)?,
+    };
+    let from = match from.trim() {
+        "" => None,
+        value => Some(parse(value)?),
+    };
+    if from.is_some_and(|from| from > to) {
+        return Err(ServerFnError::new("The start date is after the end date"));
+    }
+    Ok((from, to))
+}
+
+#[cfg(feature = "ssr")]
+pub(crate) fn end_of_day(day: NaiveDate) -> Datetime {
+    Datetime::from(day.and_hms_opt(23, 59, 59).unwrap_or_default().and_utc())
+}
+
+#[cfg(feature = "ssr")]
+fn day_of(date: &Datetime) -> NaiveDate {
+    date.into_inner_ref().date_naive()
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Deserialize)]
+struct AssetCcy {
+    id: RecordId,
+    ccy: String,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Deserialize)]
+struct PricePoint {
+    asset: RecordId,
+    price: Decimal,
+    price_date: Datetime,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Deserialize)]
+struct CashEntry {
+    amount: Decimal,
+    ccy: String,
+    tx_date: Datetime,
+}
+
+// Quantity and latest known price of one asset while replaying the history.
+#[cfg(feature = "ssr")]
+#[derive(Clone)]
+struct Track {
+    id: RecordId,
+    ccy: String,
+    prices: Vec<(Datetime, Decimal)>,
+    next: usize,
+    price: Option<Decimal>,
+    qty: Decimal,
+}
+
+/// Replays executed trades, prices and, with all portfolios selected, wallet entries
+/// day by day and values the result at each day's close. `from` is moved up to the
+/// first activity; the series is empty when there is none by `to`.
+#[cfg(feature = "ssr")]
+pub async fn value_series(
+    user: &User,
+    from: Option<NaiveDate>,
+    to: NaiveDate,
+) -> Result<ValueSeries, ServerFnError> {
+    let until = end_of_day(to);
+    let includes_cash = selected_portfolio(user).await?.is_none();
+
+    let mut response = portfolio_query(
+        user,
+        "SELECT * FROM trade WHERE owner = $owner AND (!$portfolio OR portfolio = $portfolio)
+            AND trade_date <= $until ORDER BY trade_date ASC",
+    )
+    .await?
+    .bind(("until", until.clone()))
+    .await?;
+    let trades: Vec<Trade> = response.take(0)?;
+    let trades: Vec<Trade> = trades.into_iter().filter(|t| t.status.is_executed()).collect();
+
+    let cash: Vec<CashEntry> = if includes_cash {
+        let mut response = owned_query(
+            user,
+            "SELECT amount, ccy, tx_date FROM wallet WHERE owner = $owner AND status != 'cancelled'
+                AND tx_date <= $until ORDER BY tx_date ASC",
+        )?
+        .bind(("until", until.clone()))
+        .await?;
+        response.take(0)?
+    } else {
+        Vec::new()
+    };
+
+    let asset_ids: Vec<RecordId> = trades.iter().map(|t| t.asset.clone()).collect();
+    let mut response = get_db()
+        .query(
+            "SELECT id, ccy FROM asset WHERE id IN $assets;
+             SELECT asset, price, price_date FROM price WHERE asset IN $assets AND price_date <= $until
+                ORDER BY price_date ASC;",
+        )
+        .bind(("assets", asset_ids))
+        .bind(("until", until))
+        .await?;
+    let assets: Vec<AssetCcy> = response.take(0)?;
+    let prices: Vec<PricePoint> = response.take(1)?;
+
+    let tracks: Vec<Track> = assets
+        .into_iter()
+        .map(|asset| Track {
+            prices: prices
+                .iter()
+                .filter(|p| p.asset == asset.id)
+                .map(|p| (p.price_date.clone(), p.price))
+                .collect(),
+            id: asset.id,
+            ccy: asset.ccy,
+            next: 0,
+            price: None,
+            qty: Decimal::ZERO,
+        })
+        .collect();
+
+    let ccy = reporting_ccy(user).await?;
+    let fx = load_fx_table().await?;
+    let first_activity = [
+        trades.first().map(|t| day_of(&t.trade_date)),
+        cash.first().map(|c| day_of(&c.tx_date)),
+    ]
+    .into_iter()
+    .flatten()
+    .min();
+    let mut series = ValueSeries {
+        ccy,
+        includes_cash,
+        days: Vec::new(),
+        values: Vec::new(),
+        flows: Vec::new(),
+        opening: Decimal::ZERO,
+        unconverted: Vec::new(),
+    };
+    let Some(first_activity) = first_activity.filter(|first| *first <= to) else {
+        return Ok(series);
+    };
+    let from = from.map_or(first_activity, |from| from.max(first_activity));
+
+    // A currency found without a rate is left out and the history replayed without it.
+    let history = History {
+        trades: &trades,
+        cash: &cash,
+        tracks: &tracks,
+        fx: &fx,
+    };
+    loop {
+        match history.replay(&mut series, first_activity, from, to) {
+            Ok(()) => return Ok(series),
+            Err(missing) => series.unconverted.push(missing),
+        }
+    }
+}
+
+// What `value_series` replays day by day.
+#[cfg(feature = "ssr")]
+struct History<'a> {
+    trades: &'a [Trade],
+    cash: &'a [CashEntry],
+    tracks: &'a [Track],
+    fx: &'a FxTable,
+}
+
+#[cfg(feature = "ssr")]
+impl History<'_> {
+    // Fills `series` from `first` to `to`, skipping `series.unconverted`. Fails with the
+    // first other currency that has no rate into `series.ccy` on a day it is needed.
+    fn replay(&self, series: &mut ValueSeries, first: NaiveDate, from: NaiveDate, to: NaiveDate) -> Result<(), String> {
+        let skipped = |code: &str| series.unconverted.iter().any(|c| c == code);
+        let mut tracks: Vec<Track> = self.tracks.iter().filter(|t| !skipped(&t.ccy)).cloned().collect();
+        let (mut days, mut values, mut flows, mut opening) = (Vec::new(), Vec::new(), Vec::new(), Decimal::ZERO);
+        let mut balances: Vec<(String, Decimal)> = Vec::new();
+        let (mut next_trade, mut next_cash) = (0, 0);
+        let mut day = first;
+        while day <= to {
+            let close = end_of_day(day);
+            let rate = |from_ccy: &str| {
+                self.fx
+                    .quote(from_ccy, &series.ccy, &close)
+                    .map(|q| q.rate)
+                    .map_err(|_| from_ccy.to_string())
+            };
+
+            let mut flow = Decimal::ZERO;
+            while let Some(trade) = self.trades.get(next_trade).filter(|t| day_of(&t.trade_date) <= day) {
+                next_trade += 1;
+                let Some(track) = tracks.iter_mut().find(|t| t.id == trade.asset) else {
+                    continue;
+                };
+                track.qty += trade.signed_qty();
+                let cost = match trade.side {
+                    TradeSide::Buy => trade.qty * trade.price + trade.fees,
+                    TradeSide::Sell => -(trade.qty * trade.price - trade.fees),
+                };
+                flow += cost * rate(&track.ccy)?;
+                // Until the first quote an asset is worth what was last paid for it.
+                if track.prices.first().is_none_or(|(date, _)| *date > close) {
+                    track.price = Some(trade.price);
+                }
+            }
+            while let Some(entry) = self.cash.get(next_cash).filter(|c| day_of(&c.tx_date) <= day) {
+                next_cash += 1;
+                if skipped(&entry.ccy) {
+                    continue;
+                }
+                match balances.iter_mut().find(|(code, _)| *code == entry.ccy) {
+                    Some((_, balance)) => *balance += entry.amount,
+                    None => balances.push((entry.ccy.clone(), entry.amount)),
+                }
+                flow += entry.amount * rate(&entry.ccy)?;
+            }
+
+            let mut value = Decimal::ZERO;
+            for track in tracks.iter_mut() {
+                while let Some((_, price)) = track.prices.get(track.next).filter(|(date, _)| *date <= close) {
+                    track.price = Some(*price);
+                    track.next += 1;
+                }
+                if let (false, Some(price)) = (track.qty.is_zero(), track.price) {
+                    value += track.qty * price * rate(&track.ccy)?;
+                }
+            }
+            for (code, balance) in &balances {
+                if !balance.is_zero() {
+                    value += balance * rate(code)?;
+                }
+            }
+
+            if day < from {
+                opening = value;
+            } else {
+                days.push(day);
+                values.push(value);
+                flows.push(flow);
+            }
+            day += Duration::days(1);
+        }
+
+        series.days = days;
+        series.values = values;
+        series.flows = flows;
+        series.opening = opening;
+        Ok(())
+    }
+}
+
+/// Calendar days per year; the daily series cover every calendar day.
+#[cfg(feature = "ssr")]
+pub const DAYS_PER_YEAR: f64 = 365.0;
+
+#[cfg(feature = "ssr")]
+pub fn mean(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    Some(values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// Sample covariance of two equally long series.
+#[cfg(feature = "ssr")]
+pub fn covariance(a: &[f64], b: &[f64]) -> Option<f64> {
+    if a.len() != b.len() || a.len() < 2 {
+        return None;
+    }
+    let (mean_a, mean_b) = (mean(a)?, mean(b)?);
+    let sum: f64 = a.iter().zip(b).map(|(x, y)| (x - mean_a) * (y - mean_b)).sum();
+    Some(sum / (a.len() - 1) as f64)
+}
+
+/// Sample standard deviation.
+#[cfg(feature = "ssr")]
+pub fn std_dev(values: &[f64]) -> Option<f64> {
+    covariance(values, values).map(f64::sqrt)
+}
+
+/// Compounded return of a series of periodic returns.
+#[cfg(feature = "ssr")]
+pub fn compound(returns: &[f64]) -> f64 {
+    returns.iter().fold(1.0, |growth, r| growth * (1.0 + r)) - 1.0
+}
+
+// Net present value of dated flows at `rate`, discounted from the first date.
+#[cfg(feature = "ssr")]
+fn npv(flows: &[(NaiveDate, f64)], rate: f64) -> f64 {
+    let start = flows.first().map(|(date, _)| *date).unwrap_or_default();
+    flows
+        .iter()
+        .map(|(date, amount)| amount / (1.0 + rate).powf((*date - start).num_days() as f64 / 365.0))
+        .sum()
+}
+
+/// Yearly rate at which the flows' net present value is zero, found by bisection.
+/// `None` without both money in and money out, or when no rate balances them.
+#[cfg(feature = "ssr")]
+pub fn xirr(flows: &[(NaiveDate, f64)]) -> Option<f64> {
+    if !flows.iter().any(|(_, a)| *a > 0.0) || !flows.iter().any(|(_, a)| *a < 0.0) {
+        return None;
+    }
+    // Widen the bracket both ways, towards a total loss and towards huge gains.
+    let (mut low, mut high) = (-0.9999, 1.0);
+    loop {
+        let (at_low, at_high) = (npv(flows, low), npv(flows, high));
+        if !at_low.is_finite() || !at_high.is_finite() {
+            return None;
+        }
+        if at_low == 0.0 {
+            return Some(low);
+        }
+        if at_low.signum() != at_high.signum() {
+            break;
+        }
+        if high > 1e6 {
+            return None;
+        }
+        high *= 2.0;
+        low = -1.0 + (low + 1.0) / 2.0;
+    }
+    for _ in 0..200 {
+        let mid = (low + high) / 2.0;
+        if npv(flows, mid).signum() == npv(flows, low).signum() {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    Some((low + high) / 2.0)
+}
+
+/// `value` as a percentage, `None` when not finite.
+#[cfg(feature = "ssr")]
+pub(crate) fn pct(value: f64) -> Option<f64> {
+    Some(value * 100.0).filter(|v| v.is_finite())
+}
+
+#[server(GetPerformance, "/api")]
+pub async fn get_performance(from: String, to: String) -> Result<PerformanceReport, ServerFnError> {
+    let user = require_user().await?;
+    let (from, to) = parse_range(&from, &to)?;
+    let series = value_series(&user, from, to).await?;
+    let money = |amount: Decimal| Money::new(amount, series.ccy.as_str()).rounded();
+
+    let (Some(first), Some(last)) = (series.days.first(), series.days.last()) else {
+        return Ok(PerformanceReport {
+            from: from.unwrap_or(to).format("%Y-%m-%d").to_string(),
+            to: to.format("%Y-%m-%d").to_string(),
+            includes_cash: series.includes_cash,
+            start_value: money(Decimal::ZERO),
+            end_value: money(Decimal::ZERO),
+            net_flows: money(Decimal::ZERO),
+            gain: money(Decimal::ZERO),
+            twr_pct: None,
+            twr_annualized_pct: None,
+            mwr_pct: None,
+            days: 0,
+            svg: String::new(),
+            unconverted_note: series.unconverted_note(),
+        });
+    };
+
+    let end_value = series.values.last().copied().unwrap_or_default();
+    let net_flows: Decimal = series.flows.iter().sum();
+    let cumulative = series.cumulative_returns();
+    let twr = cumulative.last().copied();
+    let years = series.days.len() as f64 / 365.0;
+    let twr_annualized = twr.filter(|_| years >= 1.0).map(|r| (1.0 + r).powf(1.0 / years) - 1.0);
+
+    // The investor's view: the opening value and purchases go in, the closing value comes out.
+    let mut cash_flows = vec![(*first - Duration::days(1), -series.opening.to_f64().unwrap_or_default())];
+    cash_flows.extend(
+        series
+            .days
+            .iter()
+            .zip(&series.flows)
+            .filter(|(_, flow)| !flow.is_zero())
+            .map(|(day, flow)| (*day, -flow.to_f64().unwrap_or_default())),
+    );
+    cash_flows.push((*last, end_value.to_f64().unwrap_or_default()));
+
+    let labels = series.days.iter().map(|d| d.format("%Y-%m-%d").to_string()).collect();
+    let points = cumulative.iter().map(|r| (r * 100.0) as f32).collect();
+    let svg = line_svg(
+        "Cumulative time-weighted return (%)",
+        sparse_labels(labels, 8),
+        vec![("Portfolio".to_string(), points)],
+    )?;
+
+    Ok(PerformanceReport {
+        from: first.format("%Y-%m-%d").to_string(),
+        to: last.format("%Y-%m-%d").to_string(),
+        includes_cash: series.includes_cash,
+        start_value: money(series.opening),
+        end_value: money(end_value),
+        net_flows: money(net_flows),
+        gain: money(end_value - series.opening - net_flows),
+        twr_pct: twr.and_then(pct),
+        twr_annualized_pct: twr_annualized.and_then(pct),
+        mwr_pct: xirr(&cash_flows).and_then(pct),
+        days: series.days.len(),
+        svg,
+        unconverted_note: series.unconverted_note(),
+    })
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+
+    fn day(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("a rate");
+        assert!((actual - expected).abs() < 1e-6, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn xirr_of_a_single_year() {
+        assert_close(xirr(&[(day("2023-01-01"), -1000.0), (day("2024-01-01"), 1100.0)]), 0.10);
+        assert_close(xirr(&[(day("2023-01-01"), -100.0), (day("2024-01-01"), 200.0)]), 1.0);
+        assert_close(xirr(&[(day("2023-01-01"), -100.0), (day("2024-01-01"), 100.0)]), 0.0);
+    }
+
+    #[test]
+    fn xirr_matches_the_spreadsheet_example() {
+        // The XIRR example of common spreadsheet documentation.
+        let flows = [
+            (day("2008-01-01"), -10000.0),
+            (day("2008-03-01"), 2750.0),
+            (day("2008-10-30"), 4250.0),
+            (day("2009-02-15"), 3250.0),
+            (day("2009-04-01"), 2750.0),
+        ];
+        assert_close(xirr(&flows), 0.373362535);
+    }
+
+    #[test]
+    fn xirr_needs_money_in_and_out() {
+        assert_eq!(xirr(&[]), None);
+        assert_eq!(xirr(&[(day("2024-01-01"), -100.0)]), None);
+        assert_eq!(xirr(&[(day("2024-01-01"), -100.0), (day("2024-06-01"), -50.0)]), None);
+        assert_eq!(xirr(&[(day("2024-01-01"), 100.0), (day("2024-06-01"), 0.0)]), None);
+    }
+
+    #[test]
+    fn xirr_of_heavy_losses() {
+        assert_close(xirr(&[(day("2023-01-01"), -100.0), (day("2024-01-01"), 50.0)]), -0.5);
+        assert_close(xirr(&[(day("2023-01-01"), -100.0), (day("2024-01-01"), 1.0)]), -0.99);
+        // Beyond the first bracket, which stops at -99.99 %.
+        assert_close(xirr(&[(day("2023-01-01"), -100.0), (day("2024-01-01"), 0.001)]), -0.99999);
+    }
+
+    #[test]
+    fn xirr_of_a_short_gain_is_annualized() {
+        // 1 % in 73 days is 5 compounding periods a year.
+        let rate = xirr(&[(day("2024-01-01"), -100.0), (day("2024-03-14"), 101.0)]);
+        assert_close(rate, 1.01f64.powi(5) - 1.0);
+    }
+
+    fn series(opening: i64, values: &[i64], flows: &[i64]) -> ValueSeries {
+        let start = day("2024-01-01");
+        ValueSeries {
+            ccy: "EUR".to_string(),
+            includes_cash: false,
+            days: (0..values.len() as i64).map(|i| start + Duration::days(i)).collect(),
+            values: values.iter().map(|v| Decimal::from(*v)).collect(),
+            flows: flows.iter().map(|f| Decimal::from(*f)).collect(),
+            opening: Decimal::from(opening),
+            unconverted: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn time_weighted_return_ignores_the_size_of_flows() {
+        // +10 %, then 100 more put in at the start of the day and +10 % again,
+        // then half taken out and flat.
+        let series = series(100, &[110, 231, 115], &[0, 100, -116]);
+        let daily = series.daily_returns();
+        assert!((daily[0] - 0.1).abs() < 1e-12);
+        assert!((daily[1] - 0.1).abs() < 1e-12);
+        assert!(daily[2].abs() < 1e-12);
+        let cumulative = series.cumulative_returns();
+        assert!((cumulative[1] - 0.21).abs() < 1e-12);
+        assert!((cumulative[2] - 0.21).abs() < 1e-12);
+        assert!((compound(&daily) - 0.21).abs() < 1e-12);
+    }
+
+    #[test]
+    fn days_with_nothing_invested_return_zero() {
+        let series = series(0, &[0, 50, 55], &[0, 50, 0]);
+        let daily = series.daily_returns();
+        assert_eq!(daily[0], 0.0);
+        assert_eq!(daily[1], 0.0);
+        assert!((daily[2] - 0.1).abs() < 1e-12);
+        assert!((series.cumulative_returns()[2] - 0.1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn holdings_and_cash_without_a_rate_are_left_out() {
+        use crate::backend::model::{FxRate, TradeStatus};
+
+        let at = |date: &str| end_of_day(day(date));
+        let trade = |asset: &str, price: i64, date: &str| Trade {
+            id: None,
+            asset: RecordId::from_table_key("asset", asset),
+            side: TradeSide::Buy,
+            price: Decimal::from(price),
+            qty: Decimal::from(2),
+            fees: Decimal::ZERO,
+            status: TradeStatus::Open,
+            trade_date: at(date),
+            portfolio: None,
+            owner: None,
+        };
+        let track = |asset: &str, ccy: &str, prices: &[(&str, i64)]| Track {
+            id: RecordId::from_table_key("asset", asset),
+            ccy: ccy.to_string(),
+            prices: prices.iter().map(|(date, price)| (at(date), Decimal::from(*price))).collect(),
+            next: 0,
+            price: None,
+            qty: Decimal::ZERO,
+        };
+        let trades = [trade("aapl", 100, "2024-01-01"), trade("sony", 1000, "2024-01-02")];
+        let cash = [
+            CashEntry { amount: Decimal::from(50), ccy: "EUR".to_string(), tx_date: at("2024-01-01") },
+            CashEntry { amount: Decimal::from(9000), ccy: "JPY".to_string(), tx_date: at("2024-01-02") },
+        ];
+        let tracks = [
+            track("aapl", "USD", &[("2024-01-03", 110)]),
+            track("sony", "JPY", &[("2024-01-03", 1200)]),
+        ];
+        let fx = FxTable::new(vec![FxRate {
+            id: None,
+            base: "EUR".to_string(),
+            quote: "USD".to_string(),
+            rate: Decimal::from(2),
+            rate_date: at("2023-12-31"),
+        }]);
+        let history = History {
+            trades: &trades,
+            cash: &cash,
+            tracks: &tracks,
+            fx: &fx,
+        };
+
+        let mut series = series(0, &[], &[]);
+        let (first, last) = (day("2024-01-01"), day("2024-01-03"));
+        assert_eq!(history.replay(&mut series, first, first, last), Err("JPY".to_string()));
+        series.unconverted.push("JPY".to_string());
+        history.replay(&mut series, first, first, last).unwrap();
+
+        // 2 AAPL at 100 then 110 USD, 2 USD to the EUR, and 50 EUR cash.
+        assert_eq!(series.flows, vec![Decimal::from(150), Decimal::ZERO, Decimal::ZERO]);
+        assert_eq!(series.values, vec![Decimal::from(150), Decimal::from(150), Decimal::from(160)]);
+        assert!(series.unconverted_note().unwrap().contains("JPY"));
+    }
+}
@@ -0,0 +1,261 @@
+use leptos::prelude::ServerFnError;
+use leptos::server;
+use serde::{Deserialize, Serialize};
+use surrealdb::RecordId;
+
+use crate::backend::model::{Money, Portfolio};
+#[cfg(feature = "ssr")]
+use crate::backend::auth::{require_user, User};
+#[cfg(feature = "ssr")]
+use crate::backend::db::owned_query;
+#[cfg(feature = "ssr")]
+use crate::backend::fx::load_fx_table;
+#[cfg(feature = "ssr")]
+use crate::backend::model::Trade;
+#[cfg(feature = "ssr")]
+use crate::backend::positions::{build_report, fold_positions, load_quotes};
+#[cfg(feature = "ssr")]
+use crate::backend::settings::{default_reporting_ccy, reporting_ccy, user_setting_id};
+#[cfg(feature = "ssr")]
+use surrealdb::engine::any::Any;
+#[cfg(feature = "ssr")]
+use surrealdb::Surreal;
+
+/// Form value of the "All portfolios" choice.
+pub const ALL_PORTFOLIOS: &str = "all";
+
+/// Portfolio every new account starts with.
+pub const DEFAULT_PORTFOLIO: &str = "Main";
+
+/// The account's portfolios and the one the pages show, `None` for all of them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortfolioChoice {
+    pub portfolios: Vec<Portfolio>,
+    pub selected: Option<RecordId>,
+}
+
+impl PortfolioChoice {
+    pub fn name_of(&self, id: &RecordId) -> Option<&str> {
+        self.portfolios
+            .iter()
+            .find(|p| p.id.as_ref() == Some(id))
+            .map(|p| p.name.as_str())
+    }
+
+    /// Heading for the current view.
+    pub fn selected_name(&self) -> String {
+        self.selected
+            .as_ref()
+            .and_then(|id| self.name_of(id))
+            .unwrap_or("All portfolios")
+            .to_string()
+    }
+}
+
+/// Totals of one portfolio in the reporting currency, for the consolidated view.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortfolioSummary {
+    pub portfolio: Portfolio,
+    pub trades: usize,
+    /// Assets with a non-zero quantity.
+    pub positions: usize,
+    pub market_value: Money,
+    pub unrealized_pnl: Money,
+    pub realized_pnl: Money,
+}
+
+/// Portfolio the user is looking at, `None` for all of them. A selection that no
+/// longer belongs to the user counts as none.
+#[cfg(feature = "ssr")]
+pub async fn selected_portfolio(user: &User) -> Result<Option<RecordId>, ServerFnError> {
+    let mut response = owned_query(
+        user,
+        "SELECT VALUE portfolio FROM setting WHERE owner = $owner AND portfolio.owner = $owner",
+    )?
+    .await?;
+    let selected: Vec<RecordId> = response.take(0)?;
+    Ok(selected.into_iter().next())
+}
+
+/// `owned_query` that also binds `$portfolio` to the selected portfolio, NONE for all
+/// of them. Trade queries filter with `(!$portfolio OR portfolio = $portfolio)`.
+#[cfg(feature = "ssr")]
+pub async fn portfolio_query(
+    user: &User,
+    sql: &str,
+) -> Result<surrealdb::method::Query<'static, Any>, ServerFnError> {
+    let selected = selected_portfolio(user).await?;
+    Ok(owned_query(user, sql)?.bind(("portfolio", selected)))
+}
+
+/// Id of one of the user's portfolios, from a form value with or without the table prefix.
+#[cfg(feature = "ssr")]
+pub async fn owned_portfolio(user: &User, portfolio: &str) -> Result<RecordId, ServerFnError> {
+    let key = portfolio.strip_prefix("portfolio:").unwrap_or(portfolio).trim();
+    if key.is_empty() {
+        return Err(ServerFnError::new("Choose a portfolio"));
+    }
+
+    let mut response = owned_query(user, "SELECT VALUE id FROM $id WHERE owner = $owner")?
+        .bind(("id", RecordId::from_table_key("portfolio", key)))
+        .await?;
+    let found: Vec<RecordId> = response.take(0)?;
+    found
+        .into_iter()
+        .next()
+        .ok_or_else(|| ServerFnError::new("Portfolio not found"))
+}
+
+/// Id of `owner`'s portfolio called `name`, created when missing.
+#[cfg(feature = "ssr")]
+pub async fn ensure_portfolio(
+    db: &Surreal<Any>,
+    owner: &RecordId,
+    name: &str,
+) -> Result<RecordId, ServerFnError> {
+    let mut response = db
+        .query(
+            "LET $found = (SELECT VALUE id FROM portfolio WHERE owner = $owner AND name = $name);
+             RETURN IF array::len($found) > 0 { $found[0] }
+                ELSE { (CREATE ONLY portfolio CONTENT { name: $name, owner: $owner }).id };",
+        )
+        .bind(("owner", owner.clone()))
+        .bind(("name", name.to_string()))
+        .await?;
+    let id: Option<RecordId> = response.take(1)?;
+    id.ok_or_else(|| ServerFnError::new("Portfolio was not created"))
+}
+
+#[cfg(feature = "ssr")]
+fn validate_name(name: &str) -> Result<String, ServerFnError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > 64 {
+        return Err(ServerFnError::new("Portfolio names take 1 to 64 characters"));
+    }
+    Ok(name.to_string())
+}
+
+#[server(GetPortfolios, "/api")]
+pub async fn get_portfolios() -> Result<PortfolioChoice, ServerFnError> {
+    let user = require_user().await?;
+    let mut response = owned_query(&user, "SELECT * FROM portfolio WHERE owner = $owner ORDER BY name")?
+        .await?;
+    let portfolios: Vec<Portfolio> = response.take(0)?;
+
+    Ok(PortfolioChoice {
+        portfolios,
+        selected: selected_portfolio(&user).await?,
+    })
+}
+
+#[server(CreatePortfolio, "/api")]
+pub async fn create_portfolio(name: String) -> Result<Portfolio, ServerFnError> {
+    let user = require_user().await?;
+    let name = validate_name(&name)?;
+
+    let mut response = owned_query(&user, "SELECT VALUE id FROM portfolio WHERE owner = $owner AND name = $name")?
+        .bind(("name", name.clone()))
+        .await?;
+    let existing: Vec<RecordId> = response.take(0)?;
+    if !existing.is_empty() {
+        return Err(ServerFnError::new(format!("You already have a portfolio named '{}'", name)));
+    }
+
+    let mut response = owned_query(&user, "CREATE ONLY portfolio CONTENT { name: $name, owner: $owner }")?
+        .bind(("name", name))
+        .await?;
+    let created: Option<Portfolio> = response.take(0)?;
+    created.ok_or_else(|| ServerFnError::new("Portfolio was not created"))
+}
+
+/// Deletes an empty portfolio; one that still holds trades is refused.
+#[server(DeletePortfolio, "/api")]
+pub async fn delete_portfolio(id: String) -> Result<(), ServerFnError> {
+    let user = require_user().await?;
+    let id = owned_portfolio(&user, &id).await?;
+
+    let mut response = owned_query(&user, "SELECT VALUE id FROM trade WHERE owner = $owner AND portfolio = $id LIMIT 1")?
+        .bind(("id", id.clone()))
+        .await?;
+    let trades: Vec<RecordId> = response.take(0)?;
+    if !trades.is_empty() {
+        return Err(ServerFnError::new("Only portfolios without trades can be deleted"));
+    }
+
+    owned_query(
+        &user,
+        "BEGIN TRANSACTION;
+         DELETE $id WHERE owner = $owner;
+         UPDATE setting SET portfolio = NONE WHERE owner = $owner AND portfolio = $id;
+         COMMIT TRANSACTION;",
+    )?
+    .bind(("id", id))
+    .await?
+    .check()?;
+
+    Ok(())
+}
+
+/// Switches the pages to one portfolio, or to all of them with [`ALL_PORTFOLIOS`].
+#[server(SelectPortfolio, "/api")]
+pub async fn select_portfolio(portfolio: String) -> Result<Option<RecordId>, ServerFnError> {
+    let user = require_user().await?;
+    let selected = match portfolio.trim() {
+        "" | ALL_PORTFOLIOS => None,
+        key => Some(owned_portfolio(&user, key).await?),
+    };
+
+    // A first settings record keeps the install-wide reporting currency.
+    owned_query(
+        &user,
+        "UPSERT $setting SET portfolio = $portfolio, owner = $owner,
+            reporting_ccy = reporting_ccy ?? $default_ccy
+         WHERE owner = $owner",
+    )?
+    .bind(("setting", user_setting_id(&user)))
+    .bind(("portfolio", selected.clone()))
+    .bind(("default_ccy", default_reporting_ccy().await?))
+    .await?
+    .check()?;
+
+    Ok(selected)
+}
+
+/// Per-portfolio totals for the "All portfolios" view, in the reporting currency.
+#[server(GetPortfolioOverview, "/api")]
+pub async fn get_portfolio_overview() -> Result<Vec<PortfolioSummary>, ServerFnError> {
+    let user = require_user().await?;
+    let mut response = owned_query(
+        &user,
+        "SELECT * FROM portfolio WHERE owner = $owner ORDER BY name;
+         SELECT * FROM trade WHERE owner = $owner;",
+    )?
+    .await?;
+    let portfolios: Vec<Portfolio> = response.take(0)?;
+    let trades: Vec<Trade> = response.take(1)?;
+
+    let quotes = load_quotes().await?;
+    let fx = load_fx_table().await?;
+    let ccy = reporting_ccy(&user).await?;
+    let now = surrealdb::Datetime::from(chrono::Utc::now());
+
+    let mut summaries = Vec::new();
+    for portfolio in portfolios {
+        let booked: Vec<Trade> = trades
+            .iter()
+            .filter(|t| t.portfolio.is_some() && t.portfolio == portfolio.id)
+            .cloned()
+            .collect();
+        let report = build_report(fold_positions(&booked, &quotes), &fx, &ccy, &now);
+        summaries.push(PortfolioSummary {
+            portfolio,
+            trades: booked.len(),
+            positions: report.positions.iter().filter(|p| !p.qty.is_zero()).count(),
+            market_value: report.market_value,
+            unrealized_pnl: report.unrealized_pnl,
+            realized_pnl: report.realized_pnl,
+        });
+    }
+
+    Ok(summaries)
+}
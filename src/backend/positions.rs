@@ -0,0 +1,363 @@
+use leptos::prelude::ServerFnError;
+use leptos::server;
+use serde::{Deserialize, Serialize};
+use surrealdb::RecordId;
+
+use crate::backend::fx::{FxQuote, FxTable};
+use crate::backend::model::{Decimal, Money, Trade};
+use rust_decimal::prelude::Signed;
+#[cfg(feature = "ssr")]
+use crate::backend::auth::{require_user, User};
+#[cfg(feature = "ssr")]
+use crate::backend::db::get_db;
+#[cfg(feature = "ssr")]
+use crate::backend::portfolios::portfolio_query;
+#[cfg(feature = "ssr")]
+use crate::backend::fx::load_fx_table;
+#[cfg(feature = "ssr")]
+use crate::backend::settings::reporting_ccy;
+
+/// Holding in one asset, folded from its executed trades using average cost.
+/// Negative `qty` is a short position. Amounts are exact decimals in the
+/// asset's `ccy`; `fx_rate` converts them into the reporting currency, and is
+/// `None` when no rate is known, which leaves the position out of the totals.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Position {
+    pub asset: RecordId,
+    pub symbol: String,
+    pub ccy: String,
+    pub precision: u32,
+    pub fx_rate: Option<Decimal>,
+    pub qty: Decimal,
+    pub avg_cost: Decimal,
+    pub cost_basis: Decimal,
+    pub last_price: Option<Decimal>,
+    pub market_value: Option<Decimal>,
+    pub unrealized_pnl: Option<Decimal>,
+    pub realized_pnl: Decimal,
+}
+
+impl Position {
+    pub fn reporting_market_value(&self) -> Option<Decimal> {
+        Some(self.market_value? * self.fx_rate?)
+    }
+
+    pub fn reporting_unrealized_pnl(&self) -> Option<Decimal> {
+        Some(self.unrealized_pnl? * self.fx_rate?)
+    }
+
+    pub fn reporting_realized_pnl(&self) -> Option<Decimal> {
+        Some(self.realized_pnl * self.fx_rate?)
+    }
+}
+
+/// Positions plus their totals in the reporting currency.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionsReport {
+    pub positions: Vec<Position>,
+    pub reporting_ccy: String,
+    pub market_value: Money,
+    pub unrealized_pnl: Money,
+    pub realized_pnl: Money,
+    /// Conversions applied, one per foreign currency held.
+    pub rates: Vec<FxQuote>,
+    /// Currencies held without a rate into the reporting currency; their positions
+    /// are listed but left out of the totals.
+    pub unconverted: Vec<String>,
+}
+
+impl PositionsReport {
+    /// Warning to show when some positions could not be converted.
+    pub fn unconverted_note(&self) -> Option<String> {
+        (!self.unconverted.is_empty()).then(|| {
+            format!(
+                "No {} rate for {}: those positions are left out of the totals.",
+                self.reporting_ccy,
+                self.unconverted.join(", ")
+            )
+        })
+    }
+}
+
+/// Latest known price for an asset.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetQuote {
+    pub id: RecordId,
+    pub symbol: String,
+    pub ccy: String,
+    pub precision: u32,
+    pub last_price: Option<Decimal>,
+}
+
+#[derive(Debug, Default)]
+struct Holding {
+    qty: Decimal,
+    avg_cost: Decimal,
+    realized_pnl: Decimal,
+}
+
+impl Holding {
+    // Positive qty buys, negative qty sells. Fees opening a position go into its
+    // average cost (raising a long's, lowering a short's entry); fees reducing one are
+    // realized straight away.
+    fn apply(&mut self, qty: Decimal, price: Decimal, fees: Decimal) {
+        let same_direction = self.qty.is_zero() || self.qty.signum() == qty.signum();
+        if same_direction {
+            let held = self.qty.abs();
+            self.avg_cost =
+                (self.avg_cost * held + price * qty.abs() + fees * qty.signum()) / (held + qty.abs());
+            self.qty += qty;
+            return;
+        }
+
+        let closing = qty.abs().min(self.qty.abs());
+        self.realized_pnl += closing * (price - self.avg_cost) * self.qty.signum() - fees;
+        self.qty += qty;
+
+        if self.qty.is_zero() {
+            self.qty = Decimal::ZERO;
+            self.avg_cost = Decimal::ZERO;
+        } else if self.qty.signum() == qty.signum() {
+            // Flipped from long to short (or back): the remainder opens at this price.
+            self.avg_cost = price;
+        }
+    }
+}
+
+/// Folds executed trades per asset into positions, valued at the quotes given.
+/// Trades are applied in `trade_date` order whatever order they arrive in.
+pub fn fold_positions(trades: &[Trade], quotes: &[AssetQuote]) -> Vec<Position> {
+    let mut ordered: Vec<&Trade> = trades.iter().filter(|t| t.status.is_executed()).collect();
+    ordered.sort_by(|a, b| a.trade_date.cmp(&b.trade_date));
+
+    let mut holdings: Vec<(RecordId, Holding)> = Vec::new();
+    for trade in ordered {
+        match holdings.iter_mut().find(|(asset, _)| *asset == trade.asset) {
+            Some((_, holding)) => holding.apply(trade.signed_qty(), trade.price, trade.fees),
+            None => {
+                let mut holding = Holding::default();
+                holding.apply(trade.signed_qty(), trade.price, trade.fees);
+                holdings.push((trade.asset.clone(), holding));
+            }
+        }
+    }
+
+    holdings
+        .into_iter()
+        .map(|(asset, holding)| {
+            let quote = quotes.iter().find(|q| q.id == asset);
+            let symbol = quote
+                .map(|q| q.symbol.clone())
+                .unwrap_or_else(|| asset.key().to_string());
+            let ccy = quote.map(|q| q.ccy.clone()).unwrap_or_default();
+            let precision = quote.map(|q| q.precision).unwrap_or(2);
+            let last_price = quote.and_then(|q| q.last_price);
+            Position {
+                symbol,
+                ccy,
+                precision,
+                fx_rate: Some(Decimal::ONE),
+                qty: holding.qty,
+                avg_cost: holding.avg_cost,
+                cost_basis: holding.avg_cost * holding.qty,
+                last_price,
+                market_value: last_price.map(|price| price * holding.qty),
+                unrealized_pnl: last_price.map(|price| (price - holding.avg_cost) * holding.qty),
+                realized_pnl: holding.realized_pnl,
+                asset,
+            }
+        })
+        .collect()
+}
+
+/// Converts positions into `reporting_ccy` at the rates effective `at` and totals them.
+/// A position without a rate keeps `fx_rate: None` and stays out of the totals.
+pub fn build_report(
+    mut positions: Vec<Position>,
+    fx: &FxTable,
+    reporting_ccy: &str,
+    at: &surrealdb::Datetime,
+) -> PositionsReport {
+    let mut rates: Vec<FxQuote> = Vec::new();
+    let mut unconverted: Vec<String> = Vec::new();
+    for position in positions.iter_mut() {
+        match fx.quote(&position.ccy, reporting_ccy, at) {
+            Ok(quote) => {
+                position.fx_rate = Some(quote.rate);
+                if quote.from != quote.to && !rates.iter().any(|r| r.from == quote.from) {
+                    rates.push(quote);
+                }
+            }
+            Err(_) => {
+                position.fx_rate = None;
+                if !unconverted.contains(&position.ccy) {
+                    unconverted.push(position.ccy.clone());
+                }
+            }
+        }
+    }
+
+    let total = |amount: Decimal| Money::new(amount, reporting_ccy).rounded();
+    PositionsReport {
+        market_value: total(positions.iter().filter_map(Position::reporting_market_value).sum()),
+        unrealized_pnl: total(positions.iter().filter_map(Position::reporting_unrealized_pnl).sum()),
+        realized_pnl: total(positions.iter().filter_map(Position::reporting_realized_pnl).sum()),
+        reporting_ccy: reporting_ccy.to_string(),
+        positions,
+        rates,
+        unconverted,
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub async fn load_quotes() -> Result<Vec<AssetQuote>, ServerFnError> {
+    let mut response = get_db()
+        .query(
+            "SELECT id, symbol, ccy, precision,
+                (SELECT price, price_date FROM price WHERE asset = $parent.id ORDER BY price_date DESC LIMIT 1)[0].price
+                AS last_price
+             FROM asset",
+        )
+        .await?;
+    let quotes: Vec<AssetQuote> = response.take(0)?;
+
+    Ok(quotes)
+}
+
+/// Positions of the selected portfolio, or of all of them, in the user's reporting currency.
+#[cfg(feature = "ssr")]
+pub async fn positions_report(user: &User) -> Result<PositionsReport, ServerFnError> {
+    let mut response = portfolio_query(
+        user,
+        "SELECT * FROM trade WHERE owner = $owner AND (!$portfolio OR portfolio = $portfolio)
+         ORDER BY trade_date ASC",
+    )
+    .await?
+    .await?;
+    let trades: Vec<Trade> = response.take(0)?;
+    let quotes = load_quotes().await?;
+
+    let mut positions = fold_positions(&trades, &quotes);
+    positions.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+
+    let fx = load_fx_table().await?;
+    let now = surrealdb::Datetime::from(chrono::Utc::now());
+    Ok(build_report(positions, &fx, &reporting_ccy(user).await?, &now))
+}
+
+#[server(GetPositions, "/api")]
+pub async fn get_positions() -> Result<PositionsReport, ServerFnError> {
+    let user = require_user().await?;
+    positions_report(&user).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::model::{FxRate, TradeSide, TradeStatus};
+    use chrono::NaiveDate;
+    use surrealdb::Datetime;
+
+    fn day(date: &str) -> Datetime {
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+        Datetime::from(date.and_hms_opt(12, 0, 0).unwrap().and_utc())
+    }
+
+    fn buy(asset: &str, price: i64, qty: i64) -> Trade {
+        Trade {
+            id: None,
+            asset: RecordId::from_table_key("asset", asset),
+            side: TradeSide::Buy,
+            price: Decimal::from(price),
+            qty: Decimal::from(qty),
+            fees: Decimal::ZERO,
+            status: TradeStatus::Open,
+            trade_date: day("2024-01-02"),
+            portfolio: None,
+            owner: None,
+        }
+    }
+
+    fn quote(asset: &str, ccy: &str, last_price: i64) -> AssetQuote {
+        AssetQuote {
+            id: RecordId::from_table_key("asset", asset),
+            symbol: asset.to_uppercase(),
+            ccy: ccy.to_string(),
+            precision: 2,
+            last_price: Some(Decimal::from(last_price)),
+        }
+    }
+
+    // Applies (qty, price, fees) steps to an empty holding.
+    fn holding(steps: &[(i64, i64, i64)]) -> Holding {
+        let mut holding = Holding::default();
+        for &(qty, price, fees) in steps {
+            holding.apply(Decimal::from(qty), Decimal::from(price), Decimal::from(fees));
+        }
+        holding
+    }
+
+    #[test]
+    fn buys_average_their_cost_and_a_partial_sell_keeps_it() {
+        let bought = holding(&[(10, 100, 0), (10, 110, 0)]);
+        assert_eq!((bought.qty, bought.avg_cost), (Decimal::from(20), Decimal::from(105)));
+        assert!(bought.realized_pnl.is_zero());
+
+        let sold = holding(&[(10, 100, 0), (10, 110, 0), (-5, 120, 0)]);
+        assert_eq!((sold.qty, sold.avg_cost), (Decimal::from(15), Decimal::from(105)));
+        assert_eq!(sold.realized_pnl, Decimal::from(75));
+    }
+
+    #[test]
+    fn fees_raise_the_entry_and_are_realized_on_the_close() {
+        let opened = holding(&[(10, 100, 10)]);
+        assert_eq!(opened.avg_cost, Decimal::from(101));
+
+        // 10 × (110 − 101) − 5 in fees, and nothing left behind.
+        let closed = holding(&[(10, 100, 10), (-10, 110, 5)]);
+        assert_eq!(closed.realized_pnl, Decimal::from(85));
+        assert!(closed.qty.is_zero());
+        assert!(closed.avg_cost.is_zero());
+
+        // Fees on a short's opening lower its entry.
+        let short = holding(&[(-10, 50, 10)]);
+        assert_eq!((short.qty, short.avg_cost), (Decimal::from(-10), Decimal::from(49)));
+    }
+
+    #[test]
+    fn a_sell_beyond_the_holding_opens_a_short_at_its_price() {
+        let flipped = holding(&[(10, 100, 0), (-15, 90, 0)]);
+        assert_eq!(flipped.realized_pnl, Decimal::from(-100));
+        assert_eq!((flipped.qty, flipped.avg_cost), (Decimal::from(-5), Decimal::from(90)));
+
+        // Buying the short back at 80 gains 5 × 10.
+        let covered = holding(&[(10, 100, 0), (-15, 90, 0), (5, 80, 0)]);
+        assert_eq!(covered.realized_pnl, Decimal::from(-50));
+        assert!(covered.qty.is_zero());
+        assert!(covered.avg_cost.is_zero());
+    }
+
+    #[test]
+    fn position_without_a_rate_is_left_out_of_the_totals() {
+        let trades = [buy("aapl", 100, 2), buy("sony", 1000, 3)];
+        let quotes = [quote("aapl", "USD", 110), quote("sony", "JPY", 1200)];
+        let fx = FxTable::new(vec![FxRate {
+            id: None,
+            base: "EUR".to_string(),
+            quote: "USD".to_string(),
+            rate: Decimal::from(2),
+            rate_date: day("2024-01-01"),
+        }]);
+
+        let report = build_report(fold_positions(&trades, &quotes), &fx, "EUR", &day("2024-02-01"));
+
+        assert_eq!(report.unconverted, vec!["JPY".to_string()]);
+        let sony = report.positions.iter().find(|p| p.symbol == "SONY").unwrap();
+        assert_eq!(sony.fx_rate, None);
+        assert_eq!(sony.reporting_market_value(), None);
+        // 2 AAPL at 110 USD, 2 USD to the EUR.
+        assert_eq!(report.market_value, Money::new(Decimal::from(110), "EUR"));
+        assert_eq!(report.unrealized_pnl, Money::new(Decimal::from(10), "EUR"));
+        assert!(report.unconverted_note().unwrap().contains("JPY"));
+    }
+}
@@ -0,0 +1,282 @@
+use leptos::prelude::ServerFnError;
+use leptos::server;
+use serde::{Deserialize, Serialize};
+
+use crate::backend::model::Decimal;
+#[cfg(feature = "ssr")]
+use crate::backend::auth::require_user;
+#[cfg(feature = "ssr")]
+use crate::backend::charts::{candlestick_svg, line_svg, sparse_labels, Candle};
+#[cfg(feature = "ssr")]
+use crate::backend::db::get_db;
+#[cfg(feature = "ssr")]
+use rust_decimal::prelude::ToPrimitive;
+#[cfg(feature = "ssr")]
+use surrealdb::{Datetime, RecordId};
+
+/// Window of price history shown on the asset page, ending at the latest price.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PriceRange {
+    #[serde(rename = "1w")]
+    Week,
+    #[default]
+    #[serde(rename = "1m")]
+    Month,
+    #[serde(rename = "3m")]
+    Quarter,
+    #[serde(rename = "1y")]
+    Year,
+    #[serde(rename = "all")]
+    All,
+}
+
+impl PriceRange {
+    pub const ALL: [PriceRange; 5] = [
+        PriceRange::Week,
+        PriceRange::Month,
+        PriceRange::Quarter,
+        PriceRange::Year,
+        PriceRange::All,
+    ];
+
+    /// Value used in the `range` query parameter.
+    pub fn as_param(self) -> &'static str {
+        match self {
+            PriceRange::Week => "1w",
+            PriceRange::Month => "1m",
+            PriceRange::Quarter => "3m",
+            PriceRange::Year => "1y",
+            PriceRange::All => "all",
+        }
+    }
+
+    pub fn from_param(value: &str) -> Option<PriceRange> {
+        PriceRange::ALL
+            .into_iter()
+            .find(|range| range.as_param().eq_ignore_ascii_case(value.trim()))
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PriceRange::Week => "1W",
+            PriceRange::Month => "1M",
+            PriceRange::Quarter => "3M",
+            PriceRange::Year => "1Y",
+            PriceRange::All => "All",
+        }
+    }
+
+    /// Calendar days covered, `None` for the whole history.
+    pub fn days(self) -> Option<i64> {
+        match self {
+            PriceRange::Week => Some(7),
+            PriceRange::Month => Some(30),
+            PriceRange::Quarter => Some(91),
+            PriceRange::Year => Some(365),
+            PriceRange::All => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChartKind {
+    #[default]
+    Line,
+    Candlestick,
+}
+
+impl ChartKind {
+    pub const ALL: [ChartKind; 2] = [ChartKind::Line, ChartKind::Candlestick];
+
+    /// Value used in the `chart` query parameter.
+    pub fn as_param(self) -> &'static str {
+        match self {
+            ChartKind::Line => "line",
+            ChartKind::Candlestick => "candlestick",
+        }
+    }
+
+    pub fn from_param(value: &str) -> Option<ChartKind> {
+        ChartKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_param().eq_ignore_ascii_case(value.trim()))
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ChartKind::Line => "Line",
+            ChartKind::Candlestick => "Candles",
+        }
+    }
+}
+
+/// Price history of one asset over a range, with the chart pre-rendered to SVG.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceHistory {
+    pub symbol: String,
+    pub ccy: String,
+    pub precision: u32,
+    pub range: PriceRange,
+    pub kind: ChartKind,
+    pub points: usize,
+    pub first: Option<Decimal>,
+    pub last: Option<Decimal>,
+    pub low: Option<Decimal>,
+    pub high: Option<Decimal>,
+    pub change_pct: Option<f64>,
+    /// Empty when there are no prices in the range.
+    pub svg: String,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Deserialize)]
+struct AssetRef {
+    id: RecordId,
+    symbol: String,
+    ccy: String,
+    precision: u32,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Deserialize)]
+struct PricePoint {
+    price: Decimal,
+    price_date: Datetime,
+}
+
+#[cfg(feature = "ssr")]
+fn utc(date: &Datetime) -> chrono::DateTime<chrono::Utc> {
+    **date.into_inner_ref()
+}
+
+// Days per candle: daily bars up to a quarter, weekly up to a year, monthly beyond.
+#[cfg(feature = "ssr")]
+fn candle_days(points: &[PricePoint]) -> i64 {
+    match (points.first(), points.last()) {
+        (Some(first), Some(last)) => {
+            let span = (utc(&last.price_date) - utc(&first.price_date)).num_days();
+            if span <= 100 {
+                1
+            } else if span <= 400 {
+                7
+            } else {
+                30
+            }
+        }
+        _ => 1,
+    }
+}
+
+// Only closes are stored, so a bar opens at the previous bar's close.
+#[cfg(feature = "ssr")]
+fn candles(points: &[PricePoint], days: i64) -> (Vec<String>, Vec<Candle>) {
+    let Some(first) = points.first() else {
+        return (Vec::new(), Vec::new());
+    };
+    let start = utc(&first.price_date);
+
+    let mut labels = Vec::new();
+    let mut bars: Vec<Candle> = Vec::new();
+    let mut bucket = None;
+    let mut previous_close = None;
+    for point in points {
+        let close = point.price.to_f32().unwrap_or_default();
+        let date = utc(&point.price_date);
+        let index = (date - start).num_days() / days;
+        match bars.last_mut() {
+            Some(bar) if bucket == Some(index) => {
+                bar.close = close;
+                bar.low = bar.low.min(close);
+                bar.high = bar.high.max(close);
+            }
+            _ => {
+                let open = previous_close.unwrap_or(close);
+                bars.push(Candle {
+                    open,
+                    close,
+                    low: open.min(close),
+                    high: open.max(close),
+                });
+                labels.push(date.format("%Y-%m-%d").to_string());
+                bucket = Some(index);
+            }
+        }
+        previous_close = Some(close);
+    }
+
+    (labels, bars)
+}
+
+#[server(GetPriceHistory, "/api")]
+pub async fn get_price_history(
+    symbol: String,
+    range: PriceRange,
+    kind: ChartKind,
+) -> Result<PriceHistory, ServerFnError> {
+    require_user().await?;
+    let db = get_db();
+    let mut response = db
+        .query("SELECT id, symbol, ccy, precision FROM asset WHERE symbol = $symbol LIMIT 1")
+        .bind(("symbol", symbol.trim().to_string()))
+        .await?;
+    let assets: Vec<AssetRef> = response.take(0)?;
+    let asset = assets
+        .into_iter()
+        .next()
+        .ok_or_else(|| ServerFnError::new(format!("Unknown asset '{}'", symbol.trim())))?;
+
+    let mut response = db
+        .query("SELECT price, price_date FROM price WHERE asset = $asset ORDER BY price_date ASC")
+        .bind(("asset", asset.id))
+        .await?;
+    let mut points: Vec<PricePoint> = response.take(0)?;
+    if let (Some(days), Some(last)) = (range.days(), points.last()) {
+        let since = utc(&last.price_date) - chrono::Duration::days(days);
+        points.retain(|p| utc(&p.price_date) >= since);
+    }
+
+    let first = points.first().map(|p| p.price);
+    let last = points.last().map(|p| p.price);
+    let change_pct = match (first, last) {
+        (Some(first), Some(last)) if !first.is_zero() => {
+            ((last - first) / first * Decimal::ONE_HUNDRED).to_f64()
+        }
+        _ => None,
+    };
+
+    let title = format!("{} ({}) - {}", asset.symbol, asset.ccy, range.label());
+    let svg = if points.is_empty() {
+        String::new()
+    } else {
+        match kind {
+            ChartKind::Line => {
+                let labels = points
+                    .iter()
+                    .map(|p| p.price_date.into_inner_ref().format("%Y-%m-%d").to_string())
+                    .collect();
+                let closes = points.iter().map(|p| p.price.to_f32().unwrap_or_default()).collect();
+                line_svg(&title, sparse_labels(labels, 8), vec![(asset.symbol.clone(), closes)])?
+            }
+            ChartKind::Candlestick => {
+                let (labels, bars) = candles(&points, candle_days(&points));
+                candlestick_svg(&title, sparse_labels(labels, 8), &bars)?
+            }
+        }
+    };
+
+    Ok(PriceHistory {
+        symbol: asset.symbol,
+        ccy: asset.ccy,
+        precision: asset.precision,
+        range,
+        kind,
+        points: points.len(),
+        first,
+        last,
+        low: points.iter().map(|p| p.price).min(),
+        high: points.iter().map(|p| p.price).max(),
+        change_pct,
+        svg,
+    })
+}
@@ -0,0 +1,342 @@
+use leptos::prelude::ServerFnError;
+use leptos::server;
+use serde::{Deserialize, Serialize};
+
+use crate::backend::prices::PriceRange;
+#[cfg(feature = "ssr")]
+use crate::backend::auth::require_user;
+#[cfg(feature = "ssr")]
+use crate::backend::db::get_db;
+#[cfg(feature = "ssr")]
+use crate::backend::model::Decimal;
+#[cfg(feature = "ssr")]
+use crate::backend::performance::{mean, pct, std_dev, value_series, DAYS_PER_YEAR};
+#[cfg(feature = "ssr")]
+use crate::backend::positions::positions_report;
+#[cfg(feature = "ssr")]
+use crate::backend::settings::reporting_ccy;
+#[cfg(feature = "ssr")]
+use chrono::Duration;
+#[cfg(feature = "ssr")]
+use rust_decimal::prelude::ToPrimitive;
+#[cfg(feature = "ssr")]
+use surrealdb::{Datetime, RecordId};
+
+/// Confidence level of the value at risk figures.
+pub const CONFIDENCE: f64 = 0.95;
+
+// Standard normal quantile at `CONFIDENCE`.
+#[cfg(feature = "ssr")]
+const Z_SCORE: f64 = 1.644_853_626_951_472_2;
+
+/// Risk measured from one series of periodic returns. Percentages are of value;
+/// losses are positive.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RiskMetrics {
+    /// Returns the figures are computed from.
+    pub observations: usize,
+    /// Standard deviation of returns, per year.
+    pub volatility_pct: Option<f64>,
+    /// Largest fall from a peak to a later trough.
+    pub max_drawdown_pct: Option<f64>,
+    /// One-period loss not exceeded at `CONFIDENCE`, from the returns seen.
+    pub var_pct: Option<f64>,
+    /// Average loss beyond `var_pct`.
+    pub cvar_pct: Option<f64>,
+    /// The same two, assuming normally distributed returns.
+    pub parametric_var_pct: Option<f64>,
+    pub parametric_cvar_pct: Option<f64>,
+    /// Mean return per year over volatility, with a zero risk-free rate.
+    pub sharpe: Option<f64>,
+    /// Mean return per year over the yearly deviation of losses alone.
+    pub sortino: Option<f64>,
+}
+
+/// Risk of one asset, in its own currency.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetRisk {
+    pub symbol: String,
+    pub ccy: String,
+    pub metrics: RiskMetrics,
+}
+
+/// Risk of the selected portfolio, in the reporting currency, and of each asset it holds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortfolioRisk {
+    pub reporting_ccy: String,
+    pub range: PriceRange,
+    pub portfolio: RiskMetrics,
+    pub assets: Vec<AssetRisk>,
+    /// Set when holdings or cash without a rate into the reporting currency were left
+    /// out of the portfolio's returns.
+    pub unconverted_note: Option<String>,
+}
+
+/// Metrics of `returns`, observed `periods_per_year` times a year. Fewer than two
+/// returns give nothing but the count.
+#[cfg(feature = "ssr")]
+pub fn risk_metrics(returns: &[f64], periods_per_year: f64) -> RiskMetrics {
+    let observations = returns.len();
+    let (Some(average), Some(sd)) = (mean(returns), std_dev(returns)) else {
+        return RiskMetrics {
+            observations,
+            ..RiskMetrics::default()
+        };
+    };
+
+    let mut growth = 1.0_f64;
+    let mut peak = 1.0_f64;
+    let mut drawdown = 0.0_f64;
+    for r in returns {
+        growth *= 1.0 + r;
+        peak = peak.max(growth);
+        drawdown = drawdown.max(1.0 - growth / peak);
+    }
+
+    let mut sorted = returns.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    // The tail holds the worst ⌈5%⌉ of the returns; its best one is the historical VaR.
+    // 1 - 0.95 is a little over 5% in floating point, so the count is rounded before
+    // the ceiling lest twenty returns get a tail of two.
+    let tail_len = (((1.0 - CONFIDENCE) * observations as f64 * 1e9).round() / 1e9).ceil() as usize;
+    let cutoff = tail_len.clamp(1, observations) - 1;
+    let tail = &sorted[..=cutoff];
+    // Expected shortfall of a standard normal: its density at the quantile over the tail's probability.
+    let shortfall = (-Z_SCORE * Z_SCORE / 2.0).exp() / (2.0 * std::f64::consts::PI).sqrt() / (1.0 - CONFIDENCE);
+
+    let yearly_mean = average * periods_per_year;
+    let volatility = sd * periods_per_year.sqrt();
+    let downside = (returns.iter().map(|r| r.min(0.0).powi(2)).sum::<f64>() / observations as f64).sqrt()
+        * periods_per_year.sqrt();
+    let ratio = |deviation: f64| (deviation > 0.0).then(|| yearly_mean / deviation).filter(|r| r.is_finite());
+
+    RiskMetrics {
+        observations,
+        volatility_pct: pct(volatility),
+        max_drawdown_pct: pct(drawdown),
+        var_pct: pct(-sorted[cutoff]),
+        cvar_pct: mean(tail).and_then(|m| pct(-m)),
+        parametric_var_pct: pct(Z_SCORE * sd - average),
+        parametric_cvar_pct: pct(shortfall * sd - average),
+        sharpe: ratio(volatility),
+        sortino: ratio(downside),
+    }
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Deserialize)]
+struct AssetRef {
+    id: RecordId,
+    symbol: String,
+    ccy: String,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Deserialize)]
+pub(crate) struct PricePoint {
+    pub asset: RecordId,
+    pub price: Decimal,
+    pub price_date: Datetime,
+}
+
+/// Metrics of the returns between consecutive prices (oldest first) within `range`
+/// of the latest one. Prices need not be daily: the year is divided by the span
+/// they cover.
+#[cfg(feature = "ssr")]
+fn price_metrics(prices: &[&PricePoint], range: PriceRange) -> RiskMetrics {
+    let utc = |date: &Datetime| **date.into_inner_ref();
+    let since = match (range.days(), prices.last()) {
+        (Some(days), Some(last)) => Some(utc(&last.price_date) - Duration::days(days)),
+        _ => None,
+    };
+    let prices: Vec<&PricePoint> = prices
+        .iter()
+        .copied()
+        .filter(|p| since.is_none_or(|since| utc(&p.price_date) >= since))
+        .collect();
+    let returns: Vec<f64> = prices
+        .windows(2)
+        .filter(|pair| pair[0].price > Decimal::ZERO)
+        .map(|pair| (pair[1].price / pair[0].price - Decimal::ONE).to_f64().unwrap_or_default())
+        .collect();
+    let span = match (prices.first(), prices.last()) {
+        (Some(first), Some(last)) => (utc(&last.price_date) - utc(&first.price_date)).num_days(),
+        _ => 0,
+    };
+    if span <= 0 {
+        return risk_metrics(&[], DAYS_PER_YEAR);
+    }
+    risk_metrics(&returns, returns.len() as f64 * DAYS_PER_YEAR / span as f64)
+}
+
+/// Prices of `assets`, oldest first.
+#[cfg(feature = "ssr")]
+pub(crate) async fn load_prices(assets: Vec<RecordId>) -> Result<Vec<PricePoint>, ServerFnError> {
+    let mut response = get_db()
+        .query("SELECT asset, price, price_date FROM price WHERE asset IN $assets ORDER BY price_date ASC")
+        .bind(("assets", assets))
+        .await?;
+    Ok(response.take(0)?)
+}
+
+/// Risk of one asset over `range`, from its stored prices.
+#[server(GetAssetRisk, "/api")]
+pub async fn get_asset_risk(symbol: String, range: PriceRange) -> Result<AssetRisk, ServerFnError> {
+    require_user().await?;
+    let mut response = get_db()
+        .query("SELECT id, symbol, ccy FROM asset WHERE symbol = $symbol LIMIT 1")
+        .bind(("symbol", symbol.trim().to_string()))
+        .await?;
+    let assets: Vec<AssetRef> = response.take(0)?;
+    let asset = assets
+        .into_iter()
+        .next()
+        .ok_or_else(|| ServerFnError::new(format!("Unknown asset '{}'", symbol.trim())))?;
+
+    let prices = load_prices(vec![asset.id]).await?;
+    let prices: Vec<&PricePoint> = prices.iter().collect();
+    Ok(AssetRisk {
+        symbol: asset.symbol,
+        ccy: asset.ccy,
+        metrics: price_metrics(&prices, range),
+    })
+}
+
+/// Risk of the selected portfolio over `range` ending today, from its daily
+/// time-weighted returns, and of every asset it holds over the same range.
+#[server(GetPortfolioRisk, "/api")]
+pub async fn get_portfolio_risk(range: PriceRange) -> Result<PortfolioRisk, ServerFnError> {
+    let user = require_user().await?;
+    let today = chrono::Utc::now().date_naive();
+    let from = range.days().map(|days| today - Duration::days(days));
+    let series = value_series(&user, from, today).await?;
+
+    let report = positions_report(&user).await?;
+    let held: Vec<_> = report.positions.iter().filter(|p| !p.qty.is_zero()).collect();
+    let prices = load_prices(held.iter().map(|p| p.asset.clone()).collect()).await?;
+    let assets = held
+        .into_iter()
+        .map(|position| {
+            let own: Vec<&PricePoint> = prices.iter().filter(|p| p.asset == position.asset).collect();
+            AssetRisk {
+                symbol: position.symbol.clone(),
+                ccy: position.ccy.clone(),
+                metrics: price_metrics(&own, range),
+            }
+        })
+        .collect();
+
+    Ok(PortfolioRisk {
+        reporting_ccy: reporting_ccy(&user).await?,
+        range,
+        portfolio: risk_metrics(&series.daily_returns(), DAYS_PER_YEAR),
+        assets,
+        unconverted_note: series.unconverted_note(),
+    })
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use std::str::FromStr;
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("a figure");
+        assert!((actual - expected).abs() < 1e-6, "{} != {}", actual, expected);
+    }
+
+    // Twenty daily returns, in percent: mean 0.15, sample deviation 2.323224, worst -5.
+    fn twenty() -> Vec<f64> {
+        [1.0, -2.0, 3.0, -1.0, 2.0, -4.0, 1.0, 0.0, 2.0, -3.0, 1.0, 1.0, -1.0, 2.0, -5.0, 3.0, 1.0, -2.0, 2.0, 2.0]
+            .iter()
+            .map(|r| r / 100.0)
+            .collect()
+    }
+
+    fn point(date: &str, price: &str) -> PricePoint {
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+        PricePoint {
+            asset: RecordId::from(("asset", "aapl")),
+            price: Decimal::from_str(price).unwrap(),
+            price_date: Datetime::from(date.and_hms_opt(12, 0, 0).unwrap().and_utc()),
+        }
+    }
+
+    #[test]
+    fn fewer_than_two_returns_give_only_the_count() {
+        let metrics = risk_metrics(&[0.01], 252.0);
+        assert_eq!(metrics.observations, 1);
+        assert!(metrics.volatility_pct.is_none() && metrics.var_pct.is_none() && metrics.sharpe.is_none());
+    }
+
+    #[test]
+    fn historical_var_and_cvar_take_the_worst_five_percent() {
+        // ⌈5% of 20⌉ = 1: the tail is the single worst return.
+        let metrics = risk_metrics(&twenty(), 252.0);
+        assert_eq!(metrics.observations, 20);
+        assert_close(metrics.var_pct, 5.0);
+        assert_close(metrics.cvar_pct, 5.0);
+
+        // ⌈5% of 21⌉ = 2: VaR is the second worst, CVaR the mean of -5 and -4.
+        let mut returns = twenty();
+        returns.push(-0.04);
+        let metrics = risk_metrics(&returns, 252.0);
+        assert_close(metrics.var_pct, 4.0);
+        assert_close(metrics.cvar_pct, 4.5);
+    }
+
+    #[test]
+    fn drawdown_compounds_from_the_peak() {
+        // Growth peaks at 1.029485 after the fifth return and is never above it again;
+        // the trough is 0.966465 after the -5%, 1 - 0.966465 / 1.029485 below the peak.
+        assert_close(risk_metrics(&twenty(), 252.0).max_drawdown_pct, 6.121_480_484_270_669);
+        // A single fall of a third from a new high, then a partial recovery.
+        assert_close(risk_metrics(&[0.5, -1.0 / 3.0, 0.25], 252.0).max_drawdown_pct, 100.0 / 3.0);
+        assert_close(risk_metrics(&[0.01, 0.02], 252.0).max_drawdown_pct, 0.0);
+    }
+
+    #[test]
+    fn parametric_var_and_cvar_follow_the_normal_distribution() {
+        // VaR = 1.644854 × 2.323224 - 0.15; CVaR = φ(1.644854) / 5% = 2.062713 times the
+        // deviation, less the mean.
+        let metrics = risk_metrics(&twenty(), 252.0);
+        assert_close(metrics.parametric_var_pct, 3.671_362_950_691_839);
+        assert_close(metrics.parametric_cvar_pct, 4.642_143_307_690_796);
+    }
+
+    #[test]
+    fn sharpe_and_sortino_annualise_mean_and_deviation() {
+        // Volatility 2.323224% × √252; downside √(Σ min(r, 0)² / 20) × √252 = √(60/20)% × √252.
+        let metrics = risk_metrics(&twenty(), 252.0);
+        assert_close(metrics.volatility_pct, 36.880_033_108_787_52);
+        assert_close(metrics.sharpe, 1.024_944_849_927_297_5);
+        assert_close(metrics.sortino, 1.374_772_708_486_751_6);
+        // No losses: nothing to divide by.
+        assert!(risk_metrics(&[0.01, 0.02], 252.0).sortino.is_none());
+    }
+
+    #[test]
+    fn irregular_prices_are_annualised_over_the_days_they_span() {
+        // +10%, -10%, +10% over ten days: 3 × 365 / 10 = 109.5 periods a year. The
+        // price forty days before the last is outside the month and left out.
+        let prices = [
+            point("2024-01-01", "50"),
+            point("2024-02-01", "100"),
+            point("2024-02-02", "110"),
+            point("2024-02-09", "99"),
+            point("2024-02-11", "108.9"),
+        ];
+        let prices: Vec<&PricePoint> = prices.iter().collect();
+        let metrics = price_metrics(&prices, PriceRange::Month);
+        assert_eq!(metrics.observations, 3);
+        assert_close(metrics.volatility_pct, 120.830_459_735_945_72);
+        assert_close(metrics.sharpe, 3.020_761_493_398_642_6);
+        assert_close(metrics.var_pct, 10.0);
+
+        // Over all of them the first return, +100% across a month, joins in.
+        assert_eq!(price_metrics(&prices, PriceRange::All).observations, 4);
+        // A single day spans nothing.
+        assert_eq!(price_metrics(&prices[4..], PriceRange::All).observations, 0);
+    }
+}
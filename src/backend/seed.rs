@@ -0,0 +1,527 @@
+use crate::backend::auth::hash_password;
+use crate::backend::portfolios::{ensure_portfolio, DEFAULT_PORTFOLIO};
+use crate::backend::model::{
+    Category, Decimal, FxRate, Price, Risk, Trade, TradeSide, TradeStatus, Wallet,
+};
+use chrono::{Duration, NaiveDate};
+use leptos::prelude::ServerFnError;
+use rand_chacha::rand_core::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::Serialize;
+use surrealdb::engine::any::Any;
+use surrealdb::{Datetime, RecordId, Surreal};
+
+/// Knobs for the demo data generator. The same options always produce the
+/// same rows, so screenshots and tests can rely on the numbers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeedOptions {
+    pub seed: u64,
+    /// Number of daily prices generated per asset, ending on `end`.
+    pub days: u32,
+    /// Last price day; [`DEFAULT_END`] unless given, so the defaults never change.
+    pub end: NaiveDate,
+    /// Account that owns the generated trades and wallet entries; created when missing.
+    pub user: String,
+}
+
+impl Default for SeedOptions {
+    fn default() -> Self {
+        SeedOptions {
+            seed: 42,
+            days: 365,
+            end: NaiveDate::parse_from_str(DEFAULT_END, "%Y-%m-%d").expect("valid default end"),
+            user: "demo".to_string(),
+        }
+    }
+}
+
+/// Last price day of the default demo data.
+pub const DEFAULT_END: &str = "2025-12-31";
+
+#[derive(Debug, Default)]
+pub struct SeedSummary {
+    pub categories: usize,
+    pub risks: usize,
+    pub assets: usize,
+    pub prices: usize,
+    pub trades: usize,
+    pub wallet: usize,
+    pub fx_rates: usize,
+    /// Whether the owning account had to be created, with [`DEMO_PASSWORD`].
+    pub created_user: bool,
+}
+
+/// Password given to the seed account when `seed` has to create it.
+pub const DEMO_PASSWORD: &str = "demo-password";
+
+// Same set `top_assets::category_bg_class` knows how to colour.
+const CATEGORIES: &[(&str, &str, &str)] = &[
+    ("bonds", "Bonds", "Government and corporate debt"),
+    ("commodities", "Commodities", "Metals, energy and agricultural futures"),
+    ("crypto", "Crypto", "Digital assets"),
+    ("forex", "Forex", "Currency pairs"),
+    ("indice", "Indices", "Equity market indices"),
+    ("stocks", "Stocks", "Listed equities"),
+];
+
+const RISKS: &[(&str, &str, u8)] = &[
+    ("low", "Low", 2),
+    ("moderate", "Moderate", 4),
+    ("elevated", "Elevated", 6),
+    ("high", "High", 8),
+];
+
+struct AssetSpec {
+    symbol: &'static str,
+    category: &'static str,
+    risk: &'static str,
+    ccy: &'static str,
+    start_price: f64,
+    // Annualised GBM parameters.
+    drift: f64,
+    volatility: f64,
+}
+
+const ASSETS: &[AssetSpec] = &[
+    AssetSpec { symbol: "AAPL", category: "stocks", risk: "moderate", ccy: "USD", start_price: 180.0, drift: 0.08, volatility: 0.25 },
+    AssetSpec { symbol: "MSFT", category: "stocks", risk: "moderate", ccy: "USD", start_price: 400.0, drift: 0.09, volatility: 0.22 },
+    AssetSpec { symbol: "TSLA", category: "stocks", risk: "high", ccy: "USD", start_price: 220.0, drift: 0.05, volatility: 0.55 },
+    AssetSpec { symbol: "SPX", category: "indice", risk: "moderate", ccy: "USD", start_price: 5000.0, drift: 0.07, volatility: 0.16 },
+    AssetSpec { symbol: "CAC40", category: "indice", risk: "moderate", ccy: "EUR", start_price: 7500.0, drift: 0.05, volatility: 0.17 },
+    AssetSpec { symbol: "US10Y", category: "bonds", risk: "low", ccy: "USD", start_price: 98.0, drift: 0.02, volatility: 0.06 },
+    AssetSpec { symbol: "BUND", category: "bonds", risk: "low", ccy: "EUR", start_price: 130.0, drift: 0.01, volatility: 0.05 },
+    AssetSpec { symbol: "GOLD", category: "commodities", risk: "moderate", ccy: "USD", start_price: 2000.0, drift: 0.04, volatility: 0.14 },
+    AssetSpec { symbol: "BRENT", category: "commodities", risk: "elevated", ccy: "USD", start_price: 82.0, drift: 0.0, volatility: 0.30 },
+    AssetSpec { symbol: "BTC", category: "crypto", risk: "high", ccy: "USD", start_price: 60000.0, drift: 0.15, volatility: 0.65 },
+    AssetSpec { symbol: "ETH", category: "crypto", risk: "high", ccy: "USD", start_price: 3000.0, drift: 0.12, volatility: 0.75 },
+    AssetSpec { symbol: "EURUSD", category: "forex", risk: "low", ccy: "USD", start_price: 1.09, drift: 0.0, volatility: 0.07 },
+];
+
+// Daily EUR crosses: (quote currency, starting rate, annualised volatility).
+const FX_PAIRS: &[(&str, f64, f64)] = &[
+    ("USD", 1.09, 0.07),
+    ("GBP", 0.86, 0.06),
+    ("CHF", 0.95, 0.06),
+    ("JPY", 160.0, 0.09),
+];
+
+// Assets stored with record links rather than the fetched `Risk` the UI reads.
+#[derive(Serialize)]
+struct AssetRow {
+    id: RecordId,
+    symbol: String,
+    category: RecordId,
+    risk: RecordId,
+    ccy: String,
+    precision: u32,
+}
+
+// Uniform in [0, 1) from the top 53 bits.
+fn uniform(rng: &mut ChaCha8Rng) -> f64 {
+    (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+// Standard normal via Box-Muller.
+fn standard_normal(rng: &mut ChaCha8Rng) -> f64 {
+    let u1 = 1.0 - uniform(rng);
+    let u2 = uniform(rng);
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+/// Daily closes following geometric Brownian motion, one value per calendar day.
+fn gbm_path(rng: &mut ChaCha8Rng, start: f64, drift: f64, volatility: f64, days: u32) -> Vec<f64> {
+    let dt = 1.0 / 365.0;
+    let step_drift = (drift - 0.5 * volatility * volatility) * dt;
+    let step_vol = volatility * dt.sqrt();
+
+    let mut price = start;
+    (0..days)
+        .map(|day| {
+            if day > 0 {
+                price *= (step_drift + step_vol * standard_normal(rng)).exp();
+            }
+            price
+        })
+        .collect()
+}
+
+// Simulated value as an exact decimal with `decimals` places.
+fn to_decimal(value: f64, decimals: u32) -> Decimal {
+    Decimal::from_f64_retain(value)
+        .unwrap_or_default()
+        .round_dp(decimals)
+}
+
+fn datetime_at(date: NaiveDate, hour: u32) -> Datetime {
+    let naive = date.and_hms_opt(hour, 0, 0).expect("valid time of day");
+    Datetime::from(naive.and_utc())
+}
+
+#[derive(Serialize)]
+struct SeedUser {
+    username: String,
+    display_name: String,
+    password_hash: String,
+}
+
+// Id of the seed account, and whether it was created just now.
+async fn seed_user(db: &Surreal<Any>, username: &str) -> Result<(RecordId, bool), ServerFnError> {
+    let username = username.trim().to_ascii_lowercase();
+    let mut response = db
+        .query("SELECT VALUE id FROM user WHERE username = $username")
+        .bind(("username", username.clone()))
+        .await?;
+    let existing: Vec<RecordId> = response.take(0)?;
+    if let Some(id) = existing.into_iter().next() {
+        return Ok((id, false));
+    }
+
+    let mut response = db
+        .query("CREATE ONLY user CONTENT $user RETURN VALUE id")
+        .bind((
+            "user",
+            SeedUser {
+                display_name: username.clone(),
+                username,
+                password_hash: hash_password(DEMO_PASSWORD)?,
+            },
+        ))
+        .await?;
+    let created: Option<RecordId> = response.take(0)?;
+    let id = created.ok_or_else(|| ServerFnError::new("Seed account was not created"))?;
+    Ok((id, true))
+}
+
+/// Writes generated data in a single transaction. Categories, risks, assets, prices
+/// and FX rates are upserted on fixed ids, so seeding again rewrites the same rows
+/// and leaves other rows of those shared tables alone. The seed account's trades and
+/// wallet entries are replaced; other accounts' data is not touched.
+pub async fn seed(db: &Surreal<Any>, options: &SeedOptions) -> Result<SeedSummary, ServerFnError> {
+    let (owner, created_user) = seed_user(db, &options.user).await?;
+    // Crypto is booked in its own portfolio so the consolidated view has something to add up.
+    let main_portfolio = ensure_portfolio(db, &owner, DEFAULT_PORTFOLIO).await?;
+    let crypto_portfolio = ensure_portfolio(db, &owner, "Crypto").await?;
+    let days = options.days.max(2);
+    let start = options.end - Duration::days(i64::from(days) - 1);
+    let date_of = |day: u32| start + Duration::days(i64::from(day));
+
+    let categories: Vec<Category> = CATEGORIES
+        .iter()
+        .map(|(key, name, description)| Category {
+            id: Some(RecordId::from_table_key("category", *key)),
+            name: name.to_string(),
+            description: description.to_string(),
+        })
+        .collect();
+
+    let risks: Vec<Risk> = RISKS
+        .iter()
+        .map(|(key, name, score)| Risk {
+            id: Some(RecordId::from_table_key("risk", *key)),
+            name: name.to_string(),
+            risk_score: *score,
+        })
+        .collect();
+
+    let mut assets = Vec::new();
+    let mut prices = Vec::new();
+    let mut trades = Vec::new();
+
+    for (index, spec) in ASSETS.iter().enumerate() {
+        let asset_id = RecordId::from_table_key("asset", spec.symbol);
+        let portfolio = if spec.category == "crypto" { &crypto_portfolio } else { &main_portfolio };
+        let decimals = if spec.start_price < 10.0 { 4 } else { 2 };
+        assets.push(AssetRow {
+            id: asset_id.clone(),
+            symbol: spec.symbol.to_string(),
+            category: RecordId::from_table_key("category", spec.category),
+            risk: RecordId::from_table_key("risk", spec.risk),
+            ccy: spec.ccy.to_string(),
+            precision: decimals,
+        });
+
+        // One stream per asset so changing `days` for one does not reshuffle the others.
+        let mut rng = ChaCha8Rng::seed_from_u64(options.seed);
+        rng.set_stream(index as u64);
+
+        let path = gbm_path(&mut rng, spec.start_price, spec.drift, spec.volatility, days);
+        for (day, close) in path.iter().enumerate() {
+            let date = date_of(day as u32);
+            prices.push(Price {
+                id: Some(RecordId::from_table_key("price", format!("{}_{}", spec.symbol, date))),
+                asset: asset_id.clone(),
+                price: to_decimal(*close, decimals),
+                price_date: datetime_at(date, 17),
+            });
+        }
+
+        // Every other asset is held: an older round trip, bought and sold again so both
+        // trades are closed, an open buy and sometimes a pending order in the last week.
+        if index % 2 == 0 {
+            let budget = 5000.0 + 10000.0 * uniform(&mut rng);
+            let bought = (uniform(&mut rng) * f64::from(days) * 0.3) as u32;
+            let sold = (bought + 1 + (uniform(&mut rng) * f64::from(days) * 0.2) as u32).min(days - 1);
+            let held = (f64::from(days) * (0.3 + uniform(&mut rng) * 0.6)) as u32;
+            let round_trip_qty = budget / path[bought as usize];
+            let picks = [
+                (TradeSide::Buy, TradeStatus::Closed, bought, round_trip_qty),
+                (TradeSide::Sell, TradeStatus::Closed, sold, round_trip_qty),
+                (TradeSide::Buy, TradeStatus::Open, held, budget / path[held as usize]),
+            ];
+            for (side, status, day, qty) in picks {
+                let price = path[day as usize];
+                trades.push(Trade {
+                    id: None,
+                    asset: asset_id.clone(),
+                    side,
+                    price: to_decimal(price, decimals),
+                    qty: if qty >= 1.0 { to_decimal(qty, 0) } else { to_decimal(qty, 4) },
+                    // A flat commission plus 0.1% of the amount.
+                    fees: to_decimal(1.0 + price * qty * 0.001, 2),
+                    status,
+                    trade_date: datetime_at(date_of(day), 10),
+                    portfolio: Some(portfolio.clone()),
+                    owner: Some(owner.clone()),
+                });
+            }
+            if uniform(&mut rng) < 0.5 {
+                let day = days - 1 - (uniform(&mut rng) * 7.0) as u32 % days;
+                let price = path[day as usize];
+                trades.push(Trade {
+                    id: None,
+                    asset: asset_id,
+                    side: TradeSide::Buy,
+                    price: to_decimal(price * 0.98, decimals),
+                    qty: if price < 1000.0 { Decimal::TEN } else { Decimal::new(1, 1) },
+                    fees: Decimal::ZERO,
+                    status: TradeStatus::Pending,
+                    trade_date: datetime_at(date_of(day), 10),
+                    portfolio: Some(portfolio.clone()),
+                    owner: Some(owner.clone()),
+                });
+            }
+        }
+    }
+
+    let mut fx_rates = Vec::new();
+    for (index, (quote, start_rate, volatility)) in FX_PAIRS.iter().enumerate() {
+        let mut rng = ChaCha8Rng::seed_from_u64(options.seed);
+        rng.set_stream((ASSETS.len() + 1 + index) as u64);
+        let decimals = if *start_rate < 10.0 { 4 } else { 2 };
+        for (day, rate) in gbm_path(&mut rng, *start_rate, 0.0, *volatility, days)
+            .into_iter()
+            .enumerate()
+        {
+            let date = date_of(day as u32);
+            fx_rates.push(FxRate {
+                id: Some(RecordId::from_table_key("fx_rate", format!("EUR_{}_{}", quote, date))),
+                base: "EUR".to_string(),
+                quote: quote.to_string(),
+                rate: to_decimal(rate, decimals),
+                rate_date: datetime_at(date, 0),
+            });
+        }
+    }
+
+    // Wallet: a monthly salary in EUR, a USD transfer every quarter and a few withdrawals.
+    let mut rng = ChaCha8Rng::seed_from_u64(options.seed);
+    rng.set_stream(ASSETS.len() as u64);
+    let mut wallet = Vec::new();
+    for (month, day) in (0..days).step_by(30).enumerate() {
+        wallet.push(Wallet {
+            id: None,
+            amount: to_decimal(3000.0 + uniform(&mut rng) * 500.0, 2),
+            ccy: "EUR".to_string(),
+            status: "completed".to_string(),
+            tx_date: datetime_at(date_of(day), 9),
+            note: "Salary".to_string(),
+            category: Some(RecordId::from_table_key("wallet_category", "salary")),
+            reference: None,
+            owner: Some(owner.clone()),
+        });
+        if month % 3 == 2 {
+            wallet.push(Wallet {
+                id: None,
+                amount: to_decimal(1000.0 + uniform(&mut rng) * 1000.0, 2),
+                ccy: "USD".to_string(),
+                status: "completed".to_string(),
+                tx_date: datetime_at(date_of(day), 11),
+                note: "Transfer from US account".to_string(),
+                category: Some(RecordId::from_table_key("wallet_category", "transfer_in")),
+                reference: None,
+                owner: Some(owner.clone()),
+            });
+        }
+        if month % 2 == 1 {
+            wallet.push(Wallet {
+                id: None,
+                amount: -to_decimal(500.0 + uniform(&mut rng) * 1500.0, 2),
+                ccy: "EUR".to_string(),
+                status: "completed".to_string(),
+                tx_date: datetime_at(date_of((day + 12).min(days - 1)), 14),
+                note: "Withdrawal".to_string(),
+                category: Some(RecordId::from_table_key("wallet_category", "cash_withdrawal")),
+                reference: None,
+                owner: Some(owner.clone()),
+            });
+        }
+    }
+
+    let summary = SeedSummary {
+        categories: categories.len(),
+        risks: risks.len(),
+        assets: assets.len(),
+        prices: prices.len(),
+        trades: trades.len(),
+        wallet: wallet.len(),
+        fx_rates: fx_rates.len(),
+        created_user,
+    };
+
+    db.query(
+        "BEGIN TRANSACTION;
+         DELETE wallet WHERE owner = $owner; DELETE trade WHERE owner = $owner;
+         DELETE lot_assignment WHERE owner = $owner;
+         FOR $row IN $categories { UPSERT $row.id CONTENT $row; };
+         FOR $row IN $risks { UPSERT $row.id CONTENT $row; };
+         FOR $row IN $assets { UPSERT $row.id CONTENT $row; };
+         FOR $row IN $prices { UPSERT $row.id CONTENT $row; };
+         FOR $row IN $fx_rates { UPSERT $row.id CONTENT $row; };
+         INSERT INTO trade $trades;
+         INSERT INTO wallet $wallet;
+         COMMIT TRANSACTION;",
+    )
+    .bind(("categories", categories))
+    .bind(("risks", risks))
+    .bind(("assets", assets))
+    .bind(("prices", prices))
+    .bind(("trades", trades))
+    .bind(("wallet", wallet))
+    .bind(("fx_rates", fx_rates))
+    .bind(("owner", owner))
+    .await?
+    .check()?;
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every row seed writes, shared ones with their ids, the account's without.
+    #[cfg(feature = "embedded")]
+    async fn snapshot(db: &Surreal<Any>, owner: &RecordId) -> String {
+        let mut response = db
+            .query(
+                "RETURN <string> [
+                    (SELECT * FROM category ORDER BY id),
+                    (SELECT * FROM risk ORDER BY id),
+                    (SELECT * FROM asset ORDER BY id),
+                    (SELECT * FROM price ORDER BY id),
+                    (SELECT * FROM fx_rate ORDER BY id),
+                    (SELECT * OMIT id FROM trade WHERE owner = $owner ORDER BY trade_date, asset, side),
+                    (SELECT * OMIT id FROM wallet WHERE owner = $owner ORDER BY tx_date, note)
+                ]",
+            )
+            .bind(("owner", owner.clone()))
+            .await
+            .unwrap();
+        let snapshot: Option<String> = response.take(0).unwrap();
+        snapshot.unwrap()
+    }
+
+    #[cfg(feature = "embedded")]
+    #[tokio::test]
+    async fn seeding_twice_gives_the_same_rows_and_keeps_other_data() {
+        let db = crate::backend::db::tests::memory_db().await;
+        let options = SeedOptions {
+            days: 40,
+            ..SeedOptions::default()
+        };
+        let first = seed(&db, &options).await.unwrap();
+        assert!(first.created_user);
+        let (owner, _) = seed_user(&db, &options.user).await.unwrap();
+        let seeded = snapshot(&db, &owner).await;
+
+        // Rows seed does not own: another account's trade and an asset of its own.
+        db.query(
+            "CREATE user:other CONTENT { username: 'other', display_name: 'other', password_hash: '' };
+             CREATE asset:custom CONTENT { symbol: 'CUSTOM', category: category:stocks, risk: risk:low, ccy: 'EUR', precision: 2 };
+             CREATE price CONTENT { asset: asset:custom, price: 10, price_date: d'2025-06-01T17:00:00Z' };
+             CREATE trade CONTENT {
+                asset: asset:custom, side: 'buy', price: 10, qty: 1, status: 'open',
+                trade_date: d'2025-06-01T10:00:00Z', owner: user:other
+             };",
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        let second = seed(&db, &options).await.unwrap();
+        assert!(!second.created_user);
+        assert_eq!((second.prices, second.trades, second.wallet), (first.prices, first.trades, first.wallet));
+        let mut response = db
+            .query(
+                "RETURN count(SELECT id FROM price WHERE asset = asset:custom);
+                 RETURN count(SELECT id FROM trade WHERE owner = user:other);
+                 RETURN count(SELECT id FROM price);",
+            )
+            .await
+            .unwrap();
+        let kept: Vec<Option<usize>> = (0..3).map(|i| response.take(i).unwrap()).collect();
+        assert_eq!(kept, vec![Some(1), Some(1), Some(first.prices + 1)]);
+        db.query("DELETE asset:custom; DELETE price WHERE asset = asset:custom;")
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+        assert_eq!(snapshot(&db, &owner).await, seeded);
+    }
+
+    #[cfg(feature = "embedded")]
+    #[tokio::test]
+    async fn closed_demo_trades_are_round_trips() {
+        let db = crate::backend::db::tests::memory_db().await;
+        let options = SeedOptions {
+            days: 60,
+            ..SeedOptions::default()
+        };
+        seed(&db, &options).await.unwrap();
+        let mut response = db
+            .query(
+                "SELECT asset, side, qty, trade_date FROM trade WHERE status = 'closed' ORDER BY asset, trade_date",
+            )
+            .await
+            .unwrap();
+        #[derive(serde::Deserialize)]
+        struct Closed {
+            asset: RecordId,
+            side: TradeSide,
+            qty: Decimal,
+            trade_date: Datetime,
+        }
+        let closed: Vec<Closed> = response.take(0).unwrap();
+        assert_eq!(closed.len(), 2 * ASSETS.iter().step_by(2).count());
+        for pair in closed.chunks(2) {
+            let [buy, sell] = pair else { panic!("closed trades come in pairs") };
+            assert_eq!(buy.asset, sell.asset);
+            assert_eq!((buy.side, sell.side), (TradeSide::Buy, TradeSide::Sell));
+            assert_eq!(buy.qty, sell.qty);
+            assert!(buy.trade_date < sell.trade_date);
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_prices() {
+        let path = |seed: u64| {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            rng.set_stream(3);
+            gbm_path(&mut rng, 100.0, 0.05, 0.2, 30)
+        };
+        assert_eq!(path(42), path(42));
+        assert_ne!(path(42), path(43));
+        assert_eq!(path(42)[0], 100.0);
+    }
+}
@@ -0,0 +1,92 @@
+use leptos::prelude::ServerFnError;
+use leptos::server;
+
+#[cfg(feature = "ssr")]
+use crate::backend::auth::{require_user, User};
+#[cfg(feature = "ssr")]
+use crate::backend::currency::normalize_ccy;
+#[cfg(feature = "ssr")]
+use crate::backend::db::{get_db, owned_query};
+#[cfg(feature = "ssr")]
+use surrealdb::RecordId;
+
+/// Reporting currency used when nothing has been chosen yet.
+pub const DEFAULT_REPORTING_CCY: &str = "EUR";
+
+// One settings record per account, keyed like the account; `setting:app` holds the
+// defaults for accounts that have not chosen anything yet.
+#[cfg(feature = "ssr")]
+pub(crate) fn user_setting_id(user: &User) -> RecordId {
+    RecordId::from_table_key("setting", user.id.key().clone())
+}
+
+/// Reporting currency of accounts that have not chosen one, from `setting:app`.
+#[cfg(feature = "ssr")]
+pub(crate) async fn default_reporting_ccy() -> Result<String, ServerFnError> {
+    let mut response = get_db()
+        .query("SELECT VALUE reporting_ccy FROM setting:app")
+        .await?;
+    let defaults: Vec<String> = response.take(0)?;
+
+    Ok(defaults
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| DEFAULT_REPORTING_CCY.to_string()))
+}
+
+/// Currency every converted total is reported in for `user`.
+#[cfg(feature = "ssr")]
+pub async fn reporting_ccy(user: &User) -> Result<String, ServerFnError> {
+    let mut response = owned_query(user, "SELECT VALUE reporting_ccy FROM setting WHERE owner = $owner")?
+        .await?;
+    let own: Vec<Option<String>> = response.take(0)?;
+
+    match own.into_iter().flatten().next() {
+        Some(ccy) => Ok(ccy),
+        None => default_reporting_ccy().await,
+    }
+}
+
+#[server(GetReportingCurrency, "/api")]
+pub async fn get_reporting_currency() -> Result<String, ServerFnError> {
+    let user = require_user().await?;
+    reporting_ccy(&user).await
+}
+
+/// Currencies that appear in the FX table, i.e. the ones totals can be converted into.
+#[server(GetReportingCurrencies, "/api")]
+pub async fn get_reporting_currencies() -> Result<Vec<String>, ServerFnError> {
+    let user = require_user().await?;
+    let mut response = get_db()
+        .query(
+            "RETURN array::sort(array::union(
+                (SELECT VALUE base FROM fx_rate),
+                (SELECT VALUE quote FROM fx_rate)
+            ))",
+        )
+        .await?;
+    let mut currencies: Vec<String> = response.take(0)?;
+
+    let current = reporting_ccy(&user).await?;
+    if !currencies.contains(&current) {
+        currencies.push(current);
+        currencies.sort();
+    }
+
+    Ok(currencies)
+}
+
+#[server(SetReportingCurrency, "/api")]
+pub async fn set_reporting_currency(ccy: String) -> Result<String, ServerFnError> {
+    let user = require_user().await?;
+    let ccy = normalize_ccy(&ccy)
+        .ok_or_else(|| ServerFnError::new(format!("'{}' is not an ISO 4217 currency code", ccy.trim())))?;
+
+    owned_query(&user, "UPSERT $setting SET reporting_ccy = $ccy, owner = $owner WHERE owner = $owner")?
+        .bind(("setting", user_setting_id(&user)))
+        .bind(("ccy", ccy.clone()))
+        .await?
+        .check()?;
+
+    Ok(ccy)
+}
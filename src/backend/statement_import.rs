@@ -0,0 +1,751 @@
+use leptos::prelude::ServerFnError;
+use leptos::server;
+use serde::{Deserialize, Serialize};
+
+use crate::backend::model::Decimal;
+use crate::backend::trade_import::{ImportResult, RowOutcome};
+#[cfg(feature = "ssr")]
+use crate::backend::auth::{require_user, User};
+#[cfg(feature = "ssr")]
+use crate::backend::currency::{minor_units, normalize_ccy};
+#[cfg(feature = "ssr")]
+use crate::backend::db::{get_db, owned_query, parse_datetime};
+#[cfg(feature = "ssr")]
+use crate::backend::model::Wallet;
+#[cfg(feature = "ssr")]
+use crate::backend::trade_import::{message, parse_number, read_records, MAX_IMPORT_BYTES};
+#[cfg(feature = "ssr")]
+use quick_xml::events::Event;
+#[cfg(feature = "ssr")]
+use surrealdb::RecordId;
+
+/// Statement files the wallet import understands; the format is detected from the content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatementFormat {
+    Csv,
+    Ofx,
+    Camt053,
+}
+
+impl StatementFormat {
+    pub fn label(self) -> &'static str {
+        match self {
+            StatementFormat::Csv => "CSV",
+            StatementFormat::Ofx => "OFX",
+            StatementFormat::Camt053 => "CAMT.053",
+        }
+    }
+
+    /// ISO 20022 bank-to-customer statements carry `BkToCstmrStmt`, OFX files an
+    /// `OFXHEADER` or `<OFX>` tag; anything else is read as CSV.
+    pub fn detect(text: &str) -> StatementFormat {
+        let head: String = text.chars().take(4096).collect::<String>().to_uppercase();
+        if head.contains("BKTOCSTMRSTMT") {
+            StatementFormat::Camt053
+        } else if head.contains("OFXHEADER") || head.contains("<OFX>") {
+            StatementFormat::Ofx
+        } else {
+            StatementFormat::Csv
+        }
+    }
+}
+
+/// One statement line as it would be recorded in the wallet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatementRow {
+    /// Line number in the file where the entry starts.
+    pub line: usize,
+    pub date: String,
+    /// Signed like wallet entries: credits positive, debits negative.
+    pub amount: Option<Decimal>,
+    pub ccy: String,
+    pub note: String,
+    pub reference: Option<String>,
+    pub status: String,
+    pub outcome: RowOutcome,
+    pub errors: Vec<String>,
+}
+
+/// Dry run of a statement import: nothing has been written yet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatementPreview {
+    pub format: StatementFormat,
+    pub rows: Vec<StatementRow>,
+    pub new: usize,
+    pub duplicates: usize,
+    pub invalid: usize,
+}
+
+// A statement line before validation, as text from whichever format it came from.
+#[cfg(feature = "ssr")]
+#[derive(Debug, Default)]
+struct StatementLine {
+    line: usize,
+    date: String,
+    amount: String,
+    ccy: String,
+    note: String,
+    reference: String,
+    status: String,
+}
+
+// Lower-case CSV header names banks commonly use, per column.
+#[cfg(feature = "ssr")]
+const CSV_DATE: &[&str] = &["date", "booking date", "transaction date", "posting date", "value date", "posted"];
+#[cfg(feature = "ssr")]
+const CSV_AMOUNT: &[&str] = &["amount", "value", "transaction amount"];
+#[cfg(feature = "ssr")]
+const CSV_DEBIT: &[&str] = &["debit", "withdrawal", "withdrawals", "paid out", "money out"];
+#[cfg(feature = "ssr")]
+const CSV_CREDIT: &[&str] = &["credit", "deposit", "deposits", "paid in", "money in"];
+#[cfg(feature = "ssr")]
+const CSV_CCY: &[&str] = &["currency", "ccy"];
+#[cfg(feature = "ssr")]
+const CSV_NOTE: &[&str] = &["description", "details", "memo", "narrative", "payee", "text", "note"];
+#[cfg(feature = "ssr")]
+const CSV_REFERENCE: &[&str] = &["reference", "ref", "transaction id", "id", "fitid"];
+#[cfg(feature = "ssr")]
+const CSV_STATUS: &[&str] = &["status", "state"];
+
+#[cfg(feature = "ssr")]
+fn find_column(headers: &[String], aliases: &[&str]) -> Option<usize> {
+    headers
+        .iter()
+        .position(|h| aliases.contains(&h.trim().to_lowercase().as_str()))
+}
+
+/// Bank CSV: a date column and either a signed amount or separate debit and credit
+/// columns; currency, description, reference and status columns are optional.
+#[cfg(feature = "ssr")]
+fn parse_csv(text: &str) -> Result<Vec<StatementLine>, ServerFnError> {
+    let (headers, records) = read_records(text)?;
+    let date = find_column(&headers, CSV_DATE)
+        .ok_or_else(|| ServerFnError::new("No date column found; name it Date or Booking date"))?;
+    let amount = find_column(&headers, CSV_AMOUNT);
+    let debit = find_column(&headers, CSV_DEBIT);
+    let credit = find_column(&headers, CSV_CREDIT);
+    if amount.is_none() && debit.is_none() && credit.is_none() {
+        return Err(ServerFnError::new(
+            "No amount column found; name it Amount, or use Debit and Credit columns",
+        ));
+    }
+    let ccy = find_column(&headers, CSV_CCY);
+    let note = find_column(&headers, CSV_NOTE);
+    let reference = find_column(&headers, CSV_REFERENCE);
+    let status = find_column(&headers, CSV_STATUS);
+
+    Ok(records
+        .into_iter()
+        .map(|(line, record)| {
+            let cell = |column: Option<usize>| {
+                column
+                    .and_then(|c| record.get(c))
+                    .cloned()
+                    .unwrap_or_default()
+            };
+            // Debits are listed as positive numbers in their own column.
+            let amount = match amount {
+                Some(_) => cell(amount),
+                None => match (cell(debit).trim_start_matches('-'), cell(credit).as_str()) {
+                    ("", credit) => credit.to_string(),
+                    (debit, _) => format!("-{}", debit),
+                },
+            };
+            StatementLine {
+                line,
+                date: cell(Some(date)),
+                amount,
+                ccy: cell(ccy),
+                note: cell(note),
+                reference: cell(reference),
+                status: cell(status),
+            }
+        })
+        .collect())
+}
+
+// Value of an OFX element, up to the next tag or line end. OFX 1.x (SGML) leaves
+// elements unclosed, OFX 2.x (XML) closes them; both read the same way.
+#[cfg(feature = "ssr")]
+fn ofx_value(block: &str, tag: &str) -> String {
+    let open = format!("<{}>", tag);
+    let Some(start) = block.find(&open).map(|i| i + open.len()) else {
+        return String::new();
+    };
+    let rest = &block[start..];
+    let end = rest.find(['<', '\r', '\n']).unwrap_or(rest.len());
+    rest[..end]
+        .trim()
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+// `20240131`, `20240131120000` or `20240131120000.000[-5:EST]`; the offset is ignored.
+#[cfg(feature = "ssr")]
+fn ofx_date(raw: &str) -> String {
+    let digits: String = raw.chars().take_while(char::is_ascii_digit).collect();
+    match digits.len() {
+        8 => format!("{}-{}-{}", &digits[0..4], &digits[4..6], &digits[6..8]),
+        n if n >= 14 => format!(
+            "{}-{}-{} {}:{}:{}",
+            &digits[0..4],
+            &digits[4..6],
+            &digits[6..8],
+            &digits[8..10],
+            &digits[10..12],
+            &digits[12..14]
+        ),
+        _ => raw.to_string(),
+    }
+}
+
+// Line of the first non-blank character at or after `offset`.
+#[cfg(feature = "ssr")]
+fn line_at(text: &str, offset: usize) -> usize {
+    let rest = &text[offset.min(text.len())..];
+    let start = text.len() - rest.trim_start().len();
+    text[..start].matches('\n').count() + 1
+}
+
+/// OFX/QFX: every `STMTTRN` becomes a completed entry in the statement's `CURDEF`.
+#[cfg(feature = "ssr")]
+fn parse_ofx(text: &str) -> Result<Vec<StatementLine>, ServerFnError> {
+    let ccy = ofx_value(text, "CURDEF");
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = text[offset..].find("<STMTTRN>").map(|i| offset + i) {
+        let end = text[start..]
+            .find("</STMTTRN>")
+            .map(|i| start + i)
+            .unwrap_or(text.len());
+        let block = &text[start..end];
+        let name = ofx_value(block, "NAME");
+        let memo = ofx_value(block, "MEMO");
+        let note = match (name.is_empty(), memo.is_empty() || memo == name) {
+            (true, _) => memo,
+            (false, true) => name,
+            (false, false) => format!("{} - {}", name, memo),
+        };
+        lines.push(StatementLine {
+            line: line_at(text, start),
+            date: ofx_date(&ofx_value(block, "DTPOSTED")),
+            amount: ofx_value(block, "TRNAMT"),
+            ccy: ccy.clone(),
+            note,
+            reference: ofx_value(block, "FITID"),
+            status: "completed".to_string(),
+        });
+        offset = end;
+    }
+    if lines.is_empty() {
+        return Err(ServerFnError::new("No transactions found in the OFX file"));
+    }
+    Ok(lines)
+}
+
+// Fields of one CAMT.053 `Ntry` collected while walking its elements.
+#[cfg(feature = "ssr")]
+#[derive(Debug, Default)]
+struct CamtEntry {
+    line: usize,
+    amount: String,
+    ccy: String,
+    debit: bool,
+    status: String,
+    booking_date: String,
+    value_date: String,
+    reference: String,
+    end_to_end: String,
+    info: String,
+    remittance: Vec<String>,
+    creditor: String,
+    debtor: String,
+}
+
+#[cfg(feature = "ssr")]
+impl CamtEntry {
+    // `path` is relative to the `Ntry` element.
+    fn take(&mut self, path: &[String], value: String) {
+        let path: Vec<&str> = path.iter().map(String::as_str).collect();
+        match path.as_slice() {
+            ["Amt"] => self.amount = value,
+            ["CdtDbtInd"] => self.debit = value == "DBIT",
+            ["Sts"] | ["Sts", "Cd"] => self.status = value,
+            ["BookgDt", "Dt" | "DtTm"] => self.booking_date = value,
+            ["ValDt", "Dt" | "DtTm"] => self.value_date = value,
+            ["AcctSvcrRef"] => self.reference = value,
+            ["AddtlNtryInf"] => self.info = value,
+            [.., "Refs", "EndToEndId"] if value != "NOTPROVIDED" => self.end_to_end = value,
+            [.., "RmtInf", "Ustrd"] => self.remittance.push(value),
+            [.., "RltdPties", "Cdtr", "Nm"] | [.., "RltdPties", "Cdtr", "Pty", "Nm"] => self.creditor = value,
+            [.., "RltdPties", "Dbtr", "Nm"] | [.., "RltdPties", "Dbtr", "Pty", "Nm"] => self.debtor = value,
+            _ => {}
+        }
+    }
+
+    fn into_line(self) -> StatementLine {
+        // The other party: who was paid on a debit, who paid on a credit.
+        let party = if self.debit { self.creditor } else { self.debtor };
+        let details = if self.remittance.is_empty() { self.info } else { self.remittance.join(" ") };
+        let note = match (party.is_empty(), details.is_empty()) {
+            (true, _) => details,
+            (false, true) => party,
+            (false, false) => format!("{} - {}", party, details),
+        };
+        let status = match self.status.as_str() {
+            "BOOK" => "completed",
+            _ => "pending",
+        };
+        StatementLine {
+            line: self.line,
+            date: if self.booking_date.is_empty() { self.value_date } else { self.booking_date },
+            amount: if self.debit { format!("-{}", self.amount) } else { self.amount },
+            ccy: self.ccy,
+            note,
+            reference: if self.reference.is_empty() { self.end_to_end } else { self.reference },
+            status: status.to_string(),
+        }
+    }
+}
+
+/// ISO 20022 CAMT.053: every `Ntry` of every statement in the file. Booked entries
+/// are completed, pending and informational ones pending.
+#[cfg(feature = "ssr")]
+fn parse_camt053(text: &str) -> Result<Vec<StatementLine>, ServerFnError> {
+    let invalid = |e: quick_xml::Error| ServerFnError::new(format!("Invalid CAMT.053 file: {}", e));
+    let mut reader = quick_xml::Reader::from_str(text);
+    reader.config_mut().trim_text(true);
+
+    let mut path: Vec<String> = Vec::new();
+    let mut entry: Option<(usize, CamtEntry)> = None;
+    let mut lines = Vec::new();
+    loop {
+        let position = reader.buffer_position() as usize;
+        match reader.read_event().map_err(invalid)? {
+            Event::Start(e) => {
+                let name = String::from_utf8_lossy(e.local_name().as_ref()).into_owned();
+                if name == "Ntry" {
+                    let camt = CamtEntry {
+                        line: line_at(text, position),
+                        ..CamtEntry::default()
+                    };
+                    entry = Some((path.len() + 1, camt));
+                }
+                if let Some((depth, camt)) = entry.as_mut() {
+                    if name == "Amt" && path.len() == *depth {
+                        if let Ok(Some(ccy)) = e.try_get_attribute("Ccy") {
+                            camt.ccy = ccy
+                                .unescape_value()
+                                .map_err(invalid)?
+                                .into_owned();
+                        }
+                    }
+                }
+                path.push(name);
+            }
+            Event::Text(t) => {
+                if let Some((depth, camt)) = entry.as_mut() {
+                    let value = t.unescape().map_err(invalid)?.into_owned();
+                    camt.take(&path[*depth..], value);
+                }
+            }
+            Event::End(_) => {
+                let closed = path.pop();
+                if closed.as_deref() == Some("Ntry") {
+                    lines.extend(entry.take().map(|(_, camt)| camt.into_line()));
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    if lines.is_empty() {
+        return Err(ServerFnError::new("No entries found in the CAMT.053 file"));
+    }
+    Ok(lines)
+}
+
+/// Booked, pending or cancelled in the wording banks use; blank counts as booked.
+#[cfg(feature = "ssr")]
+fn parse_status(raw: &str) -> Option<&'static str> {
+    match raw.trim().to_lowercase().as_str() {
+        "" | "completed" | "booked" | "book" | "settled" | "cleared" | "posted" => Some("completed"),
+        "pending" | "pdng" | "info" | "authorised" | "authorized" => Some("pending"),
+        "cancelled" | "canceled" | "reversed" | "void" => Some("cancelled"),
+        _ => None,
+    }
+}
+
+// An entry is already recorded when it has the same bank reference or, when either
+// side has none, the same currency, amount and calendar day.
+#[cfg(feature = "ssr")]
+fn same_entry(a: &Wallet, b: &Wallet) -> bool {
+    match (&a.reference, &b.reference) {
+        (Some(x), Some(y)) => x == y,
+        _ => {
+            a.ccy == b.ccy
+                && a.amount == b.amount
+                && a.tx_date.into_inner_ref().date_naive() == b.tx_date.into_inner_ref().date_naive()
+        }
+    }
+}
+
+// What a statement line is checked against: the wallet as it stands.
+#[cfg(feature = "ssr")]
+struct StatementContext {
+    existing: Vec<Wallet>,
+    owner: RecordId,
+    default_ccy: String,
+}
+
+#[cfg(feature = "ssr")]
+impl StatementContext {
+    // Validates one line; the entry is returned only when it is new.
+    fn check_line(&self, line: StatementLine) -> (StatementRow, Option<Wallet>) {
+        let mut errors = Vec::new();
+
+        let raw_ccy = if line.ccy.trim().is_empty() { self.default_ccy.as_str() } else { line.ccy.as_str() };
+        let ccy = normalize_ccy(raw_ccy);
+        if ccy.is_none() {
+            errors.push(format!("'{}' is not an ISO 4217 currency code", raw_ccy.trim()));
+        }
+
+        let amount = parse_number(&line.amount);
+        match (amount, ccy.as_deref()) {
+            (None, _) => errors.push(format!("Invalid amount '{}'", line.amount)),
+            (Some(amount), _) if amount.is_zero() => errors.push("Amount is zero".to_string()),
+            (Some(amount), Some(ccy)) if amount.normalize().scale() > minor_units(ccy) => {
+                errors.push(format!("{} amounts take at most {} decimals", ccy, minor_units(ccy)))
+            }
+            _ => {}
+        }
+
+        let tx_date = parse_datetime(&line.date).map_err(|e| errors.push(message(e))).ok();
+        let status = parse_status(&line.status);
+        if status.is_none() {
+            errors.push(format!("Unknown status '{}'", line.status));
+        }
+        let reference = Some(line.reference.trim().to_string()).filter(|r| !r.is_empty());
+
+        let mut outcome = RowOutcome::Invalid;
+        let mut new_entry = None;
+        if let (true, Some(amount), Some(ccy), Some(tx_date), Some(status)) =
+            (errors.is_empty(), amount, ccy.clone(), tx_date, status)
+        {
+            let entry = Wallet {
+                id: None,
+                amount,
+                ccy,
+                status: status.to_string(),
+                tx_date,
+                note: line.note.trim().to_string(),
+                category: None,
+                reference: reference.clone(),
+                owner: Some(self.owner.clone()),
+            };
+            if self.existing.iter().any(|e| same_entry(e, &entry)) {
+                outcome = RowOutcome::Duplicate;
+            } else {
+                outcome = RowOutcome::New;
+                new_entry = Some(entry);
+            }
+        }
+
+        let row = StatementRow {
+            line: line.line,
+            date: line.date,
+            amount,
+            ccy: ccy.unwrap_or_else(|| raw_ccy.trim().to_string()),
+            note: line.note,
+            reference,
+            status: status.unwrap_or_default().to_string(),
+            outcome,
+            errors,
+        };
+        (row, new_entry)
+    }
+}
+
+/// Validates every statement line and checks it against the user's wallet.
+/// Returns the preview and the entries that an import would create.
+#[cfg(feature = "ssr")]
+async fn dry_run(
+    user: &User,
+    statement: &str,
+    default_ccy: &str,
+) -> Result<(StatementPreview, Vec<Wallet>), ServerFnError> {
+    if statement.len() > MAX_IMPORT_BYTES {
+        return Err(ServerFnError::new(format!(
+            "Files are limited to {} KiB",
+            MAX_IMPORT_BYTES / 1024
+        )));
+    }
+    let statement = statement.trim_start_matches('\u{feff}');
+    let format = StatementFormat::detect(statement);
+    let lines = match format {
+        StatementFormat::Csv => parse_csv(statement)?,
+        StatementFormat::Ofx => parse_ofx(statement)?,
+        StatementFormat::Camt053 => parse_camt053(statement)?,
+    };
+
+    let mut response = owned_query(user, "SELECT * FROM wallet WHERE owner = $owner")?.await?;
+    let existing: Vec<Wallet> = response.take(0)?;
+    let context = StatementContext {
+        existing,
+        owner: user.id.clone(),
+        default_ccy: default_ccy.to_string(),
+    };
+
+    let mut rows = Vec::new();
+    let mut entries = Vec::new();
+    for line in lines {
+        let (row, entry) = context.check_line(line);
+        rows.push(row);
+        entries.extend(entry);
+    }
+
+    let count = |outcome: RowOutcome| rows.iter().filter(|r| r.outcome == outcome).count();
+    let preview = StatementPreview {
+        format,
+        new: count(RowOutcome::New),
+        duplicates: count(RowOutcome::Duplicate),
+        invalid: count(RowOutcome::Invalid),
+        rows,
+    };
+    Ok((preview, entries))
+}
+
+/// Dry run: what importing the statement would add to the wallet, line by line.
+/// `ccy` applies to lines whose file gives no currency.
+#[server(PreviewStatementImport, "/api")]
+pub async fn preview_statement_import(
+    statement: String,
+    ccy: String,
+) -> Result<StatementPreview, ServerFnError> {
+    let user = require_user().await?;
+    let (preview, _) = dry_run(&user, &statement, &ccy).await?;
+    Ok(preview)
+}
+
+/// Records the statement's new lines as wallet entries in one transaction. Refused
+/// while any line is invalid; lines already in the wallet are skipped.
+#[server(ImportStatement, "/api")]
+pub async fn import_statement(statement: String, ccy: String) -> Result<ImportResult, ServerFnError> {
+    let user = require_user().await?;
+    let (preview, entries) = dry_run(&user, &statement, &ccy).await?;
+    if preview.invalid > 0 {
+        return Err(ServerFnError::new(format!(
+            "{} line(s) have errors; fix the file and preview again",
+            preview.invalid
+        )));
+    }
+
+    if !entries.is_empty() {
+        get_db()
+            .query(
+                "BEGIN TRANSACTION;
+                 INSERT INTO wallet $entries;
+                 COMMIT TRANSACTION;",
+            )
+            .bind(("entries", entries))
+            .await?
+            .check()?;
+    }
+
+    Ok(ImportResult {
+        created: preview.new,
+        duplicates: preview.duplicates,
+    })
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const OFX: &str = "OFXHEADER:100
+DATA:OFXSGML
+VERSION:102
+
+<OFX>
+<BANKMSGSRSV1><STMTTRNRS><STMTRS>
+<CURDEF>EUR
+<BANKTRANLIST>
+<STMTTRN>
+<TRNTYPE>DEBIT
+<DTPOSTED>20240131120000.000[-5:EST]
+<TRNAMT>-42.50
+<FITID>T-1
+<NAME>Grocer &amp; Sons
+<MEMO>Card payment
+</STMTTRN>
+<STMTTRN>
+<TRNTYPE>CREDIT
+<DTPOSTED>20240201
+<TRNAMT>1500.00
+<FITID>T-2
+<NAME>Salary
+<MEMO>Salary
+</STMTTRN>
+</BANKTRANLIST>
+</STMTRS></STMTTRNRS></BANKMSGSRSV1>
+</OFX>
+";
+
+    const CAMT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.02">
+  <BkToCstmrStmt>
+    <Stmt>
+      <Ntry>
+        <Amt Ccy="CHF">120.00</Amt>
+        <CdtDbtInd>DBIT</CdtDbtInd>
+        <Sts>BOOK</Sts>
+        <BookgDt><Dt>2024-03-04</Dt></BookgDt>
+        <ValDt><Dt>2024-03-05</Dt></ValDt>
+        <AcctSvcrRef>REF-1</AcctSvcrRef>
+        <NtryDtls><TxDtls>
+          <RltdPties><Cdtr><Nm>Power Co</Nm></Cdtr></RltdPties>
+          <RmtInf><Ustrd>Invoice</Ustrd><Ustrd>March</Ustrd></RmtInf>
+        </TxDtls></NtryDtls>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="CHF">80.00</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <Sts>PDNG</Sts>
+        <ValDt><DtTm>2024-03-06T09:30:00</DtTm></ValDt>
+        <NtryDtls><TxDtls>
+          <Refs><EndToEndId>E2E-9</EndToEndId></Refs>
+          <RltdPties><Dbtr><Nm>Jane Doe</Nm></Dbtr></RltdPties>
+        </TxDtls></NtryDtls>
+        <AddtlNtryInf>Refund</AddtlNtryInf>
+      </Ntry>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>
+"#;
+
+    fn dec(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
+
+    fn context(existing: Vec<Wallet>) -> StatementContext {
+        StatementContext {
+            existing,
+            owner: RecordId::from_table_key("user", "alice"),
+            default_ccy: "EUR".to_string(),
+        }
+    }
+
+    fn check_all(context: &StatementContext, lines: Vec<StatementLine>) -> Vec<(StatementRow, Option<Wallet>)> {
+        lines.into_iter().map(|line| context.check_line(line)).collect()
+    }
+
+    #[test]
+    fn detects_the_format_from_the_content() {
+        assert_eq!(StatementFormat::detect(OFX), StatementFormat::Ofx);
+        assert_eq!(StatementFormat::detect(CAMT), StatementFormat::Camt053);
+        assert_eq!(StatementFormat::detect("Date,Amount\n"), StatementFormat::Csv);
+    }
+
+    #[test]
+    fn reads_ofx_transactions_with_their_sign() {
+        let lines = parse_ofx(OFX).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].line, 9);
+        assert_eq!(lines[0].date, "2024-01-31 12:00:00");
+        assert_eq!(lines[0].note, "Grocer & Sons - Card payment");
+        assert_eq!(lines[1].note, "Salary");
+
+        let checked = check_all(&context(Vec::new()), lines);
+        let entries: Vec<&Wallet> = checked.iter().filter_map(|(_, e)| e.as_ref()).collect();
+        assert_eq!(entries.len(), 2, "{:?}", checked[0].0.errors);
+        assert_eq!(entries[0].amount, dec("-42.50"));
+        assert_eq!(entries[0].ccy, "EUR");
+        assert_eq!(entries[0].reference.as_deref(), Some("T-1"));
+        assert_eq!(entries[0].status, "completed");
+        assert_eq!(entries[1].amount, dec("1500.00"));
+    }
+
+    #[test]
+    fn ofx_without_transactions_is_refused() {
+        assert!(parse_ofx("OFXHEADER:100\n<OFX><CURDEF>EUR</OFX>").is_err());
+    }
+
+    #[test]
+    fn reads_camt_entries_with_debits_negative() {
+        let lines = parse_camt053(CAMT).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].line, 5);
+        assert_eq!(lines[0].amount, "-120.00");
+        assert_eq!(lines[0].date, "2024-03-04");
+        assert_eq!(lines[0].note, "Power Co - Invoice March");
+        assert_eq!(lines[0].reference, "REF-1");
+        assert_eq!(lines[0].status, "completed");
+        // Not booked yet: dated by its value date, referenced by the end-to-end id.
+        assert_eq!(lines[1].amount, "80.00");
+        assert_eq!(lines[1].date, "2024-03-06T09:30:00");
+        assert_eq!(lines[1].note, "Jane Doe - Refund");
+        assert_eq!(lines[1].reference, "E2E-9");
+        assert_eq!(lines[1].status, "pending");
+
+        let checked = check_all(&context(Vec::new()), lines);
+        assert!(checked.iter().all(|(row, _)| row.outcome == RowOutcome::New), "{:?}", checked);
+        let debit = checked[0].1.as_ref().unwrap();
+        assert_eq!(debit.amount, dec("-120"));
+        assert_eq!(debit.ccy, "CHF");
+        assert_eq!(checked[1].1.as_ref().unwrap().amount, dec("80"));
+    }
+
+    #[test]
+    fn debit_and_credit_columns_become_signed_amounts() {
+        let csv = "Date;Description;Debit;Credit\n2024-01-02;Rent;-900,00;\n2024-01-03;Pay;;2.000,00\n";
+        let lines = parse_csv(csv).unwrap();
+        assert_eq!(lines[0].amount, "-900,00");
+        assert_eq!(lines[1].amount, "2.000,00");
+        let checked = check_all(&context(Vec::new()), lines);
+        assert_eq!(checked[0].1.as_ref().unwrap().amount, dec("-900"));
+        assert_eq!(checked[1].1.as_ref().unwrap().amount, dec("2000"));
+    }
+
+    #[test]
+    fn skips_entries_already_in_the_wallet() {
+        let (_, first) = context(Vec::new()).check_line(parse_ofx(OFX).unwrap().remove(0));
+        let recorded = context(vec![first.unwrap()]);
+        let checked = check_all(&recorded, parse_ofx(OFX).unwrap());
+        assert_eq!(checked[0].0.outcome, RowOutcome::Duplicate);
+        assert!(checked[0].1.is_none());
+        // Same reference wins over a different amount; a new reference is a new entry.
+        assert_eq!(checked[1].0.outcome, RowOutcome::New);
+        let changed = OFX.replace("<TRNAMT>-42.50", "<TRNAMT>-1.00");
+        assert_eq!(recorded.check_line(parse_ofx(&changed).unwrap().remove(0)).0.outcome, RowOutcome::Duplicate);
+    }
+
+    #[test]
+    fn without_a_reference_the_same_amount_on_the_same_day_is_a_duplicate() {
+        let csv = "Date,Amount,Currency\n2024-05-01,-10.00,EUR\n2024-05-01 18:00,-10,EUR\n2024-05-02,-10.00,EUR\n2024-05-01,-10.00,USD\n";
+        let mut lines = parse_csv(csv).unwrap().into_iter();
+        let (_, first) = context(Vec::new()).check_line(lines.next().unwrap());
+        let outcomes: Vec<RowOutcome> = check_all(&context(vec![first.unwrap()]), lines.collect())
+            .into_iter()
+            .map(|(row, _)| row.outcome)
+            .collect();
+        assert_eq!(outcomes, vec![RowOutcome::Duplicate, RowOutcome::New, RowOutcome::New]);
+    }
+
+    #[test]
+    fn reports_bad_amounts_currencies_and_statuses() {
+        let csv = "Date,Amount,Currency,Status\n2024-05-01,0,EUR,\n2024-05-01,1.234,EUR,booked\n2024-05-01,5,EURO,held\n";
+        let errors: Vec<Vec<String>> = check_all(&context(Vec::new()), parse_csv(csv).unwrap())
+            .into_iter()
+            .map(|(row, _)| row.errors)
+            .collect();
+        assert_eq!(errors[0], vec!["Amount is zero"]);
+        assert_eq!(errors[1], vec!["EUR amounts take at most 2 decimals"]);
+        assert_eq!(
+            errors[2],
+            vec!["'EURO' is not an ISO 4217 currency code", "Unknown status 'held'"]
+        );
+    }
+}
@@ -0,0 +1,915 @@
+use leptos::prelude::ServerFnError;
+use leptos::server;
+use serde::{Deserialize, Serialize};
+use surrealdb::RecordId;
+
+use crate::backend::model::Decimal;
+#[cfg(feature = "ssr")]
+use crate::backend::auth::{require_user, User};
+#[cfg(feature = "ssr")]
+use crate::backend::db::owned_query;
+#[cfg(feature = "ssr")]
+use crate::backend::model::{Trade, TradeSide};
+#[cfg(feature = "ssr")]
+use crate::backend::portfolios::selected_portfolio;
+#[cfg(feature = "ssr")]
+use crate::backend::positions::load_quotes;
+#[cfg(feature = "ssr")]
+use crate::backend::settings::user_setting_id;
+#[cfg(feature = "ssr")]
+use surrealdb::Datetime;
+
+/// Holding period after which a gain is long-term.
+pub const LONG_TERM_DAYS: i64 = 365;
+
+/// How a sell is matched to the buy lots it closes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LotMethod {
+    /// Oldest lots first.
+    #[default]
+    Fifo,
+    /// Newest lots first.
+    Lifo,
+    /// Most expensive lots first, which realizes the smallest gain.
+    HighestCost,
+    /// Lots picked by hand for one sell.
+    Specific,
+}
+
+impl LotMethod {
+    /// Methods an account can match its sells with; `Specific` is chosen per sell.
+    pub const AUTOMATIC: [LotMethod; 3] = [LotMethod::Fifo, LotMethod::Lifo, LotMethod::HighestCost];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LotMethod::Fifo => "fifo",
+            LotMethod::Lifo => "lifo",
+            LotMethod::HighestCost => "highest_cost",
+            LotMethod::Specific => "specific",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LotMethod::Fifo => "FIFO",
+            LotMethod::Lifo => "LIFO",
+            LotMethod::HighestCost => "Highest cost",
+            LotMethod::Specific => "Specific lots",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<LotMethod> {
+        [LotMethod::Fifo, LotMethod::Lifo, LotMethod::HighestCost, LotMethod::Specific]
+            .into_iter()
+            .find(|method| method.as_str() == value.trim())
+    }
+}
+
+/// Part of a buy still held. Amounts are in the asset's currency; the unit cost
+/// includes the buy's fees.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenLot {
+    pub buy: RecordId,
+    pub symbol: String,
+    pub ccy: String,
+    /// Decimals of the asset's prices.
+    pub precision: u32,
+    pub portfolio: Option<RecordId>,
+    /// `YYYY-MM-DD`.
+    pub bought: String,
+    pub qty: Decimal,
+    pub unit_cost: Decimal,
+    pub cost_basis: Decimal,
+    pub last_price: Option<Decimal>,
+    pub unrealized_gain: Option<Decimal>,
+    /// Days held so far.
+    pub holding_days: i64,
+    pub long_term: bool,
+}
+
+/// Quantity of one buy lot closed by one sell. Proceeds are net of the sell's
+/// fees, shared by quantity.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RealizedLot {
+    pub sell: RecordId,
+    pub buy: RecordId,
+    pub symbol: String,
+    pub ccy: String,
+    pub portfolio: Option<RecordId>,
+    pub bought: String,
+    pub sold: String,
+    pub qty: Decimal,
+    pub cost_basis: Decimal,
+    pub proceeds: Decimal,
+    pub gain: Decimal,
+    pub holding_days: i64,
+    pub long_term: bool,
+    pub method: LotMethod,
+}
+
+/// Realized gains in one currency, split by holding period.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GainTotal {
+    pub ccy: String,
+    pub short_term: Decimal,
+    pub long_term: Decimal,
+}
+
+/// Tax lots of the selected portfolio.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaxLotReport {
+    /// Method new sells are matched with.
+    pub method: LotMethod,
+    pub open: Vec<OpenLot>,
+    /// Newest sell first.
+    pub realized: Vec<RealizedLot>,
+    pub totals: Vec<GainTotal>,
+    /// Sells with more quantity than the lots bought before them.
+    pub unmatched: Vec<String>,
+}
+
+/// A buy lot that one sell may close.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LotCandidate {
+    pub buy: RecordId,
+    pub bought: String,
+    pub qty: Decimal,
+    /// Quantity not assigned to other sells.
+    pub available: Decimal,
+    pub unit_cost: Decimal,
+    /// Quantity this sell closes now.
+    pub assigned: Decimal,
+}
+
+/// One sell with the lots it can be matched to, for specific identification.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SellLots {
+    pub sell: RecordId,
+    pub symbol: String,
+    pub ccy: String,
+    pub precision: u32,
+    pub sold: String,
+    pub qty: Decimal,
+    pub price: Decimal,
+    pub candidates: Vec<LotCandidate>,
+}
+
+/// Stored match of part of a sell to part of a buy.
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LotAssignment {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<RecordId>,
+    owner: RecordId,
+    sell: RecordId,
+    buy: RecordId,
+    qty: Decimal,
+    method: LotMethod,
+}
+
+#[cfg(feature = "ssr")]
+struct Lot<'a> {
+    buy: &'a Trade,
+    id: RecordId,
+    unit_cost: Decimal,
+    remaining: Decimal,
+    /// Quantity stored for sells not replayed yet, kept out of automatic matching.
+    reserved: Decimal,
+}
+
+#[cfg(feature = "ssr")]
+struct Sale<'a> {
+    sell: &'a Trade,
+    /// Lot index, quantity and how it was chosen.
+    matches: Vec<(usize, Decimal, LotMethod)>,
+    unmatched: Decimal,
+}
+
+/// Lots and sales after replaying every executed trade of an account, with the
+/// assignment changes that replay made.
+#[cfg(feature = "ssr")]
+struct Ledger<'a> {
+    lots: Vec<Lot<'a>>,
+    sales: Vec<Sale<'a>>,
+    /// Sells whose stored assignments no longer fit and were matched again.
+    replaced: Vec<RecordId>,
+    added: Vec<LotAssignment>,
+}
+
+#[cfg(feature = "ssr")]
+fn day(date: &Datetime) -> chrono::NaiveDate {
+    date.into_inner_ref().date_naive()
+}
+
+#[cfg(feature = "ssr")]
+fn days_between(from: &Datetime, to: &Datetime) -> i64 {
+    (day(to) - day(from)).num_days()
+}
+
+// Whether `lot` was bought in the same portfolio and asset as `sell`, no later.
+#[cfg(feature = "ssr")]
+fn can_close(lot: &Lot, sell: &Trade) -> bool {
+    lot.buy.asset == sell.asset && lot.buy.portfolio == sell.portfolio && lot.buy.trade_date <= sell.trade_date
+}
+
+// Takes up to `qty` from the free part of the lots `sell` can close, in `method` order.
+#[cfg(feature = "ssr")]
+fn auto_match(lots: &mut [Lot], sell: &Trade, method: LotMethod, mut qty: Decimal) -> Vec<(usize, Decimal)> {
+    let mut order: Vec<usize> = (0..lots.len()).filter(|i| can_close(&lots[*i], sell)).collect();
+    match method {
+        LotMethod::Lifo => order.reverse(),
+        LotMethod::HighestCost => order.sort_by(|a, b| lots[*b].unit_cost.cmp(&lots[*a].unit_cost)),
+        LotMethod::Fifo | LotMethod::Specific => {}
+    }
+
+    let mut taken = Vec::new();
+    for index in order {
+        if qty <= Decimal::ZERO {
+            break;
+        }
+        let lot = &mut lots[index];
+        let free = lot.remaining - lot.reserved;
+        if free <= Decimal::ZERO {
+            continue;
+        }
+        let part = free.min(qty);
+        lot.remaining -= part;
+        qty -= part;
+        taken.push((index, part));
+    }
+    taken
+}
+
+/// Replays `trades` in date order. Each sell keeps its stored assignments while they
+/// still fit; a sell without them, or whose lots changed, is matched with `method`.
+#[cfg(feature = "ssr")]
+fn build_ledger<'a>(owner: &RecordId, trades: &'a [Trade], stored: &[LotAssignment], method: LotMethod) -> Ledger<'a> {
+    let mut ordered: Vec<&Trade> = trades
+        .iter()
+        .filter(|t| t.status.is_executed() && t.id.is_some())
+        .collect();
+    // A buy and a sell at the same instant: the buy comes first.
+    ordered.sort_by(|a, b| {
+        (&a.trade_date, a.side == TradeSide::Sell).cmp(&(&b.trade_date, b.side == TradeSide::Sell))
+    });
+
+    let sells: Vec<&RecordId> = ordered
+        .iter()
+        .filter(|t| t.side == TradeSide::Sell)
+        .filter_map(|t| t.id.as_ref())
+        .collect();
+    let mut ledger = Ledger {
+        lots: Vec::new(),
+        sales: Vec::new(),
+        replaced: Vec::new(),
+        added: Vec::new(),
+    };
+    for trade in ordered {
+        let Some(id) = trade.id.clone() else {
+            continue;
+        };
+        if trade.side == TradeSide::Buy {
+            let reserved = stored
+                .iter()
+                .filter(|a| a.buy == id && sells.contains(&&a.sell))
+                .map(|a| a.qty)
+                .sum();
+            ledger.lots.push(Lot {
+                buy: trade,
+                unit_cost: (trade.price * trade.qty + trade.fees) / trade.qty,
+                remaining: trade.qty,
+                reserved,
+                id,
+            });
+            continue;
+        }
+
+        // Stored quantity per lot, released from the reserve whether it still fits or not.
+        let mut wanted: Vec<(usize, Decimal, LotMethod)> = Vec::new();
+        let mut fits = true;
+        for assignment in stored.iter().filter(|a| a.sell == id) {
+            match ledger.lots.iter().position(|lot| lot.id == assignment.buy) {
+                Some(index) => {
+                    ledger.lots[index].reserved -= assignment.qty;
+                    match wanted.iter_mut().find(|(i, _, _)| *i == index) {
+                        Some((_, qty, _)) => *qty += assignment.qty,
+                        None => wanted.push((index, assignment.qty, assignment.method)),
+                    }
+                }
+                None => fits = false,
+            }
+        }
+        let total: Decimal = wanted.iter().map(|(_, qty, _)| *qty).sum();
+        fits = fits
+            && total <= trade.qty
+            && wanted
+                .iter()
+                .all(|(index, qty, _)| can_close(&ledger.lots[*index], trade) && ledger.lots[*index].remaining >= *qty);
+
+        let mut matches = Vec::new();
+        if fits {
+            for (index, qty, how) in wanted {
+                ledger.lots[index].remaining -= qty;
+                matches.push((index, qty, how));
+            }
+        } else if stored.iter().any(|a| a.sell == id) {
+            ledger.replaced.push(id.clone());
+        }
+
+        // Whatever the stored lots do not cover, e.g. after a buy was backdated.
+        let covered: Decimal = matches.iter().map(|(_, qty, _)| *qty).sum();
+        for (index, qty) in auto_match(&mut ledger.lots, trade, method, trade.qty - covered) {
+            ledger.added.push(LotAssignment {
+                id: None,
+                owner: owner.clone(),
+                sell: id.clone(),
+                buy: ledger.lots[index].id.clone(),
+                qty,
+                method,
+            });
+            matches.push((index, qty, method));
+        }
+
+        let matched: Decimal = matches.iter().map(|(_, qty, _)| *qty).sum();
+        ledger.sales.push(Sale {
+            sell: trade,
+            matches,
+            unmatched: trade.qty - matched,
+        });
+    }
+    ledger
+}
+
+#[cfg(feature = "ssr")]
+async fn lot_method(user: &User) -> Result<LotMethod, ServerFnError> {
+    let mut response = owned_query(user, "SELECT VALUE lot_method FROM setting WHERE owner = $owner")?.await?;
+    let methods: Vec<Option<String>> = response.take(0)?;
+    Ok(methods
+        .into_iter()
+        .flatten()
+        .find_map(|method| LotMethod::parse(&method))
+        .unwrap_or_default())
+}
+
+/// Every trade of `user`, with the lot assignments stored so far.
+#[cfg(feature = "ssr")]
+async fn load_trades(user: &User) -> Result<(Vec<Trade>, Vec<LotAssignment>), ServerFnError> {
+    let mut response = owned_query(
+        user,
+        "SELECT * FROM trade WHERE owner = $owner ORDER BY trade_date ASC;
+         SELECT * FROM lot_assignment WHERE owner = $owner;",
+    )?
+    .await?;
+    let trades: Vec<Trade> = response.take(0)?;
+    let stored: Vec<LotAssignment> = response.take(1)?;
+    Ok((trades, stored))
+}
+
+/// Stores what a build changed, so the next one matches every sell the same way.
+#[cfg(feature = "ssr")]
+async fn save_ledger(user: &User, ledger: &Ledger<'_>) -> Result<(), ServerFnError> {
+    if ledger.replaced.is_empty() && ledger.added.is_empty() {
+        return Ok(());
+    }
+    owned_query(
+        user,
+        "BEGIN TRANSACTION;
+         DELETE lot_assignment WHERE owner = $owner AND sell IN $replaced;
+         INSERT INTO lot_assignment $added;
+         COMMIT TRANSACTION;",
+    )?
+    .bind(("replaced", ledger.replaced.clone()))
+    .bind(("added", ledger.added.clone()))
+    .await?
+    .check()?;
+    Ok(())
+}
+
+/// Matches the sells that have no stored lots, or whose lots no longer fit, and
+/// stores the result. Called after every change to trades or lots; the pages only
+/// read the ledger.
+#[cfg(feature = "ssr")]
+pub(crate) async fn record_lot_matches(user: &User) -> Result<(), ServerFnError> {
+    let method = lot_method(user).await?;
+    let (trades, stored) = load_trades(user).await?;
+    save_ledger(user, &build_ledger(&user.id, &trades, &stored, method)).await
+}
+
+/// Drops the assignments of `trade` as a sell, so it is matched again once it has
+/// changed or gone. Sells that closed `trade` as a lot keep theirs, hand-picked ones
+/// included; `record_lot_matches` only matches them again if they no longer fit.
+#[cfg(feature = "ssr")]
+pub(crate) async fn forget_lot_assignments(user: &User, trade: &RecordId) -> Result<(), ServerFnError> {
+    owned_query(user, "DELETE lot_assignment WHERE owner = $owner AND sell = $trade")?
+        .bind(("trade", trade.clone()))
+        .await?
+        .check()?;
+    Ok(())
+}
+
+#[cfg(feature = "ssr")]
+fn trade_key(id: &str) -> Result<RecordId, ServerFnError> {
+    let key = id.strip_prefix("trade:").unwrap_or(id).trim();
+    if key.is_empty() {
+        return Err(ServerFnError::new("Missing trade id"));
+    }
+    Ok(RecordId::from_table_key("trade", key))
+}
+
+#[server(GetTaxLots, "/api")]
+pub async fn get_tax_lots() -> Result<TaxLotReport, ServerFnError> {
+    let user = require_user().await?;
+    let method = lot_method(&user).await?;
+    let (trades, stored) = load_trades(&user).await?;
+    let ledger = build_ledger(&user.id, &trades, &stored, method);
+
+    let selected = selected_portfolio(&user).await?;
+    let shown = |trade: &Trade| selected.is_none() || trade.portfolio == selected;
+    let quotes = load_quotes().await?;
+    let quote = |asset: &RecordId| quotes.iter().find(|q| q.id == *asset);
+    let symbol = |asset: &RecordId| quote(asset).map(|q| q.symbol.clone()).unwrap_or_else(|| asset.key().to_string());
+    let ccy = |asset: &RecordId| quote(asset).map(|q| q.ccy.clone()).unwrap_or_default();
+    let date = |at: &Datetime| day(at).format("%Y-%m-%d").to_string();
+    let now = Datetime::from(chrono::Utc::now());
+
+    let open = ledger
+        .lots
+        .iter()
+        .filter(|lot| lot.remaining > Decimal::ZERO && shown(lot.buy))
+        .map(|lot| {
+            let last_price = quote(&lot.buy.asset).and_then(|q| q.last_price);
+            let holding_days = days_between(&lot.buy.trade_date, &now);
+            OpenLot {
+                buy: lot.id.clone(),
+                symbol: symbol(&lot.buy.asset),
+                ccy: ccy(&lot.buy.asset),
+                precision: quote(&lot.buy.asset).map(|q| q.precision).unwrap_or(2),
+                portfolio: lot.buy.portfolio.clone(),
+                bought: date(&lot.buy.trade_date),
+                qty: lot.remaining,
+                unit_cost: lot.unit_cost,
+                cost_basis: lot.unit_cost * lot.remaining,
+                last_price,
+                unrealized_gain: last_price.map(|price| (price - lot.unit_cost) * lot.remaining),
+                holding_days,
+                long_term: holding_days > LONG_TERM_DAYS,
+            }
+        })
+        .collect();
+
+    let mut realized = Vec::new();
+    let mut unmatched = Vec::new();
+    for sale in ledger.sales.iter().rev().filter(|sale| shown(sale.sell)) {
+        let sell = sale.sell;
+        let Some(sell_id) = sell.id.clone() else {
+            continue;
+        };
+        let net_price = sell.price - sell.fees / sell.qty;
+        for (index, qty, method) in &sale.matches {
+            let lot = &ledger.lots[*index];
+            let holding_days = days_between(&lot.buy.trade_date, &sell.trade_date);
+            let cost_basis = lot.unit_cost * qty;
+            let proceeds = net_price * qty;
+            realized.push(RealizedLot {
+                sell: sell_id.clone(),
+                buy: lot.id.clone(),
+                symbol: symbol(&sell.asset),
+                ccy: ccy(&sell.asset),
+                portfolio: sell.portfolio.clone(),
+                bought: date(&lot.buy.trade_date),
+                sold: date(&sell.trade_date),
+                qty: *qty,
+                cost_basis,
+                proceeds,
+                gain: proceeds - cost_basis,
+                holding_days,
+                long_term: holding_days > LONG_TERM_DAYS,
+                method: *method,
+            });
+        }
+        if sale.unmatched > Decimal::ZERO {
+            unmatched.push(format!(
+                "Sell of {} {} on {}: {} not covered by earlier buys",
+                sell.qty.normalize(),
+                symbol(&sell.asset),
+                date(&sell.trade_date),
+                sale.unmatched.normalize()
+            ));
+        }
+    }
+
+    let mut totals: Vec<GainTotal> = Vec::new();
+    for lot in &realized {
+        let index = match totals.iter().position(|t| t.ccy == lot.ccy) {
+            Some(index) => index,
+            None => {
+                totals.push(GainTotal {
+                    ccy: lot.ccy.clone(),
+                    short_term: Decimal::ZERO,
+                    long_term: Decimal::ZERO,
+                });
+                totals.len() - 1
+            }
+        };
+        if lot.long_term {
+            totals[index].long_term += lot.gain;
+        } else {
+            totals[index].short_term += lot.gain;
+        }
+    }
+    totals.sort_by(|a, b| a.ccy.cmp(&b.ccy));
+
+    Ok(TaxLotReport {
+        method,
+        open,
+        realized,
+        totals,
+        unmatched,
+    })
+}
+
+/// Sets the method sells without assignments are matched with. Sells already
+/// matched keep their lots until `rematch_lots`.
+#[server(SetLotMethod, "/api")]
+pub async fn set_lot_method(method: String) -> Result<LotMethod, ServerFnError> {
+    let user = require_user().await?;
+    let method = LotMethod::parse(&method)
+        .filter(|method| LotMethod::AUTOMATIC.contains(method))
+        .ok_or_else(|| ServerFnError::new(format!("Unknown matching method '{}'", method.trim())))?;
+
+    // Sells still unmatched go with the method they were shown with.
+    record_lot_matches(&user).await?;
+    owned_query(&user, "UPSERT $setting SET lot_method = $method, owner = $owner WHERE owner = $owner")?
+        .bind(("setting", user_setting_id(&user)))
+        .bind(("method", method.as_str()))
+        .await?
+        .check()?;
+
+    Ok(method)
+}
+
+/// Matches every sell again with the account's method, keeping specific lots.
+/// Returns how many sells were matched again.
+#[server(RematchLots, "/api")]
+pub async fn rematch_lots() -> Result<usize, ServerFnError> {
+    let user = require_user().await?;
+    let method = lot_method(&user).await?;
+    owned_query(&user, "DELETE lot_assignment WHERE owner = $owner AND method != 'specific'")?
+        .await?
+        .check()?;
+
+    let (trades, stored) = load_trades(&user).await?;
+    let ledger = build_ledger(&user.id, &trades, &stored, method);
+    save_ledger(&user, &ledger).await?;
+
+    let mut sells: Vec<&RecordId> = ledger.added.iter().map(|a| &a.sell).collect();
+    sells.dedup();
+    Ok(sells.len())
+}
+
+#[cfg(feature = "ssr")]
+async fn sell_lots(user: &User, sell: &RecordId) -> Result<SellLots, ServerFnError> {
+    let method = lot_method(user).await?;
+    let (trades, stored) = load_trades(user).await?;
+    let ledger = build_ledger(&user.id, &trades, &stored, method);
+    // What the sell's lots would be once matched, without storing anything.
+    let stored: Vec<LotAssignment> = stored
+        .into_iter()
+        .filter(|a| !ledger.replaced.contains(&a.sell))
+        .chain(ledger.added.iter().cloned())
+        .collect();
+
+    let trade = trades
+        .iter()
+        .find(|t| t.id.as_ref() == Some(sell) && t.side == TradeSide::Sell && t.status.is_executed())
+        .ok_or_else(|| ServerFnError::new("Sell not found"))?;
+    let quotes = load_quotes().await?;
+    let quote = quotes.iter().find(|q| q.id == trade.asset);
+
+    let candidates = ledger
+        .lots
+        .iter()
+        .filter(|lot| can_close(lot, trade))
+        .map(|lot| {
+            let assigned_to = |mine: bool| -> Decimal {
+                stored
+                    .iter()
+                    .filter(|a| a.buy == lot.id && (a.sell == *sell) == mine)
+                    .map(|a| a.qty)
+                    .sum()
+            };
+            LotCandidate {
+                buy: lot.id.clone(),
+                bought: day(&lot.buy.trade_date).format("%Y-%m-%d").to_string(),
+                qty: lot.buy.qty,
+                available: lot.buy.qty - assigned_to(false),
+                unit_cost: lot.unit_cost,
+                assigned: assigned_to(true),
+            }
+        })
+        .collect();
+
+    Ok(SellLots {
+        sell: sell.clone(),
+        symbol: quote.map(|q| q.symbol.clone()).unwrap_or_else(|| trade.asset.key().to_string()),
+        ccy: quote.map(|q| q.ccy.clone()).unwrap_or_default(),
+        precision: quote.map(|q| q.precision).unwrap_or(2),
+        sold: day(&trade.trade_date).format("%Y-%m-%d").to_string(),
+        qty: trade.qty,
+        price: trade.price,
+        candidates,
+    })
+}
+
+/// The lots one sell can close, with what is free and what it closes now.
+#[server(GetSellLots, "/api")]
+pub async fn get_sell_lots(sell: String) -> Result<SellLots, ServerFnError> {
+    let user = require_user().await?;
+    sell_lots(&user, &trade_key(&sell)?).await
+}
+
+/// Matches one sell to the lots chosen by hand: `lots` pairs a buy id with the
+/// quantity it closes, and the quantities must add up to the sell's.
+#[server(AssignLots, "/api")]
+pub async fn assign_lots(sell: String, lots: Vec<(String, Decimal)>) -> Result<(), ServerFnError> {
+    let user = require_user().await?;
+    let choice = sell_lots(&user, &trade_key(&sell)?).await?;
+
+    // One row per lot: a lot listed twice closes the sum of its quantities.
+    let mut rows: Vec<LotAssignment> = Vec::new();
+    for (buy, qty) in lots.into_iter().filter(|(_, qty)| !qty.is_zero()) {
+        if qty < Decimal::ZERO {
+            return Err(ServerFnError::new("Lot quantities cannot be negative"));
+        }
+        let buy = trade_key(&buy)?;
+        match rows.iter_mut().find(|row| row.buy == buy) {
+            Some(row) => row.qty += qty,
+            None => rows.push(LotAssignment {
+                id: None,
+                owner: user.id.clone(),
+                sell: choice.sell.clone(),
+                buy,
+                qty,
+                method: LotMethod::Specific,
+            }),
+        }
+    }
+    for row in &rows {
+        let candidate = choice
+            .candidates
+            .iter()
+            .find(|c| c.buy == row.buy)
+            .ok_or_else(|| ServerFnError::new("That lot cannot be closed by this sell"))?;
+        if row.qty > candidate.available {
+            return Err(ServerFnError::new(format!(
+                "The lot bought on {} has {} available",
+                candidate.bought,
+                candidate.available.normalize()
+            )));
+        }
+    }
+    let total: Decimal = rows.iter().map(|row| row.qty).sum();
+    if total != choice.qty {
+        return Err(ServerFnError::new(format!(
+            "The lots add up to {}, the sell is {}",
+            total.normalize(),
+            choice.qty.normalize()
+        )));
+    }
+
+    owned_query(
+        &user,
+        "BEGIN TRANSACTION;
+         DELETE lot_assignment WHERE owner = $owner AND sell = $sell;
+         INSERT INTO lot_assignment $rows;
+         COMMIT TRANSACTION;",
+    )?
+    .bind(("sell", choice.sell))
+    .bind(("rows", rows))
+    .await?
+    .check()?;
+    // Later sells that counted on these lots are matched again.
+    record_lot_matches(&user).await
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+    use crate::backend::db::parse_datetime;
+    use crate::backend::model::TradeStatus;
+    use std::str::FromStr;
+
+    fn dec(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
+
+    fn owner() -> RecordId {
+        RecordId::from_table_key("user", "alice")
+    }
+
+    fn trade(key: &str, side: TradeSide, date: &str, price: &str, qty: &str) -> Trade {
+        Trade {
+            id: Some(RecordId::from_table_key("trade", key)),
+            asset: RecordId::from_table_key("asset", "aapl"),
+            side,
+            price: dec(price),
+            qty: dec(qty),
+            fees: Decimal::ZERO,
+            status: TradeStatus::Open,
+            trade_date: parse_datetime(date).unwrap(),
+            portfolio: Some(RecordId::from_table_key("portfolio", "main")),
+            owner: Some(owner()),
+        }
+    }
+
+    // Three lots of 5 at 10, 30 and 20, then a sell of 7.
+    fn trades() -> Vec<Trade> {
+        vec![
+            trade("b1", TradeSide::Buy, "2024-01-01", "10", "5"),
+            trade("b2", TradeSide::Buy, "2024-01-02", "30", "5"),
+            trade("b3", TradeSide::Buy, "2024-01-03", "20", "5"),
+            trade("s1", TradeSide::Sell, "2024-01-04", "25", "7"),
+        ]
+    }
+
+    fn stored(sell: &str, buy: &str, qty: &str) -> LotAssignment {
+        LotAssignment {
+            id: None,
+            owner: owner(),
+            sell: RecordId::from_table_key("trade", sell),
+            buy: RecordId::from_table_key("trade", buy),
+            qty: dec(qty),
+            method: LotMethod::Specific,
+        }
+    }
+
+    // Buy key and quantity of each lot a sale closes, in matching order.
+    fn closed(ledger: &Ledger, sale: usize) -> Vec<(String, Decimal)> {
+        ledger.sales[sale]
+            .matches
+            .iter()
+            .map(|(index, qty, _)| (ledger.lots[*index].id.key().to_string(), *qty))
+            .collect()
+    }
+
+    fn lots(expected: &[(&str, &str)]) -> Vec<(String, Decimal)> {
+        expected.iter().map(|(key, qty)| (key.to_string(), dec(qty))).collect()
+    }
+
+    fn remaining(ledger: &Ledger) -> Vec<Decimal> {
+        ledger.lots.iter().map(|lot| lot.remaining).collect()
+    }
+
+    #[test]
+    fn fifo_closes_the_oldest_lots_and_leaves_the_rest_open() {
+        let trades = trades();
+        let ledger = build_ledger(&owner(), &trades, &[], LotMethod::Fifo);
+        assert_eq!(closed(&ledger, 0), lots(&[("b1", "5"), ("b2", "2")]));
+        assert_eq!(remaining(&ledger), vec![dec("0"), dec("3"), dec("5")]);
+        assert_eq!(ledger.sales[0].unmatched, Decimal::ZERO);
+        assert_eq!(ledger.added.len(), 2);
+        assert!(ledger.added.iter().all(|a| a.method == LotMethod::Fifo));
+        assert!(ledger.replaced.is_empty());
+    }
+
+    #[test]
+    fn lifo_closes_the_newest_lots() {
+        let trades = trades();
+        let ledger = build_ledger(&owner(), &trades, &[], LotMethod::Lifo);
+        assert_eq!(closed(&ledger, 0), lots(&[("b3", "5"), ("b2", "2")]));
+        assert_eq!(remaining(&ledger), vec![dec("5"), dec("3"), dec("0")]);
+    }
+
+    #[test]
+    fn highest_cost_closes_the_dearest_lots() {
+        let trades = trades();
+        let ledger = build_ledger(&owner(), &trades, &[], LotMethod::HighestCost);
+        assert_eq!(closed(&ledger, 0), lots(&[("b2", "5"), ("b3", "2")]));
+    }
+
+    #[test]
+    fn fees_are_part_of_the_unit_cost() {
+        let mut trades = trades();
+        trades[0].fees = dec("2.5");
+        let ledger = build_ledger(&owner(), &trades, &[], LotMethod::Fifo);
+        assert_eq!(ledger.lots[0].unit_cost, dec("10.5"));
+    }
+
+    #[test]
+    fn specific_lots_are_kept_and_reserved_from_earlier_sells() {
+        let mut trades = trades();
+        // Sold between the third buy and the hand-matched sell.
+        trades.push(trade("s0", TradeSide::Sell, "2024-01-03T12:00:00Z", "25", "4"));
+        let stored = [stored("s1", "b3", "4"), stored("s1", "b1", "3")];
+        let ledger = build_ledger(&owner(), &trades, &stored, LotMethod::Fifo);
+
+        // s0 comes first and leaves the 3 of b1 kept for s1 alone.
+        assert_eq!(ledger.sales[0].sell.id, Some(RecordId::from_table_key("trade", "s0")));
+        assert_eq!(closed(&ledger, 0), lots(&[("b1", "2"), ("b2", "2")]));
+        assert_eq!(closed(&ledger, 1), lots(&[("b3", "4"), ("b1", "3")]));
+        assert!(ledger.sales[1].matches.iter().all(|(_, _, how)| *how == LotMethod::Specific));
+        assert_eq!(remaining(&ledger), vec![dec("0"), dec("3"), dec("1")]);
+        assert!(ledger.added.iter().all(|a| a.sell.key().to_string() == "s0"));
+        assert!(ledger.replaced.is_empty());
+    }
+
+    #[test]
+    fn stored_lots_that_no_longer_fit_are_matched_again() {
+        let trades = trades();
+        // More than the lot holds, e.g. after the buy was edited down.
+        let ledger = build_ledger(&owner(), &trades, &[stored("s1", "b2", "6"), stored("s1", "b1", "1")], LotMethod::Fifo);
+        assert_eq!(ledger.replaced, vec![RecordId::from_table_key("trade", "s1")]);
+        assert_eq!(closed(&ledger, 0), lots(&[("b1", "5"), ("b2", "2")]));
+    }
+
+    #[test]
+    fn part_of_a_sell_beyond_the_holdings_stays_unmatched() {
+        let mut trades = trades();
+        trades[3].qty = dec("20");
+        let ledger = build_ledger(&owner(), &trades, &[], LotMethod::Fifo);
+        assert_eq!(closed(&ledger, 0), lots(&[("b1", "5"), ("b2", "5"), ("b3", "5")]));
+        assert_eq!(ledger.sales[0].unmatched, dec("5"));
+        assert!(remaining(&ledger).iter().all(|qty| qty.is_zero()));
+    }
+
+    #[test]
+    fn only_earlier_executed_buys_of_the_same_holding_are_lots() {
+        let mut trades = trades();
+        trades[0].portfolio = Some(RecordId::from_table_key("portfolio", "other"));
+        trades[1].status = TradeStatus::Cancelled;
+        trades[2].trade_date = parse_datetime("2024-01-05").unwrap();
+        let ledger = build_ledger(&owner(), &trades, &[], LotMethod::Fifo);
+        assert_eq!(ledger.lots.len(), 2);
+        assert!(ledger.sales[0].matches.is_empty());
+        assert_eq!(ledger.sales[0].unmatched, dec("7"));
+    }
+
+    #[test]
+    fn auto_match_takes_only_the_free_part_of_a_lot() {
+        let trades = trades();
+        let mut lots = build_ledger(&owner(), &trades[..3], &[], LotMethod::Fifo).lots;
+        lots[0].reserved = dec("4");
+        let taken = auto_match(&mut lots, &trades[3], LotMethod::Fifo, dec("3"));
+        assert_eq!(taken, vec![(0, dec("1")), (1, dec("2"))]);
+        assert_eq!(lots[0].remaining, dec("4"));
+        assert_eq!(lots[1].remaining, dec("3"));
+        assert!(auto_match(&mut lots, &trades[3], LotMethod::Fifo, Decimal::ZERO).is_empty());
+    }
+
+    #[cfg(feature = "embedded")]
+    #[test]
+    fn specific_lots_survive_an_edit_of_their_buy() {
+        use crate::backend::db::tests::{call_as, create_user, on_global_db, sign_in_as};
+        use crate::backend::db::{create_trade, update_trade, TradeInput};
+        use crate::backend::portfolios::{ensure_portfolio, DEFAULT_PORTFOLIO};
+
+        on_global_db(|db| async move {
+            let user = create_user(db, "lot-picker").await;
+            let portfolio = ensure_portfolio(db, &user.id, DEFAULT_PORTFOLIO).await.unwrap();
+            let session = sign_in_as(db, &user).await;
+            let trade = |side, price: &str, qty: &str, date: &str| TradeInput {
+                portfolio: portfolio.to_string(),
+                asset: "asset:aapl".to_string(),
+                side,
+                price: dec(price),
+                qty: dec(qty),
+                fees: Decimal::ZERO,
+                status: TradeStatus::Open,
+                trade_date: date.to_string(),
+            };
+            let key = |trade: &Trade| trade.id.clone().unwrap().to_string();
+            let first = call_as(&session, create_trade(trade(TradeSide::Buy, "100", "10", "2025-01-02")))
+                .await
+                .unwrap();
+            let second = call_as(&session, create_trade(trade(TradeSide::Buy, "120", "10", "2025-01-03")))
+                .await
+                .unwrap();
+            let sell = call_as(&session, create_trade(trade(TradeSide::Sell, "130", "5", "2025-02-01")))
+                .await
+                .unwrap();
+            let assigned = |lots: &SellLots| -> Vec<Decimal> { lots.candidates.iter().map(|c| c.assigned).collect() };
+
+            // FIFO closes the first buy until the second is picked by hand.
+            let lots = call_as(&session, get_sell_lots(key(&sell))).await.unwrap();
+            assert_eq!(assigned(&lots), vec![dec("5"), Decimal::ZERO]);
+            call_as(&session, assign_lots(key(&sell), vec![(key(&second), dec("5"))]))
+                .await
+                .unwrap();
+
+            // Editing either buy keeps the pick while it still fits.
+            for (buy, price, qty, date) in [(&first, "101", "8", "2025-01-02"), (&second, "119", "6", "2025-01-03")] {
+                let edit = update_trade(key(buy), TradeSide::Buy, dec(price), dec(qty), Decimal::ZERO, TradeStatus::Open, date.to_string());
+                call_as(&session, edit).await.unwrap();
+                let lots = call_as(&session, get_sell_lots(key(&sell))).await.unwrap();
+                assert_eq!(assigned(&lots), vec![Decimal::ZERO, dec("5")]);
+            }
+
+            // Once it no longer does, the sell is matched again with the account's method.
+            let edit = update_trade(key(&second), TradeSide::Buy, dec("119"), dec("3"), Decimal::ZERO, TradeStatus::Open, "2025-01-03".to_string());
+            call_as(&session, edit).await.unwrap();
+            let lots = call_as(&session, get_sell_lots(key(&sell))).await.unwrap();
+            assert_eq!(assigned(&lots), vec![dec("5"), Decimal::ZERO]);
+        });
+    }
+}
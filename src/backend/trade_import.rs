@@ -0,0 +1,694 @@
+use leptos::prelude::ServerFnError;
+use leptos::server;
+use serde::{Deserialize, Serialize};
+
+use crate::backend::model::{Decimal, TradeSide};
+#[cfg(feature = "ssr")]
+use crate::backend::auth::{require_user, User};
+#[cfg(feature = "ssr")]
+use crate::backend::db::{get_db, owned_query, parse_datetime, validate_trade};
+#[cfg(feature = "ssr")]
+use crate::backend::model::{Trade, TradeStatus};
+#[cfg(feature = "ssr")]
+use crate::backend::portfolios::owned_portfolio;
+#[cfg(feature = "ssr")]
+use crate::backend::tax_lots::record_lot_matches;
+#[cfg(feature = "ssr")]
+use std::str::FromStr;
+#[cfg(feature = "ssr")]
+use surrealdb::RecordId;
+
+/// Largest file accepted, in bytes.
+pub const MAX_IMPORT_BYTES: usize = 2 * 1024 * 1024;
+
+/// Data lines shown under the column mapping.
+pub const SAMPLE_ROWS: usize = 5;
+
+/// Trade fields a CSV column can be mapped to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TradeField {
+    Symbol,
+    Side,
+    Price,
+    Qty,
+    Date,
+    Fees,
+}
+
+impl TradeField {
+    pub const ALL: [TradeField; 6] = [
+        TradeField::Symbol,
+        TradeField::Side,
+        TradeField::Price,
+        TradeField::Qty,
+        TradeField::Date,
+        TradeField::Fees,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TradeField::Symbol => "Symbol",
+            TradeField::Side => "Side",
+            TradeField::Price => "Price",
+            TradeField::Qty => "Quantity",
+            TradeField::Date => "Date",
+            TradeField::Fees => "Fees",
+        }
+    }
+
+    /// Without a side column the sign of the quantity decides; without fees they are zero.
+    pub fn is_required(self) -> bool {
+        !matches!(self, TradeField::Side | TradeField::Fees)
+    }
+
+    // Lower-case header names brokers commonly use for the field.
+    fn aliases(self) -> &'static [&'static str] {
+        match self {
+            TradeField::Symbol => &["symbol", "ticker", "instrument", "asset", "security", "code"],
+            TradeField::Side => &["side", "action", "type", "direction", "buy/sell", "b/s"],
+            TradeField::Price => &["price", "unit price", "trade price", "execution price", "rate"],
+            TradeField::Qty => &["qty", "quantity", "shares", "units", "amount", "volume"],
+            TradeField::Date => &["date", "trade date", "time", "datetime", "executed", "execution time"],
+            TradeField::Fees => &["fees", "fee", "commission", "commissions", "charges", "costs"],
+        }
+    }
+}
+
+/// Zero-based column for each field; `None` leaves the field unmapped.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ColumnMapping {
+    pub symbol: Option<usize>,
+    pub side: Option<usize>,
+    pub price: Option<usize>,
+    pub qty: Option<usize>,
+    pub date: Option<usize>,
+    pub fees: Option<usize>,
+}
+
+impl ColumnMapping {
+    pub fn get(&self, field: TradeField) -> Option<usize> {
+        match field {
+            TradeField::Symbol => self.symbol,
+            TradeField::Side => self.side,
+            TradeField::Price => self.price,
+            TradeField::Qty => self.qty,
+            TradeField::Date => self.date,
+            TradeField::Fees => self.fees,
+        }
+    }
+
+    pub fn set(&mut self, field: TradeField, column: Option<usize>) {
+        let slot = match field {
+            TradeField::Symbol => &mut self.symbol,
+            TradeField::Side => &mut self.side,
+            TradeField::Price => &mut self.price,
+            TradeField::Qty => &mut self.qty,
+            TradeField::Date => &mut self.date,
+            TradeField::Fees => &mut self.fees,
+        };
+        *slot = column;
+    }
+
+    /// Maps each field to the first header matching one of its usual names,
+    /// never giving the same column to two fields.
+    pub fn guess(headers: &[String]) -> ColumnMapping {
+        let mut mapping = ColumnMapping::default();
+        let mut taken = vec![false; headers.len()];
+        for field in TradeField::ALL {
+            let found = headers.iter().enumerate().position(|(index, header)| {
+                !taken[index] && field.aliases().contains(&header.trim().to_lowercase().as_str())
+            });
+            if let Some(index) = found {
+                taken[index] = true;
+                mapping.set(field, Some(index));
+            }
+        }
+        mapping
+    }
+}
+
+/// Header row and first lines of an uploaded file, with a guessed mapping.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsvColumns {
+    pub headers: Vec<String>,
+    pub sample: Vec<Vec<String>>,
+    /// Data lines in the file, header excluded.
+    pub rows: usize,
+    pub mapping: ColumnMapping,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RowOutcome {
+    /// Would be created.
+    New,
+    /// Already recorded by an earlier import; skipped.
+    Duplicate,
+    Invalid,
+}
+
+impl RowOutcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RowOutcome::New => "new",
+            RowOutcome::Duplicate => "duplicate",
+            RowOutcome::Invalid => "invalid",
+        }
+    }
+}
+
+/// One data line of the file as it would be imported.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportRow {
+    /// Line number in the file, header included, for pointing at the source.
+    pub line: usize,
+    pub symbol: String,
+    pub side: Option<TradeSide>,
+    pub price: Option<Decimal>,
+    pub qty: Option<Decimal>,
+    pub fees: Option<Decimal>,
+    pub date: String,
+    pub outcome: RowOutcome,
+    pub errors: Vec<String>,
+}
+
+/// Dry run of an import: nothing has been written yet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportPreview {
+    pub rows: Vec<ImportRow>,
+    pub new: usize,
+    pub duplicates: usize,
+    pub invalid: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportResult {
+    pub created: usize,
+    pub duplicates: usize,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Deserialize)]
+struct AssetRef {
+    id: RecordId,
+    symbol: String,
+    precision: u32,
+}
+
+// Message of a validation error without the server-function prefix.
+#[cfg(feature = "ssr")]
+pub(crate) fn message(err: ServerFnError) -> String {
+    match err {
+        ServerFnError::ServerError(msg) => msg,
+        other => other.to_string(),
+    }
+}
+
+// The most frequent of `,`, `;`, tab and `|` on the header line.
+#[cfg(feature = "ssr")]
+fn sniff_delimiter(text: &str) -> u8 {
+    let header = text.lines().find(|line| !line.trim().is_empty()).unwrap_or_default();
+    [b',', b';', b'\t', b'|']
+        .into_iter()
+        .max_by_key(|d| header.bytes().filter(|b| b == d).count())
+        .unwrap_or(b',')
+}
+
+// Data lines of a file with their line numbers.
+#[cfg(feature = "ssr")]
+pub(crate) type Records = Vec<(usize, Vec<String>)>;
+
+/// Header and data records of a CSV file. Blank lines are skipped.
+#[cfg(feature = "ssr")]
+pub(crate) fn read_records(text: &str) -> Result<(Vec<String>, Records), ServerFnError> {
+    if text.len() > MAX_IMPORT_BYTES {
+        return Err(ServerFnError::new(format!(
+            "Files are limited to {} KiB",
+            MAX_IMPORT_BYTES / 1024
+        )));
+    }
+    let text = text.trim_start_matches('\u{feff}');
+    if text.trim().is_empty() {
+        return Err(ServerFnError::new("The file is empty"));
+    }
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(sniff_delimiter(text))
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(text.as_bytes());
+    let headers = reader
+        .headers()
+        .map_err(|e| ServerFnError::new(format!("Cannot read the header line: {}", e)))?
+        .iter()
+        .map(str::to_string)
+        .collect();
+
+    let mut records = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| ServerFnError::new(format!("Cannot read the file: {}", e)))?;
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+        let line = record.position().map(|p| p.line() as usize).unwrap_or_default();
+        records.push((line, record.iter().map(str::to_string).collect()));
+    }
+
+    Ok((headers, records))
+}
+
+/// Parses `1234.5`, `1,234.5`, `1.234,5` or `1 234,5`. Whichever of `.` and `,` comes
+/// last is the decimal separator; a lone comma is one too.
+#[cfg(feature = "ssr")]
+pub(crate) fn parse_number(raw: &str) -> Option<Decimal> {
+    let cleaned: String = raw
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '\'')
+        .collect();
+    let cleaned = match (cleaned.rfind('.'), cleaned.rfind(',')) {
+        (Some(dot), Some(comma)) if comma > dot => cleaned.replace('.', "").replace(',', "."),
+        (Some(_), Some(_)) => cleaned.replace(',', ""),
+        (None, Some(_)) => cleaned.replace(',', "."),
+        _ => cleaned,
+    };
+    Decimal::from_str(&cleaned).ok()
+}
+
+#[cfg(feature = "ssr")]
+fn parse_side(raw: &str) -> Option<TradeSide> {
+    match raw.trim().to_lowercase().as_str() {
+        "buy" | "b" | "bought" | "purchase" | "long" => Some(TradeSide::Buy),
+        "sell" | "s" | "sold" | "sale" | "short" => Some(TradeSide::Sell),
+        _ => None,
+    }
+}
+
+// Same asset, side, price, quantity and date as a trade already in the portfolio.
+#[cfg(feature = "ssr")]
+fn same_trade(a: &Trade, b: &Trade) -> bool {
+    a.asset == b.asset
+        && a.side == b.side
+        && a.price == b.price
+        && a.qty == b.qty
+        && a.trade_date == b.trade_date
+}
+
+#[cfg(feature = "ssr")]
+fn check_mapping(mapping: &ColumnMapping, columns: usize) -> Result<(), ServerFnError> {
+    for field in TradeField::ALL {
+        match mapping.get(field) {
+            Some(column) if column >= columns => {
+                return Err(ServerFnError::new(format!(
+                    "{} is mapped to column {} but the file has {}",
+                    field.label(),
+                    column + 1,
+                    columns
+                )))
+            }
+            None if field.is_required() => {
+                return Err(ServerFnError::new(format!("Choose the column holding {}", field.label())))
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// What a file's lines are checked against: the known assets and the trades already in
+// the target portfolio, which new trades are booked in for `owner`.
+#[cfg(feature = "ssr")]
+struct ImportContext {
+    assets: Vec<AssetRef>,
+    existing: Vec<Trade>,
+    portfolio: RecordId,
+    owner: RecordId,
+}
+
+#[cfg(feature = "ssr")]
+impl ImportContext {
+    /// One data line as it would be imported, with the trade it would create.
+    fn check_row(&self, line: usize, record: &[String], mapping: &ColumnMapping) -> (ImportRow, Option<Trade>) {
+        let cell = |field: TradeField| {
+            mapping
+                .get(field)
+                .and_then(|column| record.get(column))
+                .map(String::as_str)
+                .unwrap_or_default()
+        };
+        let mut errors = Vec::new();
+
+        let symbol = cell(TradeField::Symbol).to_string();
+        let asset = self.assets.iter().find(|a| a.symbol.eq_ignore_ascii_case(&symbol));
+        if symbol.is_empty() {
+            errors.push("Missing symbol".to_string());
+        } else if asset.is_none() {
+            errors.push(format!("Unknown symbol '{}'", symbol));
+        }
+
+        let signed_qty = parse_number(cell(TradeField::Qty));
+        if signed_qty.is_none() {
+            errors.push(format!("Invalid quantity '{}'", cell(TradeField::Qty)));
+        }
+        let side = match mapping.side {
+            Some(_) => {
+                let side = parse_side(cell(TradeField::Side));
+                if side.is_none() {
+                    errors.push(format!("Invalid side '{}'", cell(TradeField::Side)));
+                }
+                side
+            }
+            None => signed_qty.map(|q| if q.is_sign_negative() { TradeSide::Sell } else { TradeSide::Buy }),
+        };
+        let qty = signed_qty.map(|q| q.abs());
+
+        let price = parse_number(cell(TradeField::Price));
+        if price.is_none() {
+            errors.push(format!("Invalid price '{}'", cell(TradeField::Price)));
+        }
+
+        // Brokers often report fees as a negative amount; only the size matters.
+        let fees = match cell(TradeField::Fees) {
+            "" => Some(Decimal::ZERO),
+            raw => {
+                let fees = parse_number(raw).map(|f| f.abs());
+                if fees.is_none() {
+                    errors.push(format!("Invalid fees '{}'", raw));
+                }
+                fees
+            }
+        };
+
+        let date = cell(TradeField::Date).to_string();
+        let trade_date = parse_datetime(&date).map_err(|e| errors.push(message(e))).ok();
+
+        let mut outcome = RowOutcome::Invalid;
+        let mut created = None;
+        if let (Some(asset), Some(side), Some(price), Some(qty), Some(fees), Some(trade_date)) =
+            (asset, side, price, qty, fees, trade_date)
+        {
+            match validate_trade(price, qty, fees, asset.precision) {
+                Err(e) => errors.push(message(e)),
+                Ok(()) => {
+                    let trade = Trade {
+                        id: None,
+                        asset: asset.id.clone(),
+                        side,
+                        price,
+                        qty,
+                        fees,
+                        status: TradeStatus::Open,
+                        trade_date,
+                        portfolio: Some(self.portfolio.clone()),
+                        owner: Some(self.owner.clone()),
+                    };
+                    if self.existing.iter().any(|t| same_trade(t, &trade)) {
+                        outcome = RowOutcome::Duplicate;
+                    } else {
+                        outcome = RowOutcome::New;
+                        created = Some(trade);
+                    }
+                }
+            }
+        }
+
+        let row = ImportRow {
+            line,
+            symbol: asset.map(|a| a.symbol.clone()).unwrap_or(symbol),
+            side,
+            price,
+            qty,
+            fees,
+            date,
+            outcome,
+            errors,
+        };
+        (row, created)
+    }
+}
+
+/// Validates every line against the mapping and the portfolio's existing trades.
+/// Returns the preview and the trades that an import would create.
+#[cfg(feature = "ssr")]
+async fn dry_run(
+    user: &User,
+    csv: &str,
+    portfolio: &str,
+    mapping: &ColumnMapping,
+) -> Result<(ImportPreview, Vec<Trade>), ServerFnError> {
+    let (headers, records) = read_records(csv)?;
+    check_mapping(mapping, headers.len())?;
+    let portfolio = owned_portfolio(user, portfolio).await?;
+
+    let mut response = get_db()
+        .query("SELECT id, symbol, precision FROM asset")
+        .await?;
+    let assets: Vec<AssetRef> = response.take(0)?;
+    let mut response = owned_query(user, "SELECT * FROM trade WHERE owner = $owner AND portfolio = $portfolio")?
+        .bind(("portfolio", portfolio.clone()))
+        .await?;
+    let existing: Vec<Trade> = response.take(0)?;
+    let context = ImportContext {
+        assets,
+        existing,
+        portfolio,
+        owner: user.id.clone(),
+    };
+
+    let mut rows = Vec::new();
+    let mut trades = Vec::new();
+    for (line, record) in records {
+        let (row, trade) = context.check_row(line, &record, mapping);
+        rows.push(row);
+        trades.extend(trade);
+    }
+
+    let count = |outcome: RowOutcome| rows.iter().filter(|r| r.outcome == outcome).count();
+    let preview = ImportPreview {
+        new: count(RowOutcome::New),
+        duplicates: count(RowOutcome::Duplicate),
+        invalid: count(RowOutcome::Invalid),
+        rows,
+    };
+    Ok((preview, trades))
+}
+
+/// Reads the header of an uploaded CSV file and guesses which column holds which field.
+#[server(ReadTradeCsv, "/api")]
+pub async fn read_trade_csv(csv: String) -> Result<CsvColumns, ServerFnError> {
+    require_user().await?;
+    let (headers, records) = read_records(&csv)?;
+
+    Ok(CsvColumns {
+        mapping: ColumnMapping::guess(&headers),
+        sample: records
+            .iter()
+            .take(SAMPLE_ROWS)
+            .map(|(_, record)| record.clone())
+            .collect(),
+        rows: records.len(),
+        headers,
+    })
+}
+
+/// Dry run: what importing the file into `portfolio` would do, line by line.
+#[server(PreviewTradeImport, "/api")]
+pub async fn preview_trade_import(
+    csv: String,
+    portfolio: String,
+    mapping: ColumnMapping,
+) -> Result<ImportPreview, ServerFnError> {
+    let user = require_user().await?;
+    let (preview, _) = dry_run(&user, &csv, &portfolio, &mapping).await?;
+    Ok(preview)
+}
+
+/// Creates the file's new trades in one transaction. Refused while any line is
+/// invalid, so an import never lands half-way; duplicates are skipped.
+#[server(ImportTrades, "/api")]
+pub async fn import_trades(
+    csv: String,
+    portfolio: String,
+    mapping: ColumnMapping,
+) -> Result<ImportResult, ServerFnError> {
+    let user = require_user().await?;
+    let (preview, trades) = dry_run(&user, &csv, &portfolio, &mapping).await?;
+    if preview.invalid > 0 {
+        return Err(ServerFnError::new(format!(
+            "{} line(s) have errors; fix the file or the mapping and preview again",
+            preview.invalid
+        )));
+    }
+
+    if !trades.is_empty() {
+        get_db()
+            .query(
+                "BEGIN TRANSACTION;
+                 INSERT INTO trade $trades;
+                 COMMIT TRANSACTION;",
+            )
+            .bind(("trades", trades))
+            .await?
+            .check()?;
+        record_lot_matches(&user).await?;
+    }
+
+    Ok(ImportResult {
+        created: preview.new,
+        duplicates: preview.duplicates,
+    })
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+
+    fn dec(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    fn context(existing: Vec<Trade>) -> ImportContext {
+        ImportContext {
+            assets: vec![AssetRef {
+                id: RecordId::from_table_key("asset", "aapl"),
+                symbol: "AAPL".to_string(),
+                precision: 2,
+            }],
+            existing,
+            portfolio: RecordId::from_table_key("portfolio", "main"),
+            owner: RecordId::from_table_key("user", "alice"),
+        }
+    }
+
+    // symbol, side, price, qty, date, fees
+    fn mapping() -> ColumnMapping {
+        ColumnMapping::guess(&strings(&["Symbol", "Side", "Price", "Qty", "Date", "Fees"]))
+    }
+
+    #[test]
+    fn guesses_columns_from_common_header_names() {
+        let mapping = ColumnMapping::guess(&strings(&["Trade Date", "Ticker", "Action", "Shares", "Unit Price", "Commission"]));
+        assert_eq!(mapping.date, Some(0));
+        assert_eq!(mapping.symbol, Some(1));
+        assert_eq!(mapping.side, Some(2));
+        assert_eq!(mapping.qty, Some(3));
+        assert_eq!(mapping.price, Some(4));
+        assert_eq!(mapping.fees, Some(5));
+    }
+
+    #[test]
+    fn never_maps_one_column_to_two_fields() {
+        // "amount" and "rate" are aliases of quantity and price, "asset" of symbol only.
+        let mapping = ColumnMapping::guess(&strings(&["asset", "amount", "rate"]));
+        assert_eq!(mapping.symbol, Some(0));
+        assert_eq!(mapping.qty, Some(1));
+        assert_eq!(mapping.price, Some(2));
+        assert_eq!(mapping.date, None);
+    }
+
+    #[test]
+    fn mapping_must_cover_required_fields_within_the_file() {
+        assert!(check_mapping(&mapping(), 6).is_ok());
+        let err = message(check_mapping(&mapping(), 5).unwrap_err());
+        assert_eq!(err, "Fees is mapped to column 6 but the file has 5");
+        let mut missing = mapping();
+        missing.date = None;
+        assert_eq!(message(check_mapping(&missing, 6).unwrap_err()), "Choose the column holding Date");
+        missing = mapping();
+        missing.side = None;
+        missing.fees = None;
+        assert!(check_mapping(&missing, 6).is_ok());
+    }
+
+    #[test]
+    fn reads_semicolon_files_with_a_byte_order_mark_and_blank_lines() {
+        let (headers, records) = read_records("\u{feff}symbol;qty\nAAPL;1,5\n\n ; \nMSFT;2\n").unwrap();
+        assert_eq!(headers, strings(&["symbol", "qty"]));
+        assert_eq!(records, vec![(2, strings(&["AAPL", "1,5"])), (5, strings(&["MSFT", "2"]))]);
+        assert!(read_records("  \n").is_err());
+    }
+
+    #[test]
+    fn parses_decimals_in_either_convention() {
+        assert_eq!(parse_number("1234.5"), Some(dec("1234.5")));
+        assert_eq!(parse_number("1,234.5"), Some(dec("1234.5")));
+        assert_eq!(parse_number("1.234,5"), Some(dec("1234.5")));
+        assert_eq!(parse_number("1 234,5"), Some(dec("1234.5")));
+        assert_eq!(parse_number("1'234.5"), Some(dec("1234.5")));
+        assert_eq!(parse_number("0,25"), Some(dec("0.25")));
+        assert_eq!(parse_number("-3"), Some(dec("-3")));
+        assert_eq!(parse_number(""), None);
+        assert_eq!(parse_number("12abc"), None);
+        assert_eq!(parse_number("1.2.3"), None);
+    }
+
+    #[test]
+    fn accepts_the_usual_date_formats() {
+        let context = context(Vec::new());
+        for date in ["2024-03-05", "05/03/2024", "05.03.2024", "2024-03-05 00:00", "2024-03-05T00:00:00Z"] {
+            let (row, trade) = context.check_row(2, &strings(&["AAPL", "buy", "10", "1", date, ""]), &mapping());
+            assert_eq!(row.outcome, RowOutcome::New, "{}: {:?}", date, row.errors);
+            assert_eq!(
+                trade.unwrap().trade_date.into_inner_ref().date_naive(),
+                chrono::NaiveDate::from_ymd_opt(2024, 3, 5).unwrap()
+            );
+        }
+        let (row, _) = context.check_row(2, &strings(&["AAPL", "buy", "10", "1", "2024-13-05", ""]), &mapping());
+        assert_eq!(row.outcome, RowOutcome::Invalid);
+        assert_eq!(row.errors, vec!["Invalid date '2024-13-05'"]);
+    }
+
+    #[test]
+    fn collects_every_error_of_a_bad_line() {
+        let (row, trade) = context(Vec::new())
+            .check_row(3, &strings(&["MSFT", "hold", "1.2.3", "x", "2024-03-05", "abc"]), &mapping());
+        assert!(trade.is_none());
+        assert_eq!(row.outcome, RowOutcome::Invalid);
+        assert_eq!(
+            row.errors,
+            vec![
+                "Unknown symbol 'MSFT'",
+                "Invalid quantity 'x'",
+                "Invalid side 'hold'",
+                "Invalid price '1.2.3'",
+                "Invalid fees 'abc'",
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_more_decimals_than_the_asset_is_priced_with() {
+        let (row, _) = context(Vec::new())
+            .check_row(2, &strings(&["AAPL", "buy", "10.125", "1", "2024-03-05", ""]), &mapping());
+        assert_eq!(row.outcome, RowOutcome::Invalid);
+        assert_eq!(row.errors, vec!["This asset is priced with at most 2 decimals"]);
+    }
+
+    #[test]
+    fn negative_quantity_is_a_sell_without_a_side_column() {
+        let mut mapping = mapping();
+        mapping.side = None;
+        let (row, trade) = context(Vec::new())
+            .check_row(2, &strings(&["aapl", "", "1.234,50", "-3", "2024-03-05", "-1,5"]), &mapping);
+        let trade = trade.expect("valid line");
+        assert_eq!(row.symbol, "AAPL");
+        assert_eq!(trade.side, TradeSide::Sell);
+        assert_eq!(trade.qty, dec("3"));
+        assert_eq!(trade.price, dec("1234.50"));
+        assert_eq!(trade.fees, dec("1.5"));
+        assert_eq!(trade.status, TradeStatus::Open);
+    }
+
+    #[test]
+    fn skips_trades_already_in_the_portfolio() {
+        let record = strings(&["AAPL", "buy", "10", "1", "2024-03-05", "2"]);
+        let (_, trade) = context(Vec::new()).check_row(2, &record, &mapping());
+        let (row, again) = context(vec![trade.unwrap()]).check_row(2, &record, &mapping());
+        assert_eq!(row.outcome, RowOutcome::Duplicate);
+        assert!(again.is_none());
+    }
+}
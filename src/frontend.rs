@@ -4,3 +4,14 @@ pub mod portfolio;
 pub mod assets;
 pub mod wallet;
 pub mod top_assets;
+pub mod reporting;
+pub mod portfolios;
+pub mod trade_import;
+pub mod statement_import;
+pub mod tax_lots;
+pub mod export;
+pub mod performance;
+pub mod risk;
+pub mod correlation;
+pub mod asset_detail;
+pub mod auth;
@@ -0,0 +1,178 @@
+use leptos::prelude::*;
+use leptos_router::components::A;
+use leptos_router::hooks::{use_params_map, use_query_map};
+use crate::backend::currency::format_price;
+use crate::backend::prices::{get_price_history, ChartKind, PriceHistory, PriceRange};
+use crate::backend::risk::get_asset_risk;
+use crate::frontend::risk::RiskStats;
+
+fn pct_change_class(pct: Option<f64>) -> &'static str {
+    match pct {
+        Some(value) if value > 0.0 => "text-green-600",
+        Some(value) if value < 0.0 => "text-red-600",
+        _ => "text-gray-600",
+    }
+}
+
+fn toggle_class(active: bool) -> &'static str {
+    if active {
+        "px-3 py-1 text-sm rounded bg-teal-600 text-white"
+    } else {
+        "px-3 py-1 text-sm rounded border border-slate-200 text-gray-700 hover:bg-gray-50"
+    }
+}
+
+#[component]
+fn PriceStats(history: PriceHistory) -> impl IntoView {
+    let price = |value: Option<_>| {
+        value
+            .map(|v| format_price(v, &history.ccy, history.precision))
+            .unwrap_or_else(|| "-".to_string())
+    };
+    let stats = [
+        ("First", price(history.first)),
+        ("Last", price(history.last)),
+        ("Low", price(history.low)),
+        ("High", price(history.high)),
+    ];
+    let change = history
+        .change_pct
+        .map(|pct| format!("{:+.2}%", pct))
+        .unwrap_or_else(|| "-".to_string());
+
+    view! {
+        <div class="grid gap-4 sm:grid-cols-5 mb-4">
+            {stats
+                .into_iter()
+                .map(|(label, value)| view! {
+                    <div class="rounded-lg border p-3">
+                        <div class="text-xs text-gray-500">{label}</div>
+                        <div class="text-lg font-semibold text-gray-900">{value}</div>
+                    </div>
+                })
+                .collect_view()}
+            <div class="rounded-lg border p-3">
+                <div class="text-xs text-gray-500">"Change"</div>
+                <div class=format!("text-lg font-semibold {}", pct_change_class(history.change_pct))>{change}</div>
+            </div>
+        </div>
+    }
+}
+
+/// Price history of one asset. Range and chart type live in the query string
+/// (`?range=3m&chart=candlestick`) so every view is a plain link.
+#[component]
+pub fn AssetDetail() -> impl IntoView {
+    let params = use_params_map();
+    let query = use_query_map();
+    let symbol = move || params.read().get("symbol").unwrap_or_default();
+    let range = move || {
+        query
+            .read()
+            .get("range")
+            .and_then(|value| PriceRange::from_param(&value))
+            .unwrap_or_default()
+    };
+    let kind = move || {
+        query
+            .read()
+            .get("chart")
+            .and_then(|value| ChartKind::from_param(&value))
+            .unwrap_or_default()
+    };
+    let history = Resource::new(
+        move || (symbol(), range(), kind()),
+        |(symbol, range, kind)| get_price_history(symbol, range, kind),
+    );
+    let risk = Resource::new(
+        move || (symbol(), range()),
+        |(symbol, range)| get_asset_risk(symbol, range),
+    );
+
+    view! {
+        <div class="p-4 max-w-5xl mx-auto">
+            <div class="mb-2">
+                <A href="/search" attr:class="text-sm text-teal-700 hover:underline">"← Assets"</A>
+            </div>
+            <h1 class="text-2xl font-bold text-gray-800 mb-4">{symbol}</h1>
+
+            <div class="flex flex-wrap items-center justify-between gap-4 mb-4">
+                <div class="flex gap-2">
+                    {move || {
+                        let (symbol, current, kind) = (symbol(), range(), kind());
+                        PriceRange::ALL
+                            .into_iter()
+                            .map(|option| {
+                                let href = format!("/asset/{}?range={}&chart={}", symbol, option.as_param(), kind.as_param());
+                                view! { <A href=href attr:class=toggle_class(option == current)>{option.label()}</A> }
+                            })
+                            .collect_view()
+                    }}
+                </div>
+                <div class="flex gap-2">
+                    {move || {
+                        let (symbol, range, current) = (symbol(), range(), kind());
+                        ChartKind::ALL
+                            .into_iter()
+                            .map(|option| {
+                                let href = format!("/asset/{}?range={}&chart={}", symbol, range.as_param(), option.as_param());
+                                view! { <A href=href attr:class=toggle_class(option == current)>{option.label()}</A> }
+                            })
+                            .collect_view()
+                    }}
+                </div>
+            </div>
+
+            <Suspense fallback=move || {
+                view! {
+                    <div class="text-center py-8">
+                        <div class="inline-block animate-spin rounded-full h-6 w-6 border-t-2 border-b-2 border-blue-500"></div>
+                    </div>
+                }
+            }>
+                {move || {
+                    history
+                        .get()
+                        .map(|result| match result {
+                            Ok(history) if history.points == 0 => view! {
+                                <div class="rounded-lg border p-4 text-sm text-gray-500">
+                                    "No prices recorded for this range."
+                                </div>
+                            }
+                            .into_any(),
+                            Ok(history) => {
+                                let svg = history.svg.clone();
+                                view! {
+                                    <PriceStats history=history/>
+                                    <div class="border rounded-lg p-2 overflow-x-auto" inner_html=svg></div>
+                                }
+                                .into_any()
+                            }
+                            Err(err) => view! {
+                                <div class="text-red-600 text-sm p-3 bg-red-50 rounded">
+                                    {format!("Error: {}", err)}
+                                </div>
+                            }
+                            .into_any(),
+                        })
+                }}
+            </Suspense>
+
+            <h2 class="text-lg font-semibold text-gray-800 mt-6 mb-2">"Risk"</h2>
+            <Suspense fallback=|| ()>
+                {move || {
+                    risk.get()
+                        .map(|result| match result {
+                            Ok(risk) => view! { <RiskStats metrics=risk.metrics/> }.into_any(),
+                            Err(err) => view! {
+                                <div class="text-red-600 text-sm p-3 bg-red-50 rounded">
+                                    {format!("Error: {}", err)}
+                                </div>
+                            }
+                            .into_any(),
+                        })
+                }}
+            </Suspense>
+        </div>
+    }
+}
@@ -1,5 +1,8 @@
 use leptos::prelude::*;
+use leptos_router::components::A;
 use surrealdb::RecordId;
+use crate::backend::allocation::risk_badge_class;
+use crate::backend::currency::format_price;
 use crate::backend::db::get_assets;
 use crate::frontend::top_assets::TopAssets;
 
@@ -7,15 +10,6 @@ fn record_key(id: &RecordId) -> String {
     String::try_from(id.key().clone()).unwrap_or_else(|_| id.key().to_string())
 }
 
-fn risk_class(score: u8) -> &'static str {
-    match score {
-        1 | 2 => "text-green-700 bg-green-100",
-        3 | 4 => "text-lime-700 bg-lime-100",
-        5 | 6 => "text-amber-700 bg-amber-100",
-        _ => "text-red-700 bg-red-100",
-    }
-}
-
 fn pct_change_class(pct: Option<f64>) -> &'static str {
     match pct {
         Some(value) if value > 0.0 => "text-green-700",
@@ -109,10 +103,10 @@ pub fn Assets() -> impl IntoView {
                                         {filtered_items.into_iter().map(|item| {                                                
                                             let category = record_key(&item.category);
                                             let risk = item.risk.risk_score;
-                                            let risk_badge = risk_class(risk);
+                                            let risk_badge = risk_badge_class(risk);
                                             let last_price = item
                                                 .last_price
-                                                .map(|price| format!("{:.2}", price))
+                                                .map(|price| format_price(price, &item.ccy, item.precision))
                                                 .unwrap_or_else(|| "-".to_string());
                                             let price_change_class = pct_change_class(item.price_change_pct);
                                             let price_change_pct = item
@@ -122,7 +116,9 @@ pub fn Assets() -> impl IntoView {
                                             view! {
                                                 <tr class="hover:bg-gray-50">
                                                     
-                                                    <td class="px-4 py-2 font-medium text-gray-900">{item.symbol}</td>
+                                                    <td class="px-4 py-2 font-medium text-gray-900">
+                                                        <A href=format!("/asset/{}", item.symbol) attr:class="hover:underline">{item.symbol.clone()}</A>
+                                                    </td>
                                                     <td class="px-4 py-2 text-gray-700">{category}</td>                                                        
                                                     <td class="px-4 py-2 text-gray-700">{last_price}</td>
                                                     <td class=format!("px-4 py-2 {}", price_change_class)>{price_change_pct}</td>
@@ -0,0 +1,113 @@
+use leptos::prelude::*;
+use crate::backend::auth::{current_user, SignIn, SignOut, SignUp, User};
+
+/// Shared handle on the signed-in user. The sign-in, sign-up and sign-out
+/// actions live here so the user resource refetches after any of them.
+#[derive(Clone, Copy)]
+pub struct Session {
+    pub sign_in: ServerAction<SignIn>,
+    pub sign_up: ServerAction<SignUp>,
+    pub sign_out: ServerAction<SignOut>,
+    pub user: Resource<Result<Option<User>, ServerFnError>>,
+}
+
+impl Session {
+    /// `None` while loading, then whether someone is signed in.
+    pub fn is_signed_in(&self) -> Option<bool> {
+        self.user.get().map(|result| matches!(result, Ok(Some(_))))
+    }
+}
+
+pub fn provide_session() {
+    let sign_in = ServerAction::<SignIn>::new();
+    let sign_up = ServerAction::<SignUp>::new();
+    let sign_out = ServerAction::<SignOut>::new();
+    let user = Resource::new(
+        move || {
+            (
+                sign_in.version().get(),
+                sign_up.version().get(),
+                sign_out.version().get(),
+            )
+        },
+        |_| current_user(),
+    );
+    provide_context(Session {
+        sign_in,
+        sign_up,
+        sign_out,
+        user,
+    });
+}
+
+pub fn use_session() -> Session {
+    expect_context::<Session>()
+}
+
+fn action_error(
+    value: impl Fn() -> Option<Result<User, ServerFnError>> + Send + Sync + 'static,
+) -> impl IntoView {
+    move || match value() {
+        Some(Err(err)) => view! { <div class="text-sm text-red-600">{err.to_string()}</div> }.into_any(),
+        _ => ().into_any(),
+    }
+}
+
+#[component]
+pub fn Login() -> impl IntoView {
+    let session = use_session();
+
+    view! {
+        <div class="p-4 max-w-3xl mx-auto">
+            <h1 class="text-2xl font-bold text-gray-800 mb-6">"Sign in to eZoro"</h1>
+            <div class="grid gap-6 md:grid-cols-2">
+                <ActionForm action=session.sign_in attr:class="p-4 border rounded-lg flex flex-col gap-3">
+                    <h2 class="text-lg font-semibold text-gray-800">"Sign in"</h2>
+                    <label class="flex flex-col text-sm text-gray-600">
+                        "Username"
+                        <input type="text" name="username" required autocomplete="username" class="mt-1 rounded border border-slate-200 px-2 py-1.5 text-gray-900"/>
+                    </label>
+                    <label class="flex flex-col text-sm text-gray-600">
+                        "Password"
+                        <input type="password" name="password" required autocomplete="current-password" class="mt-1 rounded border border-slate-200 px-2 py-1.5 text-gray-900"/>
+                    </label>
+                    {action_error(move || session.sign_in.value().get())}
+                    <button type="submit" class="rounded bg-teal-600 px-4 py-1.5 text-sm font-medium text-white hover:bg-teal-700" disabled=move || session.sign_in.pending().get()>
+                        "Sign in"
+                    </button>
+                </ActionForm>
+
+                <ActionForm action=session.sign_up attr:class="p-4 border rounded-lg flex flex-col gap-3">
+                    <h2 class="text-lg font-semibold text-gray-800">"Create an account"</h2>
+                    <label class="flex flex-col text-sm text-gray-600">
+                        "Username"
+                        <input type="text" name="username" required minlength="3" maxlength="32" autocomplete="username" class="mt-1 rounded border border-slate-200 px-2 py-1.5 text-gray-900"/>
+                    </label>
+                    <label class="flex flex-col text-sm text-gray-600">
+                        "Display name"
+                        <input type="text" name="display_name" class="mt-1 rounded border border-slate-200 px-2 py-1.5 text-gray-900"/>
+                    </label>
+                    <label class="flex flex-col text-sm text-gray-600">
+                        "Password"
+                        <input type="password" name="password" required minlength="8" autocomplete="new-password" class="mt-1 rounded border border-slate-200 px-2 py-1.5 text-gray-900"/>
+                    </label>
+                    {action_error(move || session.sign_up.value().get())}
+                    <button type="submit" class="rounded bg-slate-700 px-4 py-1.5 text-sm font-medium text-white hover:bg-slate-800" disabled=move || session.sign_up.pending().get()>
+                        "Sign up"
+                    </button>
+                </ActionForm>
+            </div>
+        </div>
+    }
+}
+
+#[component]
+pub fn SignOutButton() -> impl IntoView {
+    let session = use_session();
+
+    view! {
+        <ActionForm action=session.sign_out>
+            <button type="submit" class="text-xs text-gray-400 hover:text-white underline">"Sign out"</button>
+        </ActionForm>
+    }
+}
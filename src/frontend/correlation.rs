@@ -0,0 +1,114 @@
+use leptos::prelude::*;
+use crate::backend::correlation::{get_correlation, CorrelationReport, HIGH_CORRELATION};
+use crate::backend::prices::PriceRange;
+use crate::frontend::portfolios::use_portfolio_selection;
+use crate::frontend::reporting::use_reporting_currency;
+
+#[component]
+fn CorrelationDetails(report: CorrelationReport) -> impl IntoView {
+    if report.symbols.len() < 2 {
+        return view! {
+            <div class="rounded-lg border p-4 text-sm text-gray-500 mb-6">
+                "Correlations need at least two held assets with prices."
+            </div>
+        }
+        .into_any();
+    }
+
+    let ratio = report
+        .diversification_ratio
+        .map(|r| format!("{:.2}", r))
+        .unwrap_or_else(|| "-".to_string());
+    let pairs = if report.pairs.is_empty() {
+        view! {
+            <p class="text-sm text-gray-500">
+                {format!("No pair of holdings is correlated at {:.1} or more.", HIGH_CORRELATION)}
+            </p>
+        }
+        .into_any()
+    } else {
+        view! {
+            <ul class="divide-y text-sm">
+                {report
+                    .pairs
+                    .into_iter()
+                    .map(|pair| view! {
+                        <li class="flex justify-between py-1">
+                            <span class="text-gray-700">{format!("{} / {}", pair.first, pair.second)}</span>
+                            <span class="font-medium text-red-600">{format!("{:.2}", pair.correlation)}</span>
+                        </li>
+                    })
+                    .collect_view()}
+            </ul>
+        }
+        .into_any()
+    };
+
+    view! {
+        <div class="grid gap-4 md:grid-cols-3 mb-2">
+            <div class="rounded-lg border p-3">
+                <div class="text-xs text-gray-500">"Diversification ratio"</div>
+                <div class="text-lg font-semibold text-gray-900">{ratio}</div>
+                <p class="mt-1 text-xs text-gray-500">
+                    "1 when the holdings move as one; the higher, the more their swings offset."
+                </p>
+            </div>
+            <div class="rounded-lg border p-3 md:col-span-2">
+                <h3 class="text-sm font-semibold text-gray-700 mb-2">"Highly correlated pairs"</h3>
+                {pairs}
+            </div>
+        </div>
+        <div class="border rounded-lg p-2 overflow-x-auto mb-2" inner_html=report.svg></div>
+        <p class="text-xs text-gray-500 mb-6">
+            {format!(
+                "At least {} daily returns in {}, each pair on the days both assets have a price.",
+                report.observations,
+                report.reporting_ccy
+            )}
+        </p>
+    }
+    .into_any()
+}
+
+/// Correlation heatmap of the holdings of the selected portfolio, their
+/// diversification ratio and the pairs that move together.
+#[component]
+pub fn CorrelationPanel(
+    #[prop(into)] range: Signal<PriceRange>,
+    #[prop(into)] refresh: Signal<usize>,
+) -> impl IntoView {
+    let selection = use_portfolio_selection();
+    let reporting = use_reporting_currency();
+    let correlation = Resource::new(
+        move || (range.get(), refresh.get(), selection.version(), reporting.version()),
+        |(range, _, _, _)| get_correlation(range),
+    );
+
+    view! {
+        <h2 class="text-lg font-semibold text-gray-800 mb-2">"Correlation"</h2>
+        <Suspense fallback=|| ()>
+            {move || {
+                correlation
+                    .get()
+                    .map(|result| match result {
+                        Ok(report) => {
+                            let unconverted_note = report.unconverted_note.clone();
+                            view! {
+                                {unconverted_note.map(|note| view! {
+                                    <div class="mb-2 rounded bg-amber-50 p-3 text-sm text-amber-700">{note}</div>
+                                })}
+                                <CorrelationDetails report=report/>
+                            }
+                            .into_any()
+                        }
+                        Err(err) => view! {
+                            <div class="mb-6 text-red-600 text-sm p-3 bg-red-50 rounded">
+                                {format!("Cannot compute correlations: {}", err)}
+                            </div>
+                        }
+                        .into_any(),
+                    })
+            }}
+        </Suspense>
+    }
+}
@@ -0,0 +1,21 @@
+use leptos::prelude::*;
+use crate::backend::export::{export_url, ExportDataset, ExportFormat};
+
+/// Download links for one dataset, one per format. The files are served outside
+/// the router, hence `rel="external"`.
+#[component]
+pub fn ExportLinks(dataset: ExportDataset) -> impl IntoView {
+    view! {
+        <span class="flex items-center gap-2 text-sm text-gray-500">
+            "Export"
+            {ExportFormat::ALL
+                .into_iter()
+                .map(|format| view! {
+                    <a href=export_url(dataset, format) rel="external" download class="text-teal-700 hover:underline">
+                        {format.label()}
+                    </a>
+                })
+                .collect_view()}
+        </span>
+    }
+}
@@ -1,9 +1,221 @@
 use leptos::prelude::*;
+use leptos_router::components::A;
+use surrealdb::RecordId;
+use crate::backend::currency::format_amount;
+use crate::backend::allocation::AllocationSlice;
+use crate::backend::dashboard::{get_dashboard, Dashboard};
+use crate::backend::model::{Money, Trade, TradeSide, Wallet};
+use crate::frontend::portfolios::use_portfolio_selection;
+use crate::frontend::reporting::use_reporting_currency;
+use crate::frontend::top_assets::TopAssets;
+
+fn record_key(id: &RecordId) -> String {
+    String::try_from(id.key().clone()).unwrap_or_else(|_| id.key().to_string())
+}
+
+fn change_class(negative: bool) -> &'static str {
+    if negative {
+        "text-red-600"
+    } else {
+        "text-green-600"
+    }
+}
+
+#[component]
+fn StatCard(label: &'static str, value: Money, #[prop(optional)] detail: Option<String>) -> impl IntoView {
+    view! {
+        <div class="rounded-xl border border-slate-200 p-4 shadow-sm">
+            <div class="text-xs uppercase tracking-wide text-slate-500">{label}</div>
+            <div class="mt-1 text-2xl font-semibold text-slate-900">{value.to_string()}</div>
+            {detail.map(|detail| view! { <div class="mt-1 text-sm text-slate-500">{detail}</div> })}
+        </div>
+    }
+}
+
+#[component]
+fn AllocationBars(allocation: Vec<AllocationSlice>) -> impl IntoView {
+    if allocation.is_empty() {
+        return view! { <div class="text-sm text-slate-500">"No open positions."</div> }.into_any();
+    }
+
+    view! {
+        <ul class="space-y-3">
+            {allocation
+                .into_iter()
+                .map(|slice| {
+                    let width = format!("width: {:.1}%", slice.weight_pct.clamp(0.0, 100.0));
+                    view! {
+                        <li>
+                            <div class="flex justify-between text-sm">
+                                <span class="font-medium text-slate-700">{slice.label}</span>
+                                <span class="text-slate-500">
+                                    {format!("{} · {:.1}%", slice.value, slice.weight_pct)}
+                                </span>
+                            </div>
+                            <div class="mt-1 h-2 rounded bg-slate-100">
+                                <div class="h-2 rounded bg-teal-500" style=width></div>
+                            </div>
+                        </li>
+                    }
+                })
+                .collect_view()}
+        </ul>
+    }
+    .into_any()
+}
+
+#[component]
+fn RecentTrades(trades: Vec<Trade>) -> impl IntoView {
+    if trades.is_empty() {
+        return view! { <div class="text-sm text-slate-500">"No trades yet."</div> }.into_any();
+    }
+
+    view! {
+        <ul class="divide-y text-sm">
+            {trades
+                .into_iter()
+                .map(|trade| {
+                    let side_class = change_class(trade.side == TradeSide::Sell);
+                    let date = trade.trade_date.into_inner_ref().format("%Y-%m-%d").to_string();
+                    view! {
+                        <li class="flex items-center justify-between py-2">
+                            <div>
+                                <span class=format!("font-medium {}", side_class)>{trade.side.as_str()}</span>
+                                " "
+                                <span class="font-medium text-slate-900">{record_key(&trade.asset)}</span>
+                                <span class="text-slate-500">{format!(" {} @ {}", trade.qty.normalize(), trade.price.normalize())}</span>
+                            </div>
+                            <div class="text-right text-slate-500">
+                                <div>{date}</div>
+                                <div class="text-xs">{trade.status.as_str()}</div>
+                            </div>
+                        </li>
+                    }
+                })
+                .collect_view()}
+        </ul>
+    }
+    .into_any()
+}
+
+#[component]
+fn RecentWallet(entries: Vec<Wallet>) -> impl IntoView {
+    if entries.is_empty() {
+        return view! { <div class="text-sm text-slate-500">"No transactions yet."</div> }.into_any();
+    }
+
+    view! {
+        <ul class="divide-y text-sm">
+            {entries
+                .into_iter()
+                .map(|entry| {
+                    let amount_class = change_class(entry.amount.is_sign_negative());
+                    let amount = format_amount(entry.amount, &entry.ccy);
+                    let date = entry.tx_date.into_inner_ref().format("%Y-%m-%d").to_string();
+                    view! {
+                        <li class="flex items-center justify-between py-2">
+                            <div>
+                                <div class="font-medium text-slate-900">{entry.note}</div>
+                                <div class="text-xs text-slate-500">{date}</div>
+                            </div>
+                            <div class=format!("font-semibold {}", amount_class)>{amount}</div>
+                        </li>
+                    }
+                })
+                .collect_view()}
+        </ul>
+    }
+    .into_any()
+}
+
+#[component]
+fn DashboardView(dashboard: Dashboard) -> impl IntoView {
+    let day_change_class = change_class(dashboard.day_change.is_negative());
+    let day_change_pct = dashboard
+        .day_change_pct
+        .map(|pct| format!("{:+.2}%", pct))
+        .unwrap_or_else(|| "-".to_string());
+    let as_of = format!(
+        "{} as of {}",
+        dashboard.net_worth.ccy,
+        dashboard.as_of.into_inner_ref().format("%Y-%m-%d %H:%M UTC")
+    );
+
+    view! {
+        {dashboard.unconverted_note.map(|note| view! {
+            <div class="mb-4 rounded bg-amber-50 p-3 text-sm text-amber-700">{note}</div>
+        })}
+        <div class="grid gap-4 sm:grid-cols-2 lg:grid-cols-4 mb-6">
+            <StatCard label="Net worth" value=dashboard.net_worth detail=as_of/>
+            <StatCard label="Cash" value=dashboard.cash/>
+            <StatCard label="Invested" value=dashboard.invested/>
+            <div class="rounded-xl border border-slate-200 p-4 shadow-sm">
+                <div class="text-xs uppercase tracking-wide text-slate-500">"Day change"</div>
+                <div class=format!("mt-1 text-2xl font-semibold {}", day_change_class)>
+                    {dashboard.day_change.to_string()}
+                </div>
+                <div class=format!("mt-1 text-sm {}", day_change_class)>{day_change_pct}</div>
+            </div>
+        </div>
+
+        <div class="grid gap-6 lg:grid-cols-3 mb-6">
+            <section class="rounded-xl border border-slate-200 p-4">
+                <h2 class="text-lg font-semibold text-slate-900 mb-3">"Allocation by category"</h2>
+                <AllocationBars allocation=dashboard.allocation/>
+            </section>
+            <section class="rounded-xl border border-slate-200 p-4">
+                <div class="flex items-center justify-between mb-3">
+                    <h2 class="text-lg font-semibold text-slate-900">"Recent trades"</h2>
+                    <A href="/portfolio" attr:class="text-sm text-teal-700 hover:underline">"All"</A>
+                </div>
+                <RecentTrades trades=dashboard.recent_trades/>
+            </section>
+            <section class="rounded-xl border border-slate-200 p-4">
+                <div class="flex items-center justify-between mb-3">
+                    <h2 class="text-lg font-semibold text-slate-900">"Recent transactions"</h2>
+                    <A href="/wallet" attr:class="text-sm text-teal-700 hover:underline">"All"</A>
+                </div>
+                <RecentWallet entries=dashboard.recent_wallet/>
+            </section>
+        </div>
+
+        <TopAssets items=dashboard.assets/>
+    }
+}
 
 #[component]
 pub fn Home() -> impl IntoView {
+    let reporting = use_reporting_currency();
+    let selection = use_portfolio_selection();
+    let dashboard = Resource::new(
+        move || (reporting.version(), selection.version()),
+        |_| get_dashboard(),
+    );
+
     view! {
-        <h1>"Welcome to Home"</h1>
-        <p>"This is the home page."</p>
+        <div class="p-4 max-w-6xl mx-auto">
+            <h1 class="text-2xl font-bold text-gray-800 mb-6">"Dashboard"</h1>
+            <Suspense fallback=move || {
+                view! {
+                    <div class="text-center py-8">
+                        <div class="inline-block animate-spin rounded-full h-6 w-6 border-t-2 border-b-2 border-blue-500"></div>
+                    </div>
+                }
+            }>
+                {move || {
+                    dashboard
+                        .get()
+                        .map(|result| match result {
+                            Ok(dashboard) => view! { <DashboardView dashboard=dashboard/> }.into_any(),
+                            Err(err) => view! {
+                                <div class="text-red-600 text-sm p-3 bg-red-50 rounded">
+                                    {format!("Error: {}", err)}
+                                </div>
+                            }
+                            .into_any(),
+                        })
+                }}
+            </Suspense>
+        </div>
     }
-}
\ No newline at end of file
+}
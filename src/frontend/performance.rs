@@ -0,0 +1,274 @@
+use leptos::prelude::*;
+use leptos_router::components::A;
+use leptos_router::hooks::use_query_map;
+use crate::backend::benchmark::{get_benchmark_comparison, BenchmarkReport, INDEX_CATEGORY};
+use crate::backend::db::get_assets;
+use crate::backend::performance::{get_performance, PerformanceReport};
+use crate::backend::prices::PriceRange;
+use crate::frontend::portfolios::use_portfolio_selection;
+use crate::frontend::reporting::use_reporting_currency;
+use surrealdb::RecordId;
+
+fn record_key(id: &RecordId) -> String {
+    String::try_from(id.key().clone()).unwrap_or_else(|_| id.key().to_string())
+}
+
+fn pct_class(pct: Option<f64>) -> &'static str {
+    match pct {
+        Some(value) if value > 0.0 => "text-green-600",
+        Some(value) if value < 0.0 => "text-red-600",
+        _ => "text-gray-900",
+    }
+}
+
+fn format_pct(pct: Option<f64>) -> String {
+    pct.map(|value| format!("{:+.2}%", value))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn format_ratio(value: Option<f64>) -> String {
+    value.map(|v| format!("{:.2}", v)).unwrap_or_else(|| "-".to_string())
+}
+
+// Link for a preset range ending today, keeping the benchmark; `All` drops the start.
+fn preset_href(range: PriceRange, benchmark: &str) -> String {
+    let mut params = Vec::new();
+    if let Some(days) = range.days() {
+        let from = chrono::Utc::now().date_naive() - chrono::Duration::days(days);
+        params.push(format!("from={}", from.format("%Y-%m-%d")));
+    }
+    if !benchmark.is_empty() {
+        params.push(format!("benchmark={}", benchmark));
+    }
+    if params.is_empty() {
+        "/performance".to_string()
+    } else {
+        format!("/performance?{}", params.join("&"))
+    }
+}
+
+#[component]
+fn PerformanceStats(report: PerformanceReport) -> impl IntoView {
+    let returns = [
+        ("Time-weighted", report.twr_pct),
+        ("Time-weighted, per year", report.twr_annualized_pct),
+        ("Money-weighted (XIRR), per year", report.mwr_pct),
+    ];
+    let amounts = [
+        ("Start value", report.start_value.to_string()),
+        ("End value", report.end_value.to_string()),
+        ("Net money in", report.net_flows.to_string()),
+        ("Gain", report.gain.to_string()),
+    ];
+
+    view! {
+        {report.unconverted_note.map(|note| view! {
+            <div class="mb-4 rounded bg-amber-50 p-3 text-sm text-amber-700">{note}</div>
+        })}
+        <div class="grid gap-4 sm:grid-cols-3 mb-4">
+            {returns
+                .into_iter()
+                .map(|(label, value)| view! {
+                    <div class="rounded-lg border p-3">
+                        <div class="text-xs text-gray-500">{label}</div>
+                        <div class=format!("text-lg font-semibold {}", pct_class(value))>{format_pct(value)}</div>
+                    </div>
+                })
+                .collect_view()}
+        </div>
+        <div class="grid gap-4 sm:grid-cols-4 mb-4">
+            {amounts
+                .into_iter()
+                .map(|(label, value)| view! {
+                    <div class="rounded-lg border p-3">
+                        <div class="text-xs text-gray-500">{label}</div>
+                        <div class="text-lg font-semibold text-gray-900">{value}</div>
+                    </div>
+                })
+                .collect_view()}
+        </div>
+        <p class="text-xs text-gray-500 mb-4">
+            {format!(
+                "{} to {}, {} day(s). {}",
+                report.from,
+                report.to,
+                report.days,
+                if report.includes_cash {
+                    "Wallet cash is included; deposits and withdrawals count as money in and out."
+                } else {
+                    "Wallet cash is left out, as it is not held per portfolio."
+                }
+            )}
+        </p>
+    }
+}
+
+#[component]
+fn BenchmarkStats(report: BenchmarkReport) -> impl IntoView {
+    let stats = [
+        ("Portfolio".to_string(), format_pct(report.portfolio_pct), pct_class(report.portfolio_pct)),
+        (report.benchmark.clone(), format_pct(report.benchmark_pct), pct_class(report.benchmark_pct)),
+        ("Excess return".to_string(), format_pct(report.excess_pct), pct_class(report.excess_pct)),
+        ("Tracking error, per year".to_string(), format_pct(report.tracking_error_pct), "text-gray-900"),
+        ("Beta".to_string(), format_ratio(report.beta), "text-gray-900"),
+        ("Information ratio".to_string(), format_ratio(report.information_ratio), pct_class(report.information_ratio)),
+    ];
+
+    view! {
+        <div class="grid gap-4 sm:grid-cols-6 mb-4">
+            {stats
+                .into_iter()
+                .map(|(label, value, class)| view! {
+                    <div class="rounded-lg border p-3">
+                        <div class="text-xs text-gray-500">{label}</div>
+                        <div class=format!("text-lg font-semibold {}", class)>{value}</div>
+                    </div>
+                })
+                .collect_view()}
+        </div>
+    }
+}
+
+/// Returns of the selected portfolio, alone and against a benchmark. The range and
+/// benchmark live in the query string (`?from=2024-01-01&to=2024-12-31&benchmark=SPX`);
+/// without a range it covers the whole history.
+#[component]
+pub fn Performance() -> impl IntoView {
+    let query = use_query_map();
+    let from = move || query.read().get("from").unwrap_or_default();
+    let to = move || query.read().get("to").unwrap_or_default();
+    let benchmark = move || query.read().get("benchmark").unwrap_or_default();
+    let selection = use_portfolio_selection();
+    let reporting = use_reporting_currency();
+    let report = Resource::new(
+        move || (from(), to(), selection.version(), reporting.version()),
+        |(from, to, _, _)| get_performance(from, to),
+    );
+    let comparison = Resource::new(
+        move || (from(), to(), benchmark(), selection.version(), reporting.version()),
+        |(from, to, benchmark, _, _)| get_benchmark_comparison(from, to, benchmark),
+    );
+    let assets = Resource::new(|| (), |_| get_assets());
+
+    view! {
+        <div class="p-4 max-w-5xl mx-auto">
+            <h1 class="text-2xl font-bold text-gray-800 mb-2">"Performance"</h1>
+            <p class="text-gray-600 mb-6">
+                "Time-weighted return shows how the investments did, whatever was put in or taken out. Money-weighted return shows what your own deposits earned, timing included."
+            </p>
+
+            <div class="flex flex-wrap items-end justify-between gap-4 mb-4">
+                <div class="flex gap-2">
+                    {move || {
+                        let benchmark = benchmark();
+                        PriceRange::ALL
+                            .into_iter()
+                            .map(|range| view! {
+                                <A href=preset_href(range, &benchmark) attr:class="px-3 py-1 text-sm rounded border border-slate-200 text-gray-700 hover:bg-gray-50">
+                                    {range.label()}
+                                </A>
+                            })
+                            .collect_view()
+                    }}
+                </div>
+                <form method="get" action="/performance" class="flex items-end gap-2">
+                    <label class="flex flex-col text-sm text-gray-600">
+                        "From"
+                        <input type="date" name="from" prop:value=from class="mt-1 rounded border border-slate-200 px-2 py-1 text-gray-900"/>
+                    </label>
+                    <label class="flex flex-col text-sm text-gray-600">
+                        "To"
+                        <input type="date" name="to" prop:value=to class="mt-1 rounded border border-slate-200 px-2 py-1 text-gray-900"/>
+                    </label>
+                    <label class="flex flex-col text-sm text-gray-600">
+                        "Benchmark"
+                        <select name="benchmark" class="mt-1 rounded border border-slate-200 px-2 py-1.5 text-gray-900">
+                            <option value="" selected=move || benchmark().is_empty()>"First index"</option>
+                            <Suspense fallback=|| ()>
+                                {move || {
+                                    assets.get().and_then(Result::ok).map(|mut assets| {
+                                        // Indices first, then everything else by symbol.
+                                        assets.sort_by_key(|a| {
+                                            let index = record_key(&a.category) == INDEX_CATEGORY;
+                                            (!index, a.symbol.clone())
+                                        });
+                                        assets
+                                            .into_iter()
+                                            .map(|asset| {
+                                                let symbol = asset.symbol.clone();
+                                                view! { <option value=asset.symbol selected=move || benchmark() == symbol>{asset.symbol.clone()}</option> }
+                                            })
+                                            .collect_view()
+                                    })
+                                }}
+                            </Suspense>
+                        </select>
+                    </label>
+                    <button type="submit" class="rounded bg-slate-700 px-3 py-1.5 text-sm font-medium text-white hover:bg-slate-800">
+                        "Apply"
+                    </button>
+                </form>
+            </div>
+
+            <Suspense fallback=move || {
+                view! {
+                    <div class="text-center py-8">
+                        <div class="inline-block animate-spin rounded-full h-6 w-6 border-t-2 border-b-2 border-blue-500"></div>
+                    </div>
+                }
+            }>
+                {move || {
+                    report
+                        .get()
+                        .map(|result| match result {
+                            Ok(report) if report.days == 0 => view! {
+                                <div class="rounded-lg border p-4 text-sm text-gray-500">
+                                    "No trades or wallet entries in this range."
+                                </div>
+                            }
+                            .into_any(),
+                            Ok(report) => {
+                                let svg = report.svg.clone();
+                                view! {
+                                    <PerformanceStats report=report/>
+                                    <div class="border rounded-lg p-2 overflow-x-auto" inner_html=svg></div>
+                                }
+                                .into_any()
+                            }
+                            Err(err) => view! {
+                                <div class="text-red-600 text-sm p-3 bg-red-50 rounded">
+                                    {format!("Error: {}", err)}
+                                </div>
+                            }
+                            .into_any(),
+                        })
+                }}
+            </Suspense>
+
+            <h2 class="text-lg font-semibold text-gray-800 mt-6 mb-2">"Against a benchmark"</h2>
+            <Suspense fallback=|| ()>
+                {move || {
+                    comparison
+                        .get()
+                        .map(|result| match result {
+                            Ok(report) if report.days == 0 => ().into_any(),
+                            Ok(report) => {
+                                let svg = report.svg.clone();
+                                view! {
+                                    <BenchmarkStats report=report/>
+                                    <div class="border rounded-lg p-2 overflow-x-auto" inner_html=svg></div>
+                                }
+                                .into_any()
+                            }
+                            Err(err) => view! {
+                                <div class="text-red-600 text-sm p-3 bg-red-50 rounded">
+                                    {format!("Error: {}", err)}
+                                </div>
+                            }
+                            .into_any(),
+                        })
+                }}
+            </Suspense>
+        </div>
+    }
+}
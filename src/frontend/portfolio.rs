@@ -1,28 +1,414 @@
 use leptos::prelude::*;
+use leptos_router::components::A;
 use surrealdb::RecordId;
-use crate::backend::db::get_trades;
+use crate::backend::db::{get_assets, get_trades, CancelTrade, CreateTrade, DeleteTrade};
+use crate::backend::export::ExportDataset;
+use crate::backend::model::{Decimal, TradeSide, TradeStatus};
+use crate::backend::currency::{format_amount, format_price};
+use crate::backend::allocation::{get_allocation, AllocationCharts, AllocationSlice};
+use crate::backend::portfolios::{get_portfolios, PortfolioChoice};
+use crate::backend::positions::{get_positions, PositionsReport};
+use crate::frontend::export::ExportLinks;
+use crate::frontend::portfolios::{use_portfolio_selection, PortfolioOverview};
+use crate::frontend::reporting::use_reporting_currency;
+use crate::frontend::risk::RiskPanel;
 
 fn record_key(id: &RecordId) -> String {
     String::try_from(id.key().clone()).unwrap_or_else(|_| id.key().to_string())
 }
 
-fn status_badge(status: &str) -> &'static str {
-    match status.to_ascii_lowercase().as_str() {
-        "open" => "text-emerald-700 bg-emerald-100",
-        "closed" => "text-slate-700 bg-slate-200",
-        "pending" => "text-amber-700 bg-amber-100",
-        _ => "text-slate-700 bg-slate-100",
+fn status_badge(status: TradeStatus) -> &'static str {
+    match status {
+        TradeStatus::Open => "text-emerald-700 bg-emerald-100",
+        TradeStatus::Closed => "text-slate-700 bg-slate-200",
+        TradeStatus::Pending => "text-amber-700 bg-amber-100",
+        TradeStatus::Cancelled => "text-red-700 bg-red-100",
+    }
+}
+
+fn side_class(side: TradeSide) -> &'static str {
+    match side {
+        TradeSide::Buy => "text-green-700",
+        TradeSide::Sell => "text-red-700",
+    }
+}
+
+fn pnl_class(value: Option<Decimal>) -> &'static str {
+    match value {
+        Some(v) if v > Decimal::ZERO => "text-green-700",
+        Some(v) if v < Decimal::ZERO => "text-red-700",
+        _ => "text-gray-700",
+    }
+}
+
+// Quantities are shown exactly as booked, without trailing zeros.
+fn format_qty(qty: Decimal) -> String {
+    qty.normalize().to_string()
+}
+
+fn format_optional(value: Option<Decimal>, ccy: &str) -> String {
+    value
+        .map(|v| format_amount(v, ccy))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+#[component]
+fn HoldingsTable(report: PositionsReport) -> impl IntoView {
+    if report.positions.is_empty() {
+        return view! {
+            <div class="mb-6 rounded-lg border p-4 text-sm text-gray-500">"No holdings yet."</div>
+        }
+        .into_any();
+    }
+
+    let reporting_ccy = report.reporting_ccy.clone();
+    let unconverted_note = report.unconverted_note();
+    let rates_note = report
+        .rates
+        .iter()
+        .map(|quote| {
+            format!(
+                "{}/{} {:.4} ({})",
+                quote.from,
+                quote.to,
+                quote.rate,
+                quote.rate_date.into_inner_ref().format("%Y-%m-%d")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    view! {
+        <div class="overflow-x-auto border rounded-lg mb-6">
+            <table class="min-w-full text-sm">
+                <thead class="bg-teal-500 text-gray-700">
+                    <tr>
+                        <th class="text-left font-medium px-4 py-2">"Asset"</th>
+                        <th class="text-right font-medium px-4 py-2">"Qty"</th>
+                        <th class="text-right font-medium px-4 py-2">"Avg Cost"</th>
+                        <th class="text-right font-medium px-4 py-2">"Cost Basis"</th>
+                        <th class="text-right font-medium px-4 py-2">"Last Price"</th>
+                        <th class="text-right font-medium px-4 py-2">{format!("Market Value ({})", reporting_ccy)}</th>
+                        <th class="text-right font-medium px-4 py-2">{format!("Unrealized P&L ({})", reporting_ccy)}</th>
+                        <th class="text-right font-medium px-4 py-2">{format!("Realized P&L ({})", reporting_ccy)}</th>
+                    </tr>
+                </thead>
+                <tbody class="divide-y">
+                    {report.positions.into_iter().map(|position| {
+                        let market_value = position.reporting_market_value();
+                        let unrealized = position.reporting_unrealized_pnl();
+                        let realized = position.reporting_realized_pnl();
+                        let unrealized_class = pnl_class(unrealized);
+                        let realized_class = pnl_class(realized);
+                        let avg_cost = format_price(position.avg_cost, &position.ccy, position.precision);
+                        let cost_basis = format_amount(position.cost_basis, &position.ccy);
+                        let last_price = position
+                            .last_price
+                            .map(|price| format_price(price, &position.ccy, position.precision))
+                            .unwrap_or_else(|| "-".to_string());
+                        let market_value = format_optional(market_value, &reporting_ccy);
+                        let unrealized_text = format_optional(unrealized, &reporting_ccy);
+                        let realized_text = format_optional(realized, &reporting_ccy);
+                        view! {
+                            <tr class="hover:bg-gray-50">
+                                <td class="px-4 py-2 font-medium text-gray-900">{position.symbol}</td>
+                                <td class="px-4 py-2 text-right text-gray-700">{format_qty(position.qty)}</td>
+                                <td class="px-4 py-2 text-right text-gray-700">{avg_cost}</td>
+                                <td class="px-4 py-2 text-right text-gray-700">{cost_basis}</td>
+                                <td class="px-4 py-2 text-right text-gray-700">{last_price}</td>
+                                <td class="px-4 py-2 text-right text-gray-700">{market_value}</td>
+                                <td class=format!("px-4 py-2 text-right {}", unrealized_class)>{unrealized_text}</td>
+                                <td class=format!("px-4 py-2 text-right {}", realized_class)>{realized_text}</td>
+                            </tr>
+                        }
+                    }).collect_view()}
+                </tbody>
+                <tfoot class="bg-slate-50 font-semibold text-gray-900">
+                    <tr>
+                        <td class="px-4 py-2" colspan="5">"Total"</td>
+                        <td class="px-4 py-2 text-right">{report.market_value.to_string()}</td>
+                        <td class=format!("px-4 py-2 text-right {}", pnl_class(Some(report.unrealized_pnl.amount)))>
+                            {report.unrealized_pnl.to_string()}
+                        </td>
+                        <td class=format!("px-4 py-2 text-right {}", pnl_class(Some(report.realized_pnl.amount)))>
+                            {report.realized_pnl.to_string()}
+                        </td>
+                    </tr>
+                </tfoot>
+            </table>
+            {(!rates_note.is_empty()).then(|| view! {
+                <div class="px-4 py-2 text-xs text-gray-500 border-t">{format!("Rates used: {}", rates_note)}</div>
+            })}
+            {unconverted_note.map(|note| view! {
+                <div class="px-4 py-2 text-xs text-amber-700 bg-amber-50 border-t">{note}</div>
+            })}
+        </div>
+    }
+    .into_any()
+}
+
+fn allocation_card(title: &'static str, svg: String, slices: Vec<AllocationSlice>) -> impl IntoView {
+    view! {
+        <div class="rounded-lg border p-3">
+            <h3 class="text-sm font-semibold text-gray-700 mb-2">{title}</h3>
+            <div class="flex justify-center" inner_html=svg></div>
+            <table class="mt-2 w-full text-xs">
+                <tbody class="divide-y">
+                    {slices
+                        .into_iter()
+                        .map(|slice| view! {
+                            <tr>
+                                <td class="py-1 text-gray-700">{slice.label}</td>
+                                <td class="py-1 text-right text-gray-700">{slice.value.to_string()}</td>
+                                <td class="py-1 text-right text-gray-500">{format!("{:.1}%", slice.weight_pct)}</td>
+                            </tr>
+                        })
+                        .collect_view()}
+                </tbody>
+            </table>
+        </div>
+    }
+}
+
+#[component]
+fn AllocationPanel(charts: AllocationCharts) -> impl IntoView {
+    let allocation = charts.allocation;
+    if allocation.by_category.is_empty() {
+        return ().into_any();
+    }
+
+    view! {
+        <h2 class="text-lg font-semibold text-gray-800 mb-2">"Allocation"</h2>
+        <div class="grid gap-4 md:grid-cols-3 mb-6">
+            {allocation_card("By category", charts.category_svg, allocation.by_category)}
+            {allocation_card("By risk", charts.risk_svg, allocation.by_risk)}
+            {allocation_card("By currency", charts.currency_svg, allocation.by_currency)}
+        </div>
+    }
+    .into_any()
+}
+
+#[component]
+fn TradeForm(
+    action: ServerAction<CreateTrade>,
+    choice: Resource<Result<PortfolioChoice, ServerFnError>>,
+) -> impl IntoView {
+    let assets = Resource::new(|| (), |_| get_assets());
+
+    view! {
+        <ActionForm action=action attr:class="mb-6 p-4 border rounded-lg grid gap-3 sm:grid-cols-9 items-end">
+            <label class="flex flex-col text-sm text-gray-600">
+                "Portfolio"
+                <select name="portfolio" required class="mt-1 rounded border border-slate-200 px-2 py-1.5 text-gray-900">
+                    <Suspense fallback=|| view! { <option value="">"Loading..."</option> }>
+                        {move || {
+                            choice
+                                .get()
+                                .and_then(Result::ok)
+                                .map(|choice| {
+                                    // Defaults to the portfolio on screen, or the first one in the consolidated view.
+                                    let selected = choice.selected.clone();
+                                    choice
+                                        .portfolios
+                                        .into_iter()
+                                        .filter_map(|portfolio| {
+                                            let id = portfolio.id?;
+                                            let is_selected = selected.as_ref() == Some(&id);
+                                            Some(view! { <option value=record_key(&id) selected=is_selected>{portfolio.name}</option> })
+                                        })
+                                        .collect_view()
+                                })
+                        }}
+                    </Suspense>
+                </select>
+            </label>
+            <label class="flex flex-col text-sm text-gray-600 sm:col-span-2">
+                "Asset"
+                <select name="asset" required class="mt-1 rounded border border-slate-200 px-2 py-1.5 text-gray-900">
+                    <Suspense fallback=|| view! { <option value="">"Loading..."</option> }>
+                        {move || {
+                            assets
+                                .get()
+                                .map(|result| {
+                                    result
+                                        .unwrap_or_default()
+                                        .into_iter()
+                                        .filter_map(|asset| {
+                                            let key = record_key(asset.id.as_ref()?);
+                                            Some(view! { <option value=key>{asset.symbol}</option> })
+                                        })
+                                        .collect_view()
+                                })
+                        }}
+                    </Suspense>
+                </select>
+            </label>
+            <label class="flex flex-col text-sm text-gray-600">
+                "Side"
+                <select name="side" class="mt-1 rounded border border-slate-200 px-2 py-1.5 text-gray-900">
+                    {TradeSide::ALL.iter().map(|side| view! { <option value=side.as_str()>{side.as_str()}</option> }).collect_view()}
+                </select>
+            </label>
+            <label class="flex flex-col text-sm text-gray-600">
+                "Price"
+                <input type="number" name="price" step="any" min="0" required class="mt-1 rounded border border-slate-200 px-2 py-1.5 text-gray-900"/>
+            </label>
+            <label class="flex flex-col text-sm text-gray-600">
+                "Qty"
+                <input type="number" name="qty" step="any" min="0" required class="mt-1 rounded border border-slate-200 px-2 py-1.5 text-gray-900"/>
+            </label>
+            <label class="flex flex-col text-sm text-gray-600">
+                "Fees"
+                <input type="number" name="fees" step="any" min="0" value="0" required class="mt-1 rounded border border-slate-200 px-2 py-1.5 text-gray-900"/>
+            </label>
+            <label class="flex flex-col text-sm text-gray-600">
+                "Status"
+                <select name="status" class="mt-1 rounded border border-slate-200 px-2 py-1.5 text-gray-900">
+                    {TradeStatus::INITIAL.iter().map(|status| view! { <option value=status.as_str()>{status.as_str()}</option> }).collect_view()}
+                </select>
+            </label>
+            <label class="flex flex-col text-sm text-gray-600">
+                "Trade date"
+                <input type="datetime-local" name="trade_date" required class="mt-1 rounded border border-slate-200 px-2 py-1.5 text-gray-900"/>
+            </label>
+            <div class="sm:col-span-9 flex items-center justify-between gap-4">
+                <div class="text-sm">
+                    {move || match action.value().get() {
+                        Some(Err(err)) => view! { <span class="text-red-600">{err.to_string()}</span> }.into_any(),
+                        Some(Ok(_)) => view! { <span class="text-emerald-700">"Trade saved."</span> }.into_any(),
+                        None => ().into_any(),
+                    }}
+                </div>
+                <button type="submit" class="rounded bg-teal-600 px-4 py-1.5 text-sm font-medium text-white hover:bg-teal-700" disabled=move || action.pending().get()>
+                    "Add trade"
+                </button>
+            </div>
+        </ActionForm>
     }
 }
 
 #[component]
 pub fn Portfolio() -> impl IntoView {
-    let trades = Resource::new(|| (), |_| get_trades());
+    let create_trade = ServerAction::<CreateTrade>::new();
+    let cancel_trade = ServerAction::<CancelTrade>::new();
+    let delete_trade = ServerAction::<DeleteTrade>::new();
+    let selection = use_portfolio_selection();
+    let choice = Resource::new(move || selection.version(), |_| get_portfolios());
+    let trades = Resource::new(
+        move || {
+            (
+                create_trade.version().get(),
+                cancel_trade.version().get(),
+                delete_trade.version().get(),
+                selection.version(),
+            )
+        },
+        |_| get_trades(),
+    );
+    let assets = Resource::new(|| (), |_| get_assets());
+    let reporting = use_reporting_currency();
+    let positions = Resource::new(
+        move || {
+            (
+                create_trade.version().get(),
+                cancel_trade.version().get(),
+                delete_trade.version().get(),
+                reporting.version(),
+                selection.version(),
+            )
+        },
+        |_| get_positions(),
+    );
+    let allocation = Resource::new(
+        move || {
+            (
+                create_trade.version().get(),
+                cancel_trade.version().get(),
+                delete_trade.version().get(),
+                reporting.version(),
+                selection.version(),
+            )
+        },
+        |_| get_allocation(),
+    );
+    let trades_changed = Signal::derive(move || {
+        create_trade.version().get() + cancel_trade.version().get() + delete_trade.version().get()
+    });
+    let portfolio_name = move |id: &RecordId| {
+        choice
+            .get()
+            .and_then(Result::ok)
+            .and_then(|choice| choice.name_of(id).map(str::to_string))
+            .unwrap_or_default()
+    };
 
     view! {
         <div class="p-4 max-w-4xl mx-auto">
             <h1 class="text-2xl font-bold text-gray-800 mb-2">"Portfolio"</h1>
-            <p class="text-gray-600 mb-6">"Recent trades"</p>
+            <div class="flex items-center justify-between mb-6">
+                <p class="text-gray-600">
+                    <Suspense fallback=|| "Holdings">
+                        {move || {
+                            choice
+                                .get()
+                                .map(|result| match result {
+                                    Ok(choice) => format!("Holdings · {}", choice.selected_name()),
+                                    Err(_) => "Holdings".to_string(),
+                                })
+                        }}
+                    </Suspense>
+                </p>
+                <ExportLinks dataset=ExportDataset::Positions/>
+            </div>
+
+            <Suspense fallback=move || {
+                view! {
+                    <div class="text-center py-8">
+                        <div class="inline-block animate-spin rounded-full h-6 w-6 border-t-2 border-b-2 border-blue-500"></div>
+                    </div>
+                }
+            }>
+                {move || {
+                    positions
+                        .get()
+                        .map(|result| match result {
+                            Ok(report) => view! { <HoldingsTable report=report/> }.into_any(),
+                            Err(err) => view! {
+                                <div class="mb-6 text-red-600 text-sm p-3 bg-red-50 rounded">
+                                    {format!("Error: {}", err)}
+                                </div>
+                            }
+                            .into_any(),
+                        })
+                }}
+                {move || {
+                    allocation
+                        .get()
+                        .map(|result| match result {
+                            Ok(charts) => view! { <AllocationPanel charts=charts/> }.into_any(),
+                            Err(err) => view! {
+                                <div class="mb-6 text-red-600 text-sm p-3 bg-red-50 rounded">
+                                    {format!("Cannot compute allocation: {}", err)}
+                                </div>
+                            }
+                            .into_any(),
+                        })
+                }}
+            </Suspense>
+
+            <RiskPanel refresh=trades_changed/>
+
+            <PortfolioOverview refresh=trades_changed/>
+
+            <div class="flex items-center justify-between mb-2">
+                <h2 class="text-lg font-semibold text-gray-800">"Recent trades"</h2>
+                <div class="flex items-center gap-4">
+                    <ExportLinks dataset=ExportDataset::Trades/>
+                    <A href="/portfolio/import" attr:class="text-sm text-teal-700 hover:underline">"Import CSV"</A>
+                    <A href="/portfolio/lots" attr:class="text-sm text-teal-700 hover:underline">"Tax lots"</A>
+                </div>
+            </div>
+            <TradeForm action=create_trade choice=choice/>
 
             <Suspense fallback=move || {
                 view! {
@@ -32,6 +418,7 @@ pub fn Portfolio() -> impl IntoView {
                 }
             }>
                 {move || {
+                    let listed = assets.get().and_then(Result::ok).unwrap_or_default();
                     trades
                         .get()
                         .map(|result| match result {
@@ -40,23 +427,36 @@ pub fn Portfolio() -> impl IntoView {
                                     <table class="min-w-full text-sm">
                                         <thead class="bg-slate-100 text-gray-700">
                                             <tr>
+                                                <th class="text-left font-medium px-4 py-2">"Portfolio"</th>
                                                 <th class="text-left font-medium px-4 py-2">"Asset"</th>
+                                                <th class="text-left font-medium px-4 py-2">"Side"</th>
                                                 <th class="text-left font-medium px-4 py-2">"Price"</th>
                                                 <th class="text-left font-medium px-4 py-2">"Qty"</th>
+                                                <th class="text-left font-medium px-4 py-2">"Fees"</th>
                                                 <th class="text-left font-medium px-4 py-2">"Status"</th>
                                                 <th class="text-left font-medium px-4 py-2">"Trade Date"</th>
+                                                <th class="px-4 py-2"></th>
                                             </tr>
                                         </thead>
                                         <tbody class="divide-y">
                                             {items.into_iter().map(|item| {
+                                                let trade_key = item.id.as_ref().map(record_key).unwrap_or_default();
+                                                let cancel_key = trade_key.clone();
+                                                let can_cancel = item.status.can_transition_to(TradeStatus::Cancelled);
+                                                let can_delete = item.status.can_delete();
                                                 let asset = record_key(&item.asset);
-                                                let price = format!("{:.2}", item.price);
-                                                let qty = if item.qty.fract() == 0.0 {
-                                                    format!("{:.0}", item.qty)
-                                                } else {
-                                                    format!("{:.2}", item.qty)
+                                                let portfolio = item.portfolio.as_ref().map(portfolio_name).unwrap_or_default();
+                                                // Same precision as the holdings; plain numbers until the assets load.
+                                                let (price, fees) = match listed.iter().find(|a| a.id.as_ref() == Some(&item.asset)) {
+                                                    Some(asset) => (
+                                                        format_price(item.price, &asset.ccy, asset.precision),
+                                                        format_amount(item.fees, &asset.ccy),
+                                                    ),
+                                                    None => (item.price.normalize().to_string(), item.fees.normalize().to_string()),
                                                 };
-                                                let status_class = status_badge(&item.status);
+                                                let qty = format_qty(item.qty);
+                                                let status_class = status_badge(item.status);
+                                                let side_class = side_class(item.side);
                                                 let trade_date = item
                                                     .trade_date
                                                     .into_inner_ref()
@@ -64,15 +464,34 @@ pub fn Portfolio() -> impl IntoView {
                                                     .to_string();
                                                 view! {
                                                     <tr class="hover:bg-gray-50">
+                                                        <td class="px-4 py-2 text-gray-700">{portfolio}</td>
                                                         <td class="px-4 py-2 font-medium text-gray-900">{asset}</td>
+                                                        <td class=format!("px-4 py-2 font-medium {}", side_class)>{item.side.as_str()}</td>
                                                         <td class="px-4 py-2 text-gray-700">{price}</td>
                                                         <td class="px-4 py-2 text-gray-700">{qty}</td>
+                                                        <td class="px-4 py-2 text-gray-700">{fees}</td>
                                                         <td class="px-4 py-2">
                                                             <span class=format!("inline-flex items-center rounded px-2 py-0.5 text-xs font-semibold {}", status_class)>
-                                                                {item.status}
+                                                                {item.status.as_str()}
                                                             </span>
                                                         </td>
                                                         <td class="px-4 py-2 text-gray-700">{trade_date}</td>
+                                                        <td class="px-4 py-2">
+                                                            <div class="flex justify-end gap-2">
+                                                                {can_cancel.then(move || view! {
+                                                                    <ActionForm action=cancel_trade>
+                                                                        <input type="hidden" name="id" value=cancel_key/>
+                                                                        <button type="submit" class="text-xs text-amber-700 hover:underline">"Cancel"</button>
+                                                                    </ActionForm>
+                                                                })}
+                                                                {can_delete.then(move || view! {
+                                                                    <ActionForm action=delete_trade>
+                                                                        <input type="hidden" name="id" value=trade_key/>
+                                                                        <button type="submit" class="text-xs text-red-700 hover:underline">"Delete"</button>
+                                                                    </ActionForm>
+                                                                })}
+                                                            </div>
+                                                        </td>
                                                     </tr>
                                                 }
                                             }).collect_view()}
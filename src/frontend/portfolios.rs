@@ -0,0 +1,170 @@
+use leptos::prelude::*;
+use surrealdb::RecordId;
+use crate::backend::portfolios::{
+    get_portfolio_overview, get_portfolios, CreatePortfolio, DeletePortfolio, PortfolioSummary,
+    SelectPortfolio, ALL_PORTFOLIOS,
+};
+
+fn record_key(id: &RecordId) -> String {
+    String::try_from(id.key().clone()).unwrap_or_else(|_| id.key().to_string())
+}
+
+/// Shared handle on the portfolio selection. Pages add `version()` to their
+/// resource sources so they refresh when the user switches, creates or
+/// deletes a portfolio.
+#[derive(Clone, Copy)]
+pub struct PortfolioSelection {
+    pub select: ServerAction<SelectPortfolio>,
+    pub create: ServerAction<CreatePortfolio>,
+    pub delete: ServerAction<DeletePortfolio>,
+}
+
+impl PortfolioSelection {
+    pub fn version(&self) -> usize {
+        self.select.version().get() + self.create.version().get() + self.delete.version().get()
+    }
+}
+
+pub fn provide_portfolio_selection() {
+    provide_context(PortfolioSelection {
+        select: ServerAction::<SelectPortfolio>::new(),
+        create: ServerAction::<CreatePortfolio>::new(),
+        delete: ServerAction::<DeletePortfolio>::new(),
+    });
+}
+
+pub fn use_portfolio_selection() -> PortfolioSelection {
+    expect_context::<PortfolioSelection>()
+}
+
+#[component]
+pub fn PortfolioSelector() -> impl IntoView {
+    let selection = use_portfolio_selection();
+    let choice = Resource::new(move || selection.version(), |_| get_portfolios());
+
+    view! {
+        <ActionForm action=selection.select attr:class="flex items-center gap-2 text-sm">
+            <label for="portfolio-select" class="text-gray-400">"Portfolio"</label>
+            <Suspense fallback=|| ()>
+                {move || {
+                    choice
+                        .get()
+                        .map(|result| {
+                            let choice = result.ok();
+                            let selected = choice.as_ref().and_then(|c| c.selected.as_ref().map(record_key));
+                            let all_selected = selected.is_none();
+                            view! {
+                                <select id="portfolio-select" name="portfolio" class="rounded bg-gray-700 px-2 py-1 text-white">
+                                    <option value=ALL_PORTFOLIOS selected=all_selected>"All portfolios"</option>
+                                    {choice
+                                        .map(|c| c.portfolios)
+                                        .unwrap_or_default()
+                                        .into_iter()
+                                        .filter_map(|portfolio| {
+                                            let key = record_key(portfolio.id.as_ref()?);
+                                            let is_selected = selected.as_deref() == Some(key.as_str());
+                                            Some(view! { <option value=key selected=is_selected>{portfolio.name}</option> })
+                                        })
+                                        .collect_view()}
+                                </select>
+                            }
+                        })
+                }}
+            </Suspense>
+            <button type="submit" class="rounded bg-gray-600 px-2 py-1 hover:bg-gray-500">"Set"</button>
+        </ActionForm>
+    }
+}
+
+fn summary_row(summary: PortfolioSummary, delete: ServerAction<DeletePortfolio>) -> impl IntoView {
+    let key = summary.portfolio.id.as_ref().map(record_key).unwrap_or_default();
+    let unrealized_class = if summary.unrealized_pnl.is_negative() { "text-red-700" } else { "text-green-700" };
+    let realized_class = if summary.realized_pnl.is_negative() { "text-red-700" } else { "text-green-700" };
+    let can_delete = summary.trades == 0;
+
+    view! {
+        <tr class="hover:bg-gray-50">
+            <td class="px-4 py-2 font-medium text-gray-900">{summary.portfolio.name}</td>
+            <td class="px-4 py-2 text-right text-gray-700">{summary.positions}</td>
+            <td class="px-4 py-2 text-right text-gray-700">{summary.market_value.to_string()}</td>
+            <td class=format!("px-4 py-2 text-right {}", unrealized_class)>{summary.unrealized_pnl.to_string()}</td>
+            <td class=format!("px-4 py-2 text-right {}", realized_class)>{summary.realized_pnl.to_string()}</td>
+            <td class="px-4 py-2 text-right">
+                {can_delete.then(move || view! {
+                    <ActionForm action=delete>
+                        <input type="hidden" name="id" value=key/>
+                        <button type="submit" class="text-xs text-red-700 hover:underline">"Delete"</button>
+                    </ActionForm>
+                })}
+            </td>
+        </tr>
+    }
+}
+
+/// Consolidated view: one row per portfolio, plus the form to add one.
+#[component]
+pub fn PortfolioOverview(#[prop(into)] refresh: Signal<usize>) -> impl IntoView {
+    let selection = use_portfolio_selection();
+    let overview = Resource::new(
+        move || (selection.version(), refresh.get()),
+        |_| get_portfolio_overview(),
+    );
+
+    view! {
+        <h2 class="text-lg font-semibold text-gray-800 mb-2">"Portfolios"</h2>
+        <Suspense fallback=|| ()>
+            {move || {
+                overview
+                    .get()
+                    .map(|result| match result {
+                        Ok(summaries) => view! {
+                            <div class="overflow-x-auto border rounded-lg mb-3">
+                                <table class="min-w-full text-sm">
+                                    <thead class="bg-slate-100 text-gray-700">
+                                        <tr>
+                                            <th class="text-left font-medium px-4 py-2">"Name"</th>
+                                            <th class="text-right font-medium px-4 py-2">"Positions"</th>
+                                            <th class="text-right font-medium px-4 py-2">"Market Value"</th>
+                                            <th class="text-right font-medium px-4 py-2">"Unrealized P&L"</th>
+                                            <th class="text-right font-medium px-4 py-2">"Realized P&L"</th>
+                                            <th class="px-4 py-2"></th>
+                                        </tr>
+                                    </thead>
+                                    <tbody class="divide-y">
+                                        {summaries
+                                            .into_iter()
+                                            .map(|summary| summary_row(summary, selection.delete))
+                                            .collect_view()}
+                                    </tbody>
+                                </table>
+                            </div>
+                        }
+                        .into_any(),
+                        Err(err) => view! {
+                            <div class="mb-3 text-red-600 text-sm p-3 bg-red-50 rounded">
+                                {format!("Error: {}", err)}
+                            </div>
+                        }
+                        .into_any(),
+                    })
+            }}
+        </Suspense>
+        {move || match selection.delete.value().get() {
+            Some(Err(err)) => view! { <div class="mb-3 text-sm text-red-600">{err.to_string()}</div> }.into_any(),
+            _ => ().into_any(),
+        }}
+        <ActionForm action=selection.create attr:class="mb-6 flex items-end gap-3">
+            <label class="flex flex-col text-sm text-gray-600">
+                "New portfolio"
+                <input type="text" name="name" required maxlength="64" class="mt-1 rounded border border-slate-200 px-2 py-1.5 text-gray-900"/>
+            </label>
+            <button type="submit" class="rounded bg-slate-700 px-4 py-1.5 text-sm font-medium text-white hover:bg-slate-800" disabled=move || selection.create.pending().get()>
+                "Create"
+            </button>
+            {move || match selection.create.value().get() {
+                Some(Err(err)) => view! { <span class="text-sm text-red-600">{err.to_string()}</span> }.into_any(),
+                _ => ().into_any(),
+            }}
+        </ActionForm>
+    }
+}
@@ -0,0 +1,61 @@
+use leptos::prelude::*;
+use crate::backend::settings::{get_reporting_currencies, get_reporting_currency, SetReportingCurrency};
+
+/// Shared handle on the reporting-currency setting. Pages add `version()` to
+/// their resource sources so converted totals refresh when it changes.
+#[derive(Clone, Copy)]
+pub struct ReportingCurrency {
+    pub set: ServerAction<SetReportingCurrency>,
+}
+
+impl ReportingCurrency {
+    pub fn version(&self) -> usize {
+        self.set.version().get()
+    }
+}
+
+pub fn provide_reporting_currency() {
+    provide_context(ReportingCurrency {
+        set: ServerAction::<SetReportingCurrency>::new(),
+    });
+}
+
+pub fn use_reporting_currency() -> ReportingCurrency {
+    expect_context::<ReportingCurrency>()
+}
+
+#[component]
+pub fn CurrencySelector() -> impl IntoView {
+    let reporting = use_reporting_currency();
+    let current = Resource::new(move || reporting.version(), |_| get_reporting_currency());
+    let available = Resource::new(|| (), |_| get_reporting_currencies());
+
+    view! {
+        <ActionForm action=reporting.set attr:class="flex items-center gap-2 text-sm">
+            <label for="reporting-ccy" class="text-gray-400">"Currency"</label>
+            <Suspense fallback=|| ()>
+                {move || {
+                    let selected = current.get().and_then(Result::ok).unwrap_or_default();
+                    available
+                        .get()
+                        .map(|result| {
+                            view! {
+                                <select id="reporting-ccy" name="ccy" class="rounded bg-gray-700 px-2 py-1 text-white">
+                                    {result
+                                        .unwrap_or_default()
+                                        .into_iter()
+                                        .map(|ccy| {
+                                            let is_selected = ccy == selected;
+                                            let label = ccy.clone();
+                                            view! { <option value=ccy selected=is_selected>{label}</option> }
+                                        })
+                                        .collect_view()}
+                                </select>
+                            }
+                        })
+                }}
+            </Suspense>
+            <button type="submit" class="rounded bg-gray-600 px-2 py-1 hover:bg-gray-500">"Set"</button>
+        </ActionForm>
+    }
+}
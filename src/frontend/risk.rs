@@ -0,0 +1,168 @@
+use leptos::prelude::*;
+use leptos_router::components::A;
+use crate::backend::prices::PriceRange;
+use crate::backend::risk::{get_portfolio_risk, RiskMetrics, CONFIDENCE};
+use crate::frontend::correlation::CorrelationPanel;
+use crate::frontend::portfolios::use_portfolio_selection;
+use crate::frontend::reporting::use_reporting_currency;
+
+fn format_pct(value: Option<f64>) -> String {
+    value.map(|v| format!("{:.2}%", v)).unwrap_or_else(|| "-".to_string())
+}
+
+fn format_ratio(value: Option<f64>) -> String {
+    value.map(|v| format!("{:.2}", v)).unwrap_or_else(|| "-".to_string())
+}
+
+fn toggle_class(active: bool) -> &'static str {
+    if active {
+        "px-3 py-1 text-sm rounded bg-teal-600 text-white"
+    } else {
+        "px-3 py-1 text-sm rounded border border-slate-200 text-gray-700 hover:bg-gray-50"
+    }
+}
+
+// Label and formatted value of each metric, in display order.
+fn metric_cells(metrics: &RiskMetrics) -> [(String, String); 8] {
+    let level = format!("{:.0}%", CONFIDENCE * 100.0);
+    [
+        ("Volatility, per year".to_string(), format_pct(metrics.volatility_pct)),
+        ("Max drawdown".to_string(), format_pct(metrics.max_drawdown_pct)),
+        (format!("VaR {}", level), format_pct(metrics.var_pct)),
+        (format!("CVaR {}", level), format_pct(metrics.cvar_pct)),
+        (format!("Normal VaR {}", level), format_pct(metrics.parametric_var_pct)),
+        (format!("Normal CVaR {}", level), format_pct(metrics.parametric_cvar_pct)),
+        ("Sharpe".to_string(), format_ratio(metrics.sharpe)),
+        ("Sortino".to_string(), format_ratio(metrics.sortino)),
+    ]
+}
+
+/// Risk figures of one series as a grid of cards.
+#[component]
+pub fn RiskStats(metrics: RiskMetrics) -> impl IntoView {
+    if metrics.volatility_pct.is_none() {
+        return view! {
+            <div class="rounded-lg border p-4 text-sm text-gray-500 mb-4">
+                "Not enough prices to measure risk."
+            </div>
+        }
+        .into_any();
+    }
+
+    view! {
+        <div class="grid gap-4 sm:grid-cols-4 mb-2">
+            {metric_cells(&metrics)
+                .into_iter()
+                .map(|(label, value)| view! {
+                    <div class="rounded-lg border p-3">
+                        <div class="text-xs text-gray-500">{label}</div>
+                        <div class="text-lg font-semibold text-gray-900">{value}</div>
+                    </div>
+                })
+                .collect_view()}
+        </div>
+        <p class="text-xs text-gray-500 mb-4">
+            {format!("From {} returns. VaR and CVaR are losses over one period.", metrics.observations)}
+        </p>
+    }
+    .into_any()
+}
+
+/// Risk of the selected portfolio and of each asset it holds, then how the holdings
+/// move together, over a range picked with the buttons above the table.
+#[component]
+pub fn RiskPanel(#[prop(into)] refresh: Signal<usize>) -> impl IntoView {
+    let selection = use_portfolio_selection();
+    let reporting = use_reporting_currency();
+    let range = RwSignal::new(PriceRange::Year);
+    let risk = Resource::new(
+        move || (range.get(), refresh.get(), selection.version(), reporting.version()),
+        |(range, _, _, _)| get_portfolio_risk(range),
+    );
+    let headers = metric_cells(&RiskMetrics::default()).map(|(label, _)| label);
+
+    view! {
+        <div class="flex flex-wrap items-center justify-between gap-4 mb-2">
+            <h2 class="text-lg font-semibold text-gray-800">"Risk"</h2>
+            <div class="flex gap-2">
+                {PriceRange::ALL
+                    .into_iter()
+                    .map(|option| view! {
+                        <button type="button" class=move || toggle_class(range.get() == option) on:click=move |_| range.set(option)>
+                            {option.label()}
+                        </button>
+                    })
+                    .collect_view()}
+            </div>
+        </div>
+        <Suspense fallback=|| ()>
+            {move || {
+                risk.get()
+                    .map(|result| match result {
+                        Ok(risk) if risk.portfolio.observations == 0 && risk.assets.is_empty() => ().into_any(),
+                        Ok(risk) => {
+                            let mut rows = vec![(format!("Portfolio ({})", risk.reporting_ccy), None, risk.portfolio)];
+                            rows.extend(
+                                risk.assets
+                                    .into_iter()
+                                    .map(|asset| (format!("{} ({})", asset.symbol, asset.ccy), Some(asset.symbol), asset.metrics)),
+                            );
+                            let unconverted_note = risk.unconverted_note;
+                            view! {
+                                {unconverted_note.map(|note| view! {
+                                    <div class="mb-2 rounded bg-amber-50 p-3 text-sm text-amber-700">{note}</div>
+                                })}
+                                <div class="overflow-x-auto border rounded-lg mb-2">
+                                    <table class="min-w-full text-sm">
+                                        <thead class="bg-slate-100 text-gray-700">
+                                            <tr>
+                                                <th class="text-left font-medium px-4 py-2"></th>
+                                                {headers
+                                                    .clone()
+                                                    .into_iter()
+                                                    .map(|label| view! { <th class="text-right font-medium px-4 py-2">{label}</th> })
+                                                    .collect_view()}
+                                            </tr>
+                                        </thead>
+                                        <tbody class="divide-y">
+                                            {rows
+                                                .into_iter()
+                                                .map(|(label, symbol, metrics)| view! {
+                                                    <tr>
+                                                        <td class="px-4 py-2 font-medium text-gray-900">
+                                                            {match symbol {
+                                                                Some(symbol) => view! {
+                                                                    <A href=format!("/asset/{}", symbol) attr:class="text-teal-700 hover:underline">{label}</A>
+                                                                }
+                                                                .into_any(),
+                                                                None => label.into_any(),
+                                                            }}
+                                                        </td>
+                                                        {metric_cells(&metrics)
+                                                            .into_iter()
+                                                            .map(|(_, value)| view! { <td class="px-4 py-2 text-right text-gray-700">{value}</td> })
+                                                            .collect_view()}
+                                                    </tr>
+                                                })
+                                                .collect_view()}
+                                        </tbody>
+                                    </table>
+                                </div>
+                                <p class="text-xs text-gray-500 mb-6">
+                                    "The portfolio is measured on daily time-weighted returns in the reporting currency, each asset on its own prices and currency. VaR and CVaR are one-day losses."
+                                </p>
+                            }
+                            .into_any()
+                        }
+                        Err(err) => view! {
+                            <div class="mb-6 text-red-600 text-sm p-3 bg-red-50 rounded">
+                                {format!("Cannot measure risk: {}", err)}
+                            </div>
+                        }
+                        .into_any(),
+                    })
+            }}
+        </Suspense>
+        <CorrelationPanel range=range refresh=refresh/>
+    }
+}
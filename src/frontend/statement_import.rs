@@ -0,0 +1,174 @@
+use leptos::prelude::*;
+use leptos_router::components::A;
+use crate::backend::currency::format_amount;
+use crate::backend::statement_import::{ImportStatement, PreviewStatementImport, StatementPreview};
+use crate::backend::trade_import::RowOutcome;
+use crate::frontend::trade_import::load_file;
+
+fn outcome_badge(outcome: RowOutcome) -> &'static str {
+    match outcome {
+        RowOutcome::New => "text-emerald-700 bg-emerald-100",
+        RowOutcome::Duplicate => "text-slate-700 bg-slate-200",
+        RowOutcome::Invalid => "text-red-700 bg-red-100",
+    }
+}
+
+#[component]
+fn StatementTable(preview: StatementPreview) -> impl IntoView {
+    view! {
+        <p class="text-sm text-gray-700 mb-2">
+            {format!(
+                "{} statement: {} new, {} already in the wallet, {} with errors",
+                preview.format.label(), preview.new, preview.duplicates, preview.invalid
+            )}
+        </p>
+        <div class="overflow-x-auto border rounded-lg mb-4">
+            <table class="min-w-full text-sm">
+                <thead class="bg-slate-100 text-gray-700">
+                    <tr>
+                        <th class="text-left font-medium px-3 py-2">"Line"</th>
+                        <th class="text-left font-medium px-3 py-2">"Result"</th>
+                        <th class="text-left font-medium px-3 py-2">"Date"</th>
+                        <th class="text-right font-medium px-3 py-2">"Amount"</th>
+                        <th class="text-left font-medium px-3 py-2">"Note"</th>
+                        <th class="text-left font-medium px-3 py-2">"Reference"</th>
+                        <th class="text-left font-medium px-3 py-2">"Status"</th>
+                        <th class="text-left font-medium px-3 py-2">"Errors"</th>
+                    </tr>
+                </thead>
+                <tbody class="divide-y">
+                    {preview
+                        .rows
+                        .into_iter()
+                        .map(|row| {
+                            let amount = row
+                                .amount
+                                .map(|a| format_amount(a, &row.ccy))
+                                .unwrap_or_else(|| "-".to_string());
+                            let amount_class = if row.amount.is_some_and(|a| a.is_sign_negative()) { "text-red-700" } else { "text-gray-700" };
+                            view! {
+                                <tr class="hover:bg-gray-50">
+                                    <td class="px-3 py-2 text-gray-500">{row.line}</td>
+                                    <td class="px-3 py-2">
+                                        <span class=format!("inline-flex items-center rounded px-2 py-0.5 text-xs font-semibold {}", outcome_badge(row.outcome))>
+                                            {row.outcome.as_str()}
+                                        </span>
+                                    </td>
+                                    <td class="px-3 py-2 text-gray-700">{row.date}</td>
+                                    <td class=format!("px-3 py-2 text-right {}", amount_class)>{amount}</td>
+                                    <td class="px-3 py-2 text-gray-900">{row.note}</td>
+                                    <td class="px-3 py-2 text-xs text-gray-500">{row.reference.unwrap_or_default()}</td>
+                                    <td class="px-3 py-2 text-gray-700">{row.status}</td>
+                                    <td class="px-3 py-2 text-red-700">{row.errors.join("; ")}</td>
+                                </tr>
+                            }
+                        })
+                        .collect_view()}
+                </tbody>
+            </table>
+        </div>
+    }
+}
+
+/// Bank statement import: load a CSV, OFX or CAMT.053 file, preview, then import.
+#[component]
+pub fn StatementImport() -> impl IntoView {
+    let statement = RwSignal::new(String::new());
+    let ccy = RwSignal::new("EUR".to_string());
+    let preview = ServerAction::<PreviewStatementImport>::new();
+    let import = ServerAction::<ImportStatement>::new();
+
+    let run_preview = move |_| {
+        preview.dispatch(PreviewStatementImport {
+            statement: statement.get_untracked(),
+            ccy: ccy.get_untracked(),
+        });
+    };
+    let run_import = move |_| {
+        import.dispatch(ImportStatement {
+            statement: statement.get_untracked(),
+            ccy: ccy.get_untracked(),
+        });
+    };
+    let can_import = move || {
+        matches!(preview.value().get(), Some(Ok(p)) if p.invalid == 0 && p.new > 0) && !import.pending().get()
+    };
+
+    view! {
+        <div class="p-4 max-w-5xl mx-auto">
+            <div class="flex items-center justify-between mb-2">
+                <h1 class="text-2xl font-bold text-gray-800">"Import bank statement"</h1>
+                <A href="/wallet" attr:class="text-sm text-teal-700 hover:underline">"Back to wallet"</A>
+            </div>
+            <p class="text-gray-600 mb-6">
+                "Bank CSV exports, OFX/QFX files and ISO 20022 CAMT.053 statements are recognised automatically. Lines already in the wallet are skipped."
+            </p>
+
+            <div class="mb-6 p-4 border rounded-lg flex flex-col gap-3">
+                <input
+                    type="file"
+                    accept=".csv,.ofx,.qfx,.xml,text/csv,text/plain,application/xml,text/xml"
+                    class="text-sm"
+                    on:change=move |ev| load_file(ev, statement)
+                />
+                <textarea
+                    rows="6"
+                    placeholder="...or paste the statement here"
+                    class="rounded border border-slate-200 px-2 py-1.5 font-mono text-xs text-gray-900"
+                    prop:value=move || statement.get()
+                    on:input=move |ev| statement.set(event_target_value(&ev))
+                ></textarea>
+                <div class="flex items-end gap-4">
+                    <label class="flex flex-col text-sm text-gray-600">
+                        "Currency when the file has none"
+                        <input
+                            type="text"
+                            maxlength="3"
+                            class="mt-1 w-24 rounded border border-slate-200 px-2 py-1.5 uppercase text-gray-900"
+                            prop:value=move || ccy.get()
+                            on:input=move |ev| ccy.set(event_target_value(&ev))
+                        />
+                    </label>
+                    <button
+                        class="rounded bg-slate-700 px-4 py-1.5 text-sm font-medium text-white hover:bg-slate-800"
+                        disabled=move || statement.get().trim().is_empty() || preview.pending().get()
+                        on:click=run_preview
+                    >
+                        "Preview"
+                    </button>
+                </div>
+            </div>
+
+            {move || match preview.value().get() {
+                None => ().into_any(),
+                Some(Err(err)) => view! {
+                    <div class="mb-6 text-red-600 text-sm p-3 bg-red-50 rounded">{format!("Error: {}", err)}</div>
+                }
+                .into_any(),
+                Some(Ok(result)) => view! {
+                    <StatementTable preview=result/>
+                    <div class="flex items-center gap-4">
+                        <button
+                            class="rounded bg-teal-600 px-4 py-1.5 text-sm font-medium text-white hover:bg-teal-700 disabled:opacity-50"
+                            disabled=move || !can_import()
+                            on:click=run_import
+                        >
+                            "Import"
+                        </button>
+                        {move || match import.value().get() {
+                            Some(Ok(done)) => view! {
+                                <span class="text-sm text-emerald-700">
+                                    {format!("Imported {} entry(ies), skipped {} duplicate(s).", done.created, done.duplicates)}
+                                </span>
+                            }
+                            .into_any(),
+                            Some(Err(err)) => view! { <span class="text-sm text-red-600">{err.to_string()}</span> }.into_any(),
+                            None => ().into_any(),
+                        }}
+                    </div>
+                }
+                .into_any(),
+            }}
+        </div>
+    }
+}
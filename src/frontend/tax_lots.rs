@@ -0,0 +1,395 @@
+use std::str::FromStr;
+
+use leptos::prelude::*;
+use leptos_router::components::A;
+use leptos_router::hooks::use_params_map;
+use surrealdb::RecordId;
+use crate::backend::currency::{format_amount, format_price};
+use crate::backend::model::Decimal;
+use crate::backend::tax_lots::{
+    get_sell_lots, get_tax_lots, AssignLots, LotMethod, RematchLots, SellLots, SetLotMethod, TaxLotReport,
+    LONG_TERM_DAYS,
+};
+use crate::frontend::portfolios::use_portfolio_selection;
+
+fn record_key(id: &RecordId) -> String {
+    String::try_from(id.key().clone()).unwrap_or_else(|_| id.key().to_string())
+}
+
+// Quantities are shown exactly as booked, without trailing zeros.
+fn format_qty(qty: Decimal) -> String {
+    qty.normalize().to_string()
+}
+
+fn gain_class(value: Option<Decimal>) -> &'static str {
+    match value {
+        Some(v) if v > Decimal::ZERO => "text-green-700",
+        Some(v) if v < Decimal::ZERO => "text-red-700",
+        _ => "text-gray-700",
+    }
+}
+
+fn holding(days: i64, long_term: bool) -> impl IntoView {
+    view! {
+        <span class="text-gray-700">{format!("{} d", days)}</span>
+        {long_term.then(|| view! {
+            <span class="ml-2 rounded px-1.5 py-0.5 text-xs text-emerald-700 bg-emerald-100">"long"</span>
+        })}
+    }
+}
+
+#[component]
+fn LotTables(report: TaxLotReport) -> impl IntoView {
+    view! {
+        <div class="grid gap-4 sm:grid-cols-3 mb-6">
+            {report
+                .totals
+                .into_iter()
+                .map(|total| view! {
+                    <div class="rounded-lg border p-3">
+                        <div class="text-xs text-gray-500">{format!("Realized gain, {}", total.ccy)}</div>
+                        <div class="mt-1 flex justify-between text-sm">
+                            <span class="text-gray-600">"Short-term"</span>
+                            <span class=format!("font-semibold {}", gain_class(Some(total.short_term)))>
+                                {format_amount(total.short_term, &total.ccy)}
+                            </span>
+                        </div>
+                        <div class="flex justify-between text-sm">
+                            <span class="text-gray-600">"Long-term"</span>
+                            <span class=format!("font-semibold {}", gain_class(Some(total.long_term)))>
+                                {format_amount(total.long_term, &total.ccy)}
+                            </span>
+                        </div>
+                    </div>
+                })
+                .collect_view()}
+        </div>
+
+        {(!report.unmatched.is_empty()).then(|| view! {
+            <div class="mb-6 rounded p-3 text-sm text-amber-800 bg-amber-50">
+                <ul class="list-disc pl-5">
+                    {report.unmatched.into_iter().map(|line| view! { <li>{line}</li> }).collect_view()}
+                </ul>
+            </div>
+        })}
+
+        <h2 class="text-lg font-semibold text-gray-800 mb-2">"Open lots"</h2>
+        <div class="overflow-x-auto border rounded-lg mb-6">
+            <table class="min-w-full text-sm">
+                <thead class="bg-slate-100 text-gray-700">
+                    <tr>
+                        <th class="text-left font-medium px-4 py-2">"Asset"</th>
+                        <th class="text-left font-medium px-4 py-2">"Bought"</th>
+                        <th class="text-right font-medium px-4 py-2">"Qty"</th>
+                        <th class="text-right font-medium px-4 py-2">"Unit cost"</th>
+                        <th class="text-right font-medium px-4 py-2">"Cost basis"</th>
+                        <th class="text-right font-medium px-4 py-2">"Unrealized"</th>
+                        <th class="text-left font-medium px-4 py-2">"Held"</th>
+                    </tr>
+                </thead>
+                <tbody class="divide-y">
+                    {report
+                        .open
+                        .into_iter()
+                        .map(|lot| view! {
+                            <tr>
+                                <td class="px-4 py-2 font-medium text-gray-900">{lot.symbol.clone()}</td>
+                                <td class="px-4 py-2 text-gray-700">{lot.bought.clone()}</td>
+                                <td class="px-4 py-2 text-right text-gray-700">{format_qty(lot.qty)}</td>
+                                <td class="px-4 py-2 text-right text-gray-700">{format_price(lot.unit_cost, &lot.ccy, lot.precision)}</td>
+                                <td class="px-4 py-2 text-right text-gray-700">{format_amount(lot.cost_basis, &lot.ccy)}</td>
+                                <td class=format!("px-4 py-2 text-right {}", gain_class(lot.unrealized_gain))>
+                                    {lot.unrealized_gain.map(|g| format_amount(g, &lot.ccy)).unwrap_or_else(|| "-".to_string())}
+                                </td>
+                                <td class="px-4 py-2">{holding(lot.holding_days, lot.long_term)}</td>
+                            </tr>
+                        })
+                        .collect_view()}
+                </tbody>
+            </table>
+        </div>
+
+        <h2 class="text-lg font-semibold text-gray-800 mb-2">"Realized"</h2>
+        <div class="overflow-x-auto border rounded-lg">
+            <table class="min-w-full text-sm">
+                <thead class="bg-slate-100 text-gray-700">
+                    <tr>
+                        <th class="text-left font-medium px-4 py-2">"Asset"</th>
+                        <th class="text-left font-medium px-4 py-2">"Bought"</th>
+                        <th class="text-left font-medium px-4 py-2">"Sold"</th>
+                        <th class="text-right font-medium px-4 py-2">"Qty"</th>
+                        <th class="text-right font-medium px-4 py-2">"Cost basis"</th>
+                        <th class="text-right font-medium px-4 py-2">"Proceeds"</th>
+                        <th class="text-right font-medium px-4 py-2">"Gain"</th>
+                        <th class="text-left font-medium px-4 py-2">"Held"</th>
+                        <th class="text-left font-medium px-4 py-2">"Matched by"</th>
+                    </tr>
+                </thead>
+                <tbody class="divide-y">
+                    {report
+                        .realized
+                        .into_iter()
+                        .map(|lot| view! {
+                            <tr>
+                                <td class="px-4 py-2 font-medium text-gray-900">{lot.symbol.clone()}</td>
+                                <td class="px-4 py-2 text-gray-700">{lot.bought.clone()}</td>
+                                <td class="px-4 py-2 text-gray-700">{lot.sold.clone()}</td>
+                                <td class="px-4 py-2 text-right text-gray-700">{format_qty(lot.qty)}</td>
+                                <td class="px-4 py-2 text-right text-gray-700">{format_amount(lot.cost_basis, &lot.ccy)}</td>
+                                <td class="px-4 py-2 text-right text-gray-700">{format_amount(lot.proceeds, &lot.ccy)}</td>
+                                <td class=format!("px-4 py-2 text-right {}", gain_class(Some(lot.gain)))>{format_amount(lot.gain, &lot.ccy)}</td>
+                                <td class="px-4 py-2">{holding(lot.holding_days, lot.long_term)}</td>
+                                <td class="px-4 py-2">
+                                    <A href=format!("/portfolio/lots/{}", record_key(&lot.sell)) attr:class="text-teal-700 hover:underline">
+                                        {lot.method.label()}
+                                    </A>
+                                </td>
+                            </tr>
+                        })
+                        .collect_view()}
+                </tbody>
+            </table>
+        </div>
+    }
+}
+
+/// Tax-lot ledger of the selected portfolio: the method sells are matched with,
+/// open lots, and the gain each sell realized on each lot it closed.
+#[component]
+pub fn TaxLots() -> impl IntoView {
+    let set_method = ServerAction::<SetLotMethod>::new();
+    let rematch = ServerAction::<RematchLots>::new();
+    let selection = use_portfolio_selection();
+    let report = Resource::new(
+        move || (set_method.version().get(), rematch.version().get(), selection.version()),
+        |_| get_tax_lots(),
+    );
+
+    view! {
+        <div class="p-4 max-w-5xl mx-auto">
+            <div class="flex items-center justify-between mb-2">
+                <h1 class="text-2xl font-bold text-gray-800">"Tax lots"</h1>
+                <A href="/portfolio" attr:class="text-sm text-teal-700 hover:underline">"Back to portfolio"</A>
+            </div>
+            <p class="text-gray-600 mb-6">
+                {format!(
+                    "Each sell closes buy lots of the same asset and portfolio. Once matched, a sell keeps its lots, so past gains do not move. Gains on lots held over {} days are long-term.",
+                    LONG_TERM_DAYS
+                )}
+            </p>
+
+            <div class="flex flex-wrap items-end gap-4 mb-6">
+                <ActionForm action=set_method attr:class="flex items-end gap-2">
+                    <label class="flex flex-col text-sm text-gray-600">
+                        "Match new sells by"
+                        <Suspense fallback=|| ()>
+                            {move || {
+                                let current = report
+                                    .get()
+                                    .and_then(Result::ok)
+                                    .map(|report| report.method)
+                                    .unwrap_or_default();
+                                view! {
+                                    <select name="method" class="mt-1 rounded border border-slate-200 px-2 py-1.5 text-gray-900">
+                                        {LotMethod::AUTOMATIC
+                                            .into_iter()
+                                            .map(|method| view! {
+                                                <option value=method.as_str() selected=method == current>{method.label()}</option>
+                                            })
+                                            .collect_view()}
+                                    </select>
+                                }
+                            }}
+                        </Suspense>
+                    </label>
+                    <button type="submit" class="rounded bg-slate-700 px-3 py-1.5 text-sm font-medium text-white hover:bg-slate-800">
+                        "Save"
+                    </button>
+                </ActionForm>
+                <ActionForm action=rematch attr:class="flex items-end gap-2">
+                    <button type="submit" class="rounded border border-slate-200 px-3 py-1.5 text-sm text-gray-700 hover:bg-gray-50">
+                        "Re-match past sells"
+                    </button>
+                </ActionForm>
+                {move || {
+                    rematch.value().get().map(|result| match result {
+                        Ok(count) => view! {
+                            <span class="text-sm text-gray-600">
+                                {format!("{} sell(s) matched again; lots picked by hand were kept.", count)}
+                            </span>
+                        }
+                        .into_any(),
+                        Err(err) => view! { <span class="text-sm text-red-600">{format!("Error: {}", err)}</span> }.into_any(),
+                    })
+                }}
+                {move || {
+                    set_method.value().get().and_then(Result::err).map(|err| view! {
+                        <span class="text-sm text-red-600">{format!("Error: {}", err)}</span>
+                    })
+                }}
+            </div>
+
+            <Suspense fallback=move || {
+                view! {
+                    <div class="text-center py-8">
+                        <div class="inline-block animate-spin rounded-full h-6 w-6 border-t-2 border-b-2 border-blue-500"></div>
+                    </div>
+                }
+            }>
+                {move || {
+                    report
+                        .get()
+                        .map(|result| match result {
+                            Ok(report) => view! { <LotTables report=report/> }.into_any(),
+                            Err(err) => view! {
+                                <div class="text-red-600 text-sm p-3 bg-red-50 rounded">
+                                    {format!("Error: {}", err)}
+                                </div>
+                            }
+                            .into_any(),
+                        })
+                }}
+            </Suspense>
+        </div>
+    }
+}
+
+#[component]
+fn LotPicker(lots: SellLots, action: ServerAction<AssignLots>) -> impl IntoView {
+    let sell = record_key(&lots.sell);
+    let quantities: Vec<(String, RwSignal<String>)> = lots
+        .candidates
+        .iter()
+        .map(|lot| (record_key(&lot.buy), RwSignal::new(format_qty(lot.assigned))))
+        .collect();
+    let error = RwSignal::new(None::<String>);
+    let inputs = quantities.clone();
+    let submit = move |_| {
+        let mut chosen = Vec::new();
+        for (buy, qty) in &inputs {
+            let value = qty.get_untracked();
+            match value.trim() {
+                "" => {}
+                text => match Decimal::from_str(text) {
+                    Ok(qty) => chosen.push((buy.clone(), qty)),
+                    Err(_) => {
+                        error.set(Some(format!("'{}' is not a quantity", text)));
+                        return;
+                    }
+                },
+            }
+        }
+        error.set(None);
+        action.dispatch(AssignLots {
+            sell: sell.clone(),
+            lots: chosen,
+        });
+    };
+
+    view! {
+        <p class="text-gray-600 mb-4">
+            {format!(
+                "Sell of {} {} at {} on {}. Enter how much of each lot it closes; the quantities must add up to {}.",
+                format_qty(lots.qty),
+                lots.symbol,
+                format_price(lots.price, &lots.ccy, lots.precision),
+                lots.sold,
+                format_qty(lots.qty)
+            )}
+        </p>
+        <div class="overflow-x-auto border rounded-lg mb-4">
+            <table class="min-w-full text-sm">
+                <thead class="bg-slate-100 text-gray-700">
+                    <tr>
+                        <th class="text-left font-medium px-4 py-2">"Bought"</th>
+                        <th class="text-right font-medium px-4 py-2">"Qty"</th>
+                        <th class="text-right font-medium px-4 py-2">"Unit cost"</th>
+                        <th class="text-right font-medium px-4 py-2">"Available"</th>
+                        <th class="text-right font-medium px-4 py-2">"Close"</th>
+                    </tr>
+                </thead>
+                <tbody class="divide-y">
+                    {lots
+                        .candidates
+                        .into_iter()
+                        .zip(quantities)
+                        .map(|(lot, (_, qty))| view! {
+                            <tr>
+                                <td class="px-4 py-2 text-gray-700">{lot.bought}</td>
+                                <td class="px-4 py-2 text-right text-gray-700">{format_qty(lot.qty)}</td>
+                                <td class="px-4 py-2 text-right text-gray-700">{format_price(lot.unit_cost, &lots.ccy, lots.precision)}</td>
+                                <td class="px-4 py-2 text-right text-gray-700">{format_qty(lot.available)}</td>
+                                <td class="px-4 py-2 text-right">
+                                    <input
+                                        type="text"
+                                        inputmode="decimal"
+                                        prop:value=qty
+                                        on:input=move |ev| qty.set(event_target_value(&ev))
+                                        class="w-28 rounded border border-slate-200 px-2 py-1 text-right text-gray-900"
+                                    />
+                                </td>
+                            </tr>
+                        })
+                        .collect_view()}
+                </tbody>
+            </table>
+        </div>
+        <div class="flex items-center gap-4">
+            <button
+                type="button"
+                on:click=submit
+                disabled=move || action.pending().get()
+                class="rounded bg-slate-700 px-3 py-1.5 text-sm font-medium text-white hover:bg-slate-800 disabled:opacity-50"
+            >
+                "Use these lots"
+            </button>
+            {move || error.get().map(|message| view! { <span class="text-sm text-red-600">{message}</span> })}
+            {move || {
+                action.value().get().map(|result| match result {
+                    Ok(()) => view! { <span class="text-sm text-green-700">"Saved."</span> }.into_any(),
+                    Err(err) => view! { <span class="text-sm text-red-600">{format!("Error: {}", err)}</span> }.into_any(),
+                })
+            }}
+        </div>
+    }
+}
+
+/// Specific identification: picks by hand the lots one sell closes.
+#[component]
+pub fn SellLotsEditor() -> impl IntoView {
+    let params = use_params_map();
+    let sell = move || params.read().get("sell").unwrap_or_default();
+    let assign = ServerAction::<AssignLots>::new();
+    let lots = Resource::new(
+        move || (sell(), assign.version().get()),
+        |(sell, _)| get_sell_lots(sell),
+    );
+
+    view! {
+        <div class="p-4 max-w-4xl mx-auto">
+            <div class="flex items-center justify-between mb-2">
+                <h1 class="text-2xl font-bold text-gray-800">"Choose lots"</h1>
+                <A href="/portfolio/lots" attr:class="text-sm text-teal-700 hover:underline">"Back to tax lots"</A>
+            </div>
+            <Suspense fallback=move || {
+                view! {
+                    <div class="text-center py-8">
+                        <div class="inline-block animate-spin rounded-full h-6 w-6 border-t-2 border-b-2 border-blue-500"></div>
+                    </div>
+                }
+            }>
+                {move || {
+                    lots.get().map(|result| match result {
+                        Ok(lots) => view! { <LotPicker lots=lots action=assign/> }.into_any(),
+                        Err(err) => view! {
+                            <div class="text-red-600 text-sm p-3 bg-red-50 rounded">
+                                {format!("Error: {}", err)}
+                            </div>
+                        }
+                        .into_any(),
+                    })
+                }}
+            </Suspense>
+        </div>
+    }
+}
@@ -1,21 +1,15 @@
 use leptos::prelude::*;
+use leptos_router::components::A;
 use surrealdb::RecordId;
 
+use crate::backend::allocation::risk_badge_class;
+use crate::backend::currency::format_price;
 use crate::backend::model::AssetWithPrice;
 
 fn record_key(id: &RecordId) -> String {
     String::try_from(id.key().clone()).unwrap_or_else(|_| id.key().to_string())
 }
 
-fn risk_class(score: u8) -> &'static str {
-    match score {
-        1 | 2 => "text-green-700 bg-green-100",
-        3 | 4 => "text-lime-700 bg-lime-100",
-        5 | 6 => "text-amber-700 bg-amber-100",
-        _ => "text-red-700 bg-red-100",
-    }
-}
-
 fn pct_change_class(pct: Option<f64>) -> &'static str {
     match pct {
         Some(value) if value > 0.0 => "text-green-700",
@@ -76,22 +70,24 @@ pub fn TopAssets(items: Vec<AssetWithPrice>) -> impl IntoView {
                     .map(|item| {
                         let category = record_key(&item.category);
                         let card_bg = category_bg_class(&category);
-                        let risk_badge = risk_class(item.risk.risk_score);
+                        let risk_badge = risk_badge_class(item.risk.risk_score);
                         let last_price = item
                             .last_price
-                            .map(|price| format!("{:.2}", price))
+                            .map(|price| format_price(price, &item.ccy, item.precision))
                             .unwrap_or_else(|| "-".to_string());
                         let pct_display = item
                             .price_change_pct
                             .map(|pct| format!("{:+.2}%", pct))
                             .unwrap_or_else(|| "-".to_string());
                         let pct_class = pct_change_class(item.price_change_pct);
+                        let symbol = item.symbol.clone();
+                        let href = format!("/asset/{}", item.symbol);
                         view! {
                             <div class=format!("rounded-xl border border-slate-200 p-4 shadow-sm {}", card_bg)>
                                 <div class="flex items-start justify-between">
                                     <div>
                                         <div class="text-xs uppercase tracking-wide text-slate-500">{category.clone()}</div>
-                                        <div class="text-lg font-semibold text-slate-900">{item.symbol.clone()}</div>
+                                        <A href=href attr:class="text-lg font-semibold text-slate-900 hover:underline">{symbol}</A>
                                     </div>
                                     <span class=format!("inline-flex items-center rounded-full px-2 py-0.5 text-xs font-semibold {}", risk_badge)>
                                         {format!("Risk {}", item.risk.risk_score)}
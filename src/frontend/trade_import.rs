@@ -0,0 +1,315 @@
+use leptos::prelude::*;
+use leptos_router::components::A;
+use surrealdb::RecordId;
+use crate::backend::model::Decimal;
+use crate::backend::portfolios::get_portfolios;
+use crate::backend::trade_import::{
+    ColumnMapping, CsvColumns, ImportPreview, ImportTrades, PreviewTradeImport, ReadTradeCsv,
+    RowOutcome, TradeField,
+};
+
+fn record_key(id: &RecordId) -> String {
+    String::try_from(id.key().clone()).unwrap_or_else(|_| id.key().to_string())
+}
+
+fn outcome_badge(outcome: RowOutcome) -> &'static str {
+    match outcome {
+        RowOutcome::New => "text-emerald-700 bg-emerald-100",
+        RowOutcome::Duplicate => "text-slate-700 bg-slate-200",
+        RowOutcome::Invalid => "text-red-700 bg-red-100",
+    }
+}
+
+fn optional_number(value: Option<Decimal>) -> String {
+    value
+        .map(|v| v.normalize().to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+// Loads the chosen file's text into `csv`; reading files is browser-only.
+pub(crate) fn load_file(ev: leptos::ev::Event, csv: RwSignal<String>) {
+    #[cfg(feature = "hydrate")]
+    {
+        let input: web_sys::HtmlInputElement = event_target(&ev);
+        if let Some(file) = input.files().and_then(|files| files.get(0)) {
+            leptos::task::spawn_local(async move {
+                if let Ok(text) = wasm_bindgen_futures::JsFuture::from(file.text()).await {
+                    csv.set(text.as_string().unwrap_or_default());
+                }
+            });
+        }
+    }
+    #[cfg(not(feature = "hydrate"))]
+    let _ = (ev, csv);
+}
+
+#[component]
+fn MappingTable(columns: CsvColumns, mapping: RwSignal<ColumnMapping>) -> impl IntoView {
+    let headers = columns.headers.clone();
+
+    view! {
+        <div class="grid gap-3 sm:grid-cols-3 mb-4">
+            {TradeField::ALL
+                .into_iter()
+                .map(|field| {
+                    let headers = headers.clone();
+                    let label = if field.is_required() {
+                        field.label().to_string()
+                    } else {
+                        format!("{} (optional)", field.label())
+                    };
+                    view! {
+                        <label class="flex flex-col text-sm text-gray-600">
+                            {label}
+                            <select
+                                class="mt-1 rounded border border-slate-200 px-2 py-1.5 text-gray-900"
+                                on:change=move |ev| {
+                                    let column = event_target_value(&ev).parse::<usize>().ok();
+                                    mapping.update(|m| m.set(field, column));
+                                }
+                            >
+                                <option value="" selected=move || mapping.get().get(field).is_none()>"Not in file"</option>
+                                {headers
+                                    .into_iter()
+                                    .enumerate()
+                                    .map(|(index, header)| {
+                                        view! {
+                                            <option value=index.to_string() selected=move || mapping.get().get(field) == Some(index)>
+                                                {format!("{}: {}", index + 1, header)}
+                                            </option>
+                                        }
+                                    })
+                                    .collect_view()}
+                            </select>
+                        </label>
+                    }
+                })
+                .collect_view()}
+        </div>
+        <div class="overflow-x-auto border rounded-lg mb-4">
+            <table class="min-w-full text-xs">
+                <thead class="bg-slate-100 text-gray-700">
+                    <tr>
+                        {columns.headers.into_iter().map(|h| view! { <th class="text-left font-medium px-3 py-1">{h}</th> }).collect_view()}
+                    </tr>
+                </thead>
+                <tbody class="divide-y">
+                    {columns
+                        .sample
+                        .into_iter()
+                        .map(|row| view! {
+                            <tr>{row.into_iter().map(|cell| view! { <td class="px-3 py-1 text-gray-700">{cell}</td> }).collect_view()}</tr>
+                        })
+                        .collect_view()}
+                </tbody>
+            </table>
+            <div class="px-3 py-1 text-xs text-gray-500 border-t">{format!("{} data line(s) in the file", columns.rows)}</div>
+        </div>
+    }
+}
+
+#[component]
+fn PreviewTable(preview: ImportPreview) -> impl IntoView {
+    view! {
+        <p class="text-sm text-gray-700 mb-2">
+            {format!(
+                "{} new, {} already imported, {} with errors",
+                preview.new, preview.duplicates, preview.invalid
+            )}
+        </p>
+        <div class="overflow-x-auto border rounded-lg mb-4">
+            <table class="min-w-full text-sm">
+                <thead class="bg-slate-100 text-gray-700">
+                    <tr>
+                        <th class="text-left font-medium px-3 py-2">"Line"</th>
+                        <th class="text-left font-medium px-3 py-2">"Result"</th>
+                        <th class="text-left font-medium px-3 py-2">"Symbol"</th>
+                        <th class="text-left font-medium px-3 py-2">"Side"</th>
+                        <th class="text-right font-medium px-3 py-2">"Qty"</th>
+                        <th class="text-right font-medium px-3 py-2">"Price"</th>
+                        <th class="text-right font-medium px-3 py-2">"Fees"</th>
+                        <th class="text-left font-medium px-3 py-2">"Date"</th>
+                        <th class="text-left font-medium px-3 py-2">"Errors"</th>
+                    </tr>
+                </thead>
+                <tbody class="divide-y">
+                    {preview
+                        .rows
+                        .into_iter()
+                        .map(|row| view! {
+                            <tr class="hover:bg-gray-50">
+                                <td class="px-3 py-2 text-gray-500">{row.line}</td>
+                                <td class="px-3 py-2">
+                                    <span class=format!("inline-flex items-center rounded px-2 py-0.5 text-xs font-semibold {}", outcome_badge(row.outcome))>
+                                        {row.outcome.as_str()}
+                                    </span>
+                                </td>
+                                <td class="px-3 py-2 font-medium text-gray-900">{row.symbol}</td>
+                                <td class="px-3 py-2 text-gray-700">{row.side.map(|s| s.as_str()).unwrap_or("-")}</td>
+                                <td class="px-3 py-2 text-right text-gray-700">{optional_number(row.qty)}</td>
+                                <td class="px-3 py-2 text-right text-gray-700">{optional_number(row.price)}</td>
+                                <td class="px-3 py-2 text-right text-gray-700">{optional_number(row.fees)}</td>
+                                <td class="px-3 py-2 text-gray-700">{row.date}</td>
+                                <td class="px-3 py-2 text-red-700">{row.errors.join("; ")}</td>
+                            </tr>
+                        })
+                        .collect_view()}
+                </tbody>
+            </table>
+        </div>
+    }
+}
+
+/// Broker CSV import: load a file, map its columns, preview, then import.
+#[component]
+pub fn TradeImport() -> impl IntoView {
+    let csv = RwSignal::new(String::new());
+    let portfolio = RwSignal::new(String::new());
+    let mapping = RwSignal::new(ColumnMapping::default());
+    let read = ServerAction::<ReadTradeCsv>::new();
+    let preview = ServerAction::<PreviewTradeImport>::new();
+    let import = ServerAction::<ImportTrades>::new();
+    let portfolios = Resource::new(|| (), |_| get_portfolios());
+
+    // Start from the guessed mapping whenever a file's columns are read.
+    Effect::new(move |_| {
+        if let Some(Ok(columns)) = read.value().get() {
+            mapping.set(columns.mapping);
+        }
+    });
+    // Default to the portfolio on screen, else the first one.
+    Effect::new(move |_| {
+        if let Some(Ok(choice)) = portfolios.get() {
+            if portfolio.get_untracked().is_empty() {
+                let id = choice.selected.or_else(|| choice.portfolios.first().and_then(|p| p.id.clone()));
+                portfolio.set(id.as_ref().map(record_key).unwrap_or_default());
+            }
+        }
+    });
+
+    let run_preview = move |_| {
+        preview.dispatch(PreviewTradeImport {
+            csv: csv.get_untracked(),
+            portfolio: portfolio.get_untracked(),
+            mapping: mapping.get_untracked(),
+        });
+    };
+    let run_import = move |_| {
+        import.dispatch(ImportTrades {
+            csv: csv.get_untracked(),
+            portfolio: portfolio.get_untracked(),
+            mapping: mapping.get_untracked(),
+        });
+    };
+    let can_import = move || {
+        matches!(preview.value().get(), Some(Ok(p)) if p.invalid == 0 && p.new > 0) && !import.pending().get()
+    };
+
+    view! {
+        <div class="p-4 max-w-5xl mx-auto">
+            <div class="flex items-center justify-between mb-2">
+                <h1 class="text-2xl font-bold text-gray-800">"Import trades"</h1>
+                <A href="/portfolio" attr:class="text-sm text-teal-700 hover:underline">"Back to portfolio"</A>
+            </div>
+            <p class="text-gray-600 mb-6">
+                "Load a broker export, tell us which column is which, check the preview, then import. Nothing is saved before the last step."
+            </p>
+
+            <h2 class="text-lg font-semibold text-gray-800 mb-2">"1. File"</h2>
+            <div class="mb-6 p-4 border rounded-lg flex flex-col gap-3">
+                <input type="file" accept=".csv,text/csv,text/plain" class="text-sm" on:change=move |ev| load_file(ev, csv)/>
+                <textarea
+                    rows="6"
+                    placeholder="...or paste the CSV here"
+                    class="rounded border border-slate-200 px-2 py-1.5 font-mono text-xs text-gray-900"
+                    prop:value=move || csv.get()
+                    on:input=move |ev| csv.set(event_target_value(&ev))
+                ></textarea>
+                <label class="flex items-center gap-2 text-sm text-gray-600">
+                    "Portfolio"
+                    <select
+                        class="rounded border border-slate-200 px-2 py-1.5 text-gray-900"
+                        on:change=move |ev| portfolio.set(event_target_value(&ev))
+                    >
+                        <Suspense fallback=|| view! { <option value="">"Loading..."</option> }>
+                            {move || {
+                                portfolios.get().and_then(Result::ok).map(|choice| {
+                                    choice
+                                        .portfolios
+                                        .into_iter()
+                                        .filter_map(|p| {
+                                            let key = record_key(p.id.as_ref()?);
+                                            let value = key.clone();
+                                            Some(view! { <option value=value selected=move || portfolio.get() == key>{p.name}</option> })
+                                        })
+                                        .collect_view()
+                                })
+                            }}
+                        </Suspense>
+                    </select>
+                </label>
+                <div>
+                    <button
+                        class="rounded bg-slate-700 px-4 py-1.5 text-sm font-medium text-white hover:bg-slate-800"
+                        disabled=move || csv.get().trim().is_empty() || read.pending().get()
+                        on:click=move |_| { read.dispatch(ReadTradeCsv { csv: csv.get_untracked() }); }
+                    >
+                        "Read columns"
+                    </button>
+                </div>
+            </div>
+
+            {move || match read.value().get() {
+                None => ().into_any(),
+                Some(Err(err)) => view! {
+                    <div class="mb-6 text-red-600 text-sm p-3 bg-red-50 rounded">{format!("Error: {}", err)}</div>
+                }
+                .into_any(),
+                Some(Ok(columns)) => view! {
+                    <h2 class="text-lg font-semibold text-gray-800 mb-2">"2. Columns"</h2>
+                    <MappingTable columns=columns mapping=mapping/>
+                    <button
+                        class="mb-6 rounded bg-slate-700 px-4 py-1.5 text-sm font-medium text-white hover:bg-slate-800"
+                        disabled=move || preview.pending().get()
+                        on:click=run_preview
+                    >
+                        "Preview"
+                    </button>
+                }
+                .into_any(),
+            }}
+
+            {move || match preview.value().get() {
+                None => ().into_any(),
+                Some(Err(err)) => view! {
+                    <div class="mb-6 text-red-600 text-sm p-3 bg-red-50 rounded">{format!("Error: {}", err)}</div>
+                }
+                .into_any(),
+                Some(Ok(result)) => view! {
+                    <h2 class="text-lg font-semibold text-gray-800 mb-2">"3. Preview"</h2>
+                    <PreviewTable preview=result/>
+                    <div class="flex items-center gap-4">
+                        <button
+                            class="rounded bg-teal-600 px-4 py-1.5 text-sm font-medium text-white hover:bg-teal-700 disabled:opacity-50"
+                            disabled=move || !can_import()
+                            on:click=run_import
+                        >
+                            "Import"
+                        </button>
+                        {move || match import.value().get() {
+                            Some(Ok(done)) => view! {
+                                <span class="text-sm text-emerald-700">
+                                    {format!("Imported {} trade(s), skipped {} duplicate(s).", done.created, done.duplicates)}
+                                </span>
+                            }
+                            .into_any(),
+                            Some(Err(err)) => view! { <span class="text-sm text-red-600">{err.to_string()}</span> }.into_any(),
+                            None => ().into_any(),
+                        }}
+                    </div>
+                }
+                .into_any(),
+            }}
+        </div>
+    }
+}
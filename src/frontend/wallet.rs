@@ -1,15 +1,124 @@
+use std::collections::HashMap;
+
 use leptos::prelude::*;
-use crate::backend::db::get_wallet;
+use leptos_router::components::A;
+use surrealdb::RecordId;
+use crate::backend::db::{
+    get_wallet, get_wallet_categories, get_wallet_total, AddWalletEntry, UpdateWalletEntry,
+    WALLET_STATUSES,
+};
+use crate::backend::currency::format_amount;
+use crate::backend::export::ExportDataset;
+use crate::backend::model::WalletCategory;
+use crate::frontend::export::ExportLinks;
+use crate::frontend::reporting::use_reporting_currency;
+
+fn record_key(id: &RecordId) -> String {
+    String::try_from(id.key().clone()).unwrap_or_else(|_| id.key().to_string())
+}
+
+fn status_badge(status: &str) -> &'static str {
+    match status.to_ascii_lowercase().as_str() {
+        "completed" => "text-emerald-700 bg-emerald-100",
+        "pending" => "text-amber-700 bg-amber-100",
+        "cancelled" => "text-red-700 bg-red-100",
+        _ => "text-slate-700 bg-slate-100",
+    }
+}
+
+fn category_options(categories: &[WalletCategory], selected: Option<&str>) -> impl IntoView {
+    let mut options = vec![view! { <option value="" selected=selected.is_none()>"Uncategorised"</option> }.into_any()];
+    for category in categories {
+        let Some(key) = category.id.as_ref().map(record_key) else {
+            continue;
+        };
+        let is_selected = selected == Some(key.as_str());
+        let label = format!("{} ({})", category.name, category.kind);
+        options.push(view! { <option value=key selected=is_selected>{label}</option> }.into_any());
+    }
+    options
+}
+
+#[component]
+fn WalletEntryForm(action: ServerAction<AddWalletEntry>, categories: Vec<WalletCategory>) -> impl IntoView {
+    view! {
+        <ActionForm action=action attr:class="mb-6 p-4 border rounded-lg grid gap-3 sm:grid-cols-4 items-end">
+            <label class="flex flex-col text-sm text-gray-600">
+                "Type"
+                <select name="kind" class="mt-1 rounded border border-slate-200 px-2 py-1.5 text-gray-900">
+                    <option value="deposit">"Deposit"</option>
+                    <option value="withdrawal">"Withdrawal"</option>
+                </select>
+            </label>
+            <label class="flex flex-col text-sm text-gray-600">
+                "Amount"
+                <input type="number" name="amount" min="0.01" step="0.01" required class="mt-1 rounded border border-slate-200 px-2 py-1.5 text-gray-900"/>
+            </label>
+            <label class="flex flex-col text-sm text-gray-600">
+                "Currency"
+                <input type="text" name="ccy" value="EUR" maxlength="3" required class="mt-1 rounded border border-slate-200 px-2 py-1.5 uppercase text-gray-900"/>
+            </label>
+            <label class="flex flex-col text-sm text-gray-600">
+                "Date"
+                <input type="datetime-local" name="tx_date" required class="mt-1 rounded border border-slate-200 px-2 py-1.5 text-gray-900"/>
+            </label>
+            <label class="flex flex-col text-sm text-gray-600">
+                "Status"
+                <select name="status" class="mt-1 rounded border border-slate-200 px-2 py-1.5 text-gray-900">
+                    {WALLET_STATUSES.iter().map(|status| view! { <option value=*status selected=*status == "completed">{*status}</option> }).collect_view()}
+                </select>
+            </label>
+            <label class="flex flex-col text-sm text-gray-600">
+                "Category"
+                <select name="category" class="mt-1 rounded border border-slate-200 px-2 py-1.5 text-gray-900">
+                    {category_options(&categories, None)}
+                </select>
+            </label>
+            <label class="flex flex-col text-sm text-gray-600 sm:col-span-2">
+                "Note"
+                <input type="text" name="note" class="mt-1 rounded border border-slate-200 px-2 py-1.5 text-gray-900"/>
+            </label>
+            <div class="sm:col-span-4 flex items-center justify-between gap-4">
+                <div class="text-sm">
+                    {move || match action.value().get() {
+                        Some(Err(err)) => view! { <span class="text-red-600">{err.to_string()}</span> }.into_any(),
+                        Some(Ok(_)) => view! { <span class="text-emerald-700">"Transaction saved."</span> }.into_any(),
+                        None => ().into_any(),
+                    }}
+                </div>
+                <button type="submit" class="rounded bg-teal-600 px-4 py-1.5 text-sm font-medium text-white hover:bg-teal-700" disabled=move || action.pending().get()>
+                    "Add transaction"
+                </button>
+            </div>
+        </ActionForm>
+    }
+}
 
 #[component]
 pub fn Wallet() -> impl IntoView {
-    let wallet_entries = Resource::new(|| (), |_| get_wallet());
-    const EUR_USD_RATE: f64 = 1.09;
+    let add_entry = ServerAction::<AddWalletEntry>::new();
+    let update_entry = ServerAction::<UpdateWalletEntry>::new();
+    let wallet_entries = Resource::new(
+        move || (add_entry.version().get(), update_entry.version().get()),
+        |_| get_wallet(),
+    );
+    let reporting = use_reporting_currency();
+    let wallet_total = Resource::new(
+        move || (add_entry.version().get(), update_entry.version().get(), reporting.version()),
+        |_| get_wallet_total(),
+    );
+    let categories = Resource::new(|| (), |_| get_wallet_categories());
 
     view! {
         <div class="p-4 max-w-3xl mx-auto">
             <h1 class="text-2xl font-bold text-gray-800 mb-2">"Wallet"</h1>
-            <p class="text-gray-600 mb-6">"Transactions"</p>
+            <div class="flex items-center justify-between mb-6">
+                <p class="text-gray-600">"Transactions"</p>
+                <div class="flex items-center gap-4">
+                    <ExportLinks dataset=ExportDataset::Wallet/>
+                    <A href="/wallet/import" attr:class="text-sm text-teal-700 hover:underline">"Import statement"</A>
+                </div>
+            </div>
 
             <Suspense fallback=move || {
                 view! {
@@ -19,60 +128,138 @@ pub fn Wallet() -> impl IntoView {
                 }
             }>
                 {move || {
+                    categories
+                        .get()
+                        .map(|result| {
+                            let categories = result.unwrap_or_default();
+                            view! { <WalletEntryForm action=add_entry categories=categories/> }
+                        })
+                }}
+                {move || {
+                    let categories = categories.get().and_then(Result::ok).unwrap_or_default();
+                    let category_names: HashMap<String, String> = categories
+                        .iter()
+                        .filter_map(|c| Some((record_key(c.id.as_ref()?), c.name.clone())))
+                        .collect();
                     wallet_entries
                         .get()
                         .map(|result| match result {
                             Ok(items) => {
-                                let total_eur: f64 = items
-                                    .iter()
-                                    .map(|item| {
-                                        let amount = item.amount as f64;
-                                        if item.ccy.eq_ignore_ascii_case("EUR") {
-                                            amount
-                                        } else if item.ccy.eq_ignore_ascii_case("USD") {
-                                            amount / EUR_USD_RATE
-                                        } else {
-                                            amount
-                                        }
-                                    })
-                                    .sum();
-
                                 view! {
                                     <ul class="space-y-3">
                                         {items.into_iter().map(|item| {
-                                            let amount = item.amount as f64;
-                                            let label = if amount < 0.0 { "Debit" } else { "Credit" };
-                                            let amount_abs = amount.abs();
-                                            let label_class = if amount < 0.0 { "text-red-600" } else { "text-green-600" };
+                                            let debit = item.amount.is_sign_negative();
+                                            let label = if debit { "Debit" } else { "Credit" };
+                                            let amount_abs = format_amount(item.amount.abs(), &item.ccy);
+                                            let label_class = if debit { "text-red-600" } else { "text-green-600" };
                                             let tx_date = item
                                                 .tx_date
                                                 .into_inner_ref()
                                                 .format("%Y-%m-%d %H:%M UTC")
                                                 .to_string();
+                                            let entry_key = item.id.as_ref().map(record_key).unwrap_or_default();
+                                            let category_key = item.category.as_ref().map(record_key);
+                                            let category_name = category_key
+                                                .as_ref()
+                                                .and_then(|key| category_names.get(key).cloned())
+                                                .unwrap_or_else(|| "Uncategorised".to_string());
+                                            let status_class = status_badge(&item.status);
+                                            let current_status = item.status.to_ascii_lowercase();
+                                            let category_select = category_options(&categories, category_key.as_deref());
 
                                             view! {
-                                                <li class="p-4 border rounded-lg flex items-center justify-between gap-4">
-                                                    <div class="flex flex-col">
-                                                        <span class="text-sm text-gray-500">"Date"</span>
-                                                        <span class={format!("font-medium {}", label_class)}>{label}</span>
-                                                    </div>
-                                                    <div class="text-right">
-                                                        <div class="text-sm text-gray-500">{tx_date}</div>
-                                                        <div class="text-lg font-semibold text-gray-900">
-                                                            {format!("{:.2} {}", amount_abs, item.ccy)}
+                                                <li class="p-4 border rounded-lg">
+                                                    <div class="flex items-center justify-between gap-4">
+                                                        <div class="flex flex-col">
+                                                            <span class="text-sm text-gray-500">"Date"</span>
+                                                            <span class={format!("font-medium {}", label_class)}>{label}</span>
+                                                        </div>
+                                                        <div class="flex-1 text-sm text-gray-700">
+                                                            <div>{item.note.clone()}</div>
+                                                            <div class="text-xs text-gray-500">{category_name}</div>
+                                                            {item.reference.clone().map(|reference| view! {
+                                                                <div class="text-xs text-gray-400">{format!("Ref. {}", reference)}</div>
+                                                            })}
+                                                        </div>
+                                                        <div class="text-right">
+                                                            <div class="text-sm text-gray-500">{tx_date}</div>
+                                                            <div class="text-lg font-semibold text-gray-900">
+                                                                {amount_abs}
+                                                            </div>
+                                                            <span class=format!("inline-flex items-center rounded px-2 py-0.5 text-xs font-semibold {}", status_class)>
+                                                                {item.status.clone()}
+                                                            </span>
                                                         </div>
                                                     </div>
+                                                    <details class="mt-2 text-sm">
+                                                        <summary class="cursor-pointer text-gray-500">"Edit"</summary>
+                                                        <ActionForm action=update_entry attr:class="mt-2 grid gap-2 sm:grid-cols-4 items-end">
+                                                            <input type="hidden" name="id" value=entry_key/>
+                                                            <input type="text" name="note" value=item.note class="sm:col-span-2 rounded border border-slate-200 px-2 py-1 text-gray-900"/>
+                                                            <select name="status" class="rounded border border-slate-200 px-2 py-1 text-gray-900">
+                                                                {WALLET_STATUSES.iter().map(|status| view! { <option value=*status selected=*status == current_status>{*status}</option> }).collect_view()}
+                                                            </select>
+                                                            <select name="category" class="rounded border border-slate-200 px-2 py-1 text-gray-900">
+                                                                {category_select}
+                                                            </select>
+                                                            <button type="submit" class="sm:col-span-4 justify-self-end rounded bg-slate-700 px-3 py-1 text-xs font-medium text-white hover:bg-slate-800">
+                                                                "Save"
+                                                            </button>
+                                                        </ActionForm>
+                                                    </details>
                                                 </li>
                                             }
                                         }).collect_view()}
                                     </ul>
-                                    <div class="mt-6 p-4 border rounded-lg flex items-center justify-between">
-                                        <div class="text-sm text-gray-600">
-                                            {format!("Rate used: EUR/USD {:.4}", EUR_USD_RATE)}
-                                        </div>
-                                        <div class="text-lg font-semibold text-gray-900">
-                                            {format!("Total: {:.2} EUR", total_eur)}
-                                        </div>
+                                    <div class="mt-6 p-4 border rounded-lg">
+                                        {move || {
+                                            wallet_total
+                                                .get()
+                                                .map(|result| match result {
+                                                    Ok(total) => view! {
+                                                        <div class="flex items-center justify-between">
+                                                            <div class="text-sm text-gray-600">
+                                                                {total
+                                                                    .rates
+                                                                    .iter()
+                                                                    .map(|quote| {
+                                                                        let rate_date = quote
+                                                                            .rate_date
+                                                                            .into_inner_ref()
+                                                                            .format("%Y-%m-%d")
+                                                                            .to_string();
+                                                                        view! {
+                                                                            <div>{format!("Rate used: {}/{} {:.4} ({})", quote.from, quote.to, quote.rate, rate_date)}</div>
+                                                                        }
+                                                                    })
+                                                                    .collect_view()}
+                                                            </div>
+                                                            <div class="text-right">
+                                                                <div class="text-lg font-semibold text-gray-900">
+                                                                    {format!("Total: {}", total.total)}
+                                                                </div>
+                                                                <div class="text-xs text-gray-500">
+                                                                    {format!(
+                                                                        "{} as of {}",
+                                                                        total.total.ccy,
+                                                                        total.as_of.into_inner_ref().format("%Y-%m-%d"),
+                                                                    )}
+                                                                </div>
+                                                            </div>
+                                                        </div>
+                                                        {total.unconverted_note().map(|note| view! {
+                                                            <div class="mt-2 text-sm text-amber-700">{note}</div>
+                                                        })}
+                                                    }
+                                                    .into_any(),
+                                                    Err(err) => view! {
+                                                        <div class="text-red-600 text-sm">
+                                                            {format!("Cannot compute total: {}", err)}
+                                                        </div>
+                                                    }
+                                                    .into_any(),
+                                                })
+                                        }}
                                     </div>
                                 }
                             }
@@ -7,7 +7,7 @@ async fn main() {
     use leptos::prelude::*;
     use leptos_axum::{generate_route_list, LeptosRoutes};
     use resume::app::*;
-    use resume::backend::db;
+    use resume::backend::{auth, config, db, export, migrations, seed};
 
     let conf = get_configuration(None).unwrap();
     let addr = conf.leptos_options.site_addr;
@@ -15,12 +15,111 @@ async fn main() {
     // Generate the list of routes in your Leptos App
     let routes = generate_route_list(App);
 
-     if let Err(e) = db::init_db().await {
+    let app_config = match config::load() {
+        Ok(app_config) => app_config,
+        Err(e) => {
+            log!("Invalid configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Maintenance subcommands run against the database and exit without serving
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("migrate") => {
+            let result = match db::connect(&app_config.database).await {
+                Ok(conn) => migrations::migrate(&conn).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(applied) if applied.is_empty() => {
+                    log!("Schema already at version {}", migrations::latest_version());
+                }
+                Ok(_) => log!("Schema migrated to version {}", migrations::latest_version()),
+                Err(e) => {
+                    log!("Migration failed: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        Some("assign-owner") => {
+            let Some(username) = args.get(1) else {
+                log!("usage: resume assign-owner <username>");
+                std::process::exit(2);
+            };
+            let result = match db::connect(&app_config.database).await {
+                Ok(conn) => match migrations::migrate(&conn).await {
+                    Ok(_) => db::assign_owner(&conn, username).await,
+                    Err(e) => Err(e),
+                },
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(assigned) => log!(
+                    "Assigned {} trades and {} wallet entries to '{}'",
+                    assigned.trades,
+                    assigned.wallet,
+                    username
+                ),
+                Err(e) => {
+                    log!("Assigning owner failed: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        Some("seed") => {
+            let options = match parse_seed_options(&args[1..]) {
+                Ok(options) => options,
+                Err(e) => {
+                    log!("{}", e);
+                    log!("usage: resume seed [--seed N] [--days N] [--end YYYY-MM-DD] [--user NAME]");
+                    std::process::exit(2);
+                }
+            };
+            let result = match db::connect(&app_config.database).await {
+                Ok(conn) => match migrations::migrate(&conn).await {
+                    Ok(_) => seed::seed(&conn, &options).await,
+                    Err(e) => Err(e),
+                },
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(summary) => {
+                    log!(
+                        "Seeded {} categories, {} risks, {} assets, {} prices, {} trades, {} wallet entries, {} FX rates for '{}'",
+                        summary.categories,
+                        summary.risks,
+                        summary.assets,
+                        summary.prices,
+                        summary.trades,
+                        summary.wallet,
+                        summary.fx_rates,
+                        options.user
+                    );
+                    if summary.created_user {
+                        log!("Created account '{}' with password '{}'", options.user, seed::DEMO_PASSWORD);
+                    }
+                }
+                Err(e) => {
+                    log!("Seeding failed: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        _ => {}
+    }
+
+    auth::init_sessions(&app_config.session);
+    if let Err(e) = db::init_db(&app_config.database).await {
         log!("Failed to initialize database: {}", e);
         std::process::exit(1);
     }
 
     let app = Router::new()
+        .route("/export/{file}", axum::routing::get(export::export_file))
         .leptos_routes(&leptos_options, routes, {
             let leptos_options = leptos_options.clone();
             move || shell(leptos_options.clone())
@@ -37,6 +136,36 @@ async fn main() {
         .unwrap();
 }
 
+#[cfg(feature = "ssr")]
+fn parse_seed_options(args: &[String]) -> Result<resume::backend::seed::SeedOptions, String> {
+    let mut options = resume::backend::seed::SeedOptions::default();
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        let value = iter
+            .next()
+            .ok_or_else(|| format!("missing value for {}", flag))?;
+        match flag.as_str() {
+            "--seed" => {
+                options.seed = value
+                    .parse()
+                    .map_err(|_| format!("invalid --seed '{}'", value))?
+            }
+            "--days" => {
+                options.days = value
+                    .parse()
+                    .map_err(|_| format!("invalid --days '{}'", value))?
+            }
+            "--end" => {
+                options.end = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                    .map_err(|_| format!("invalid --end '{}'", value))?
+            }
+            "--user" => options.user = value.trim().to_ascii_lowercase(),
+            other => return Err(format!("unknown option {}", other)),
+        }
+    }
+    Ok(options)
+}
+
 #[cfg(not(feature = "ssr"))]
 pub fn main() {
     // no client-side main function